@@ -1,6 +1,9 @@
 // NES APU - Audio Processing Unit
 // Canais: 2 Pulse, 1 Triangle, 1 Noise, 1 DMC
 
+use crate::region::Region;
+use crate::savestate::{StateReader, StateResult, StateWriter};
+
 const LENGTH_TABLE: [u8; 32] = [
     10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14,
     12, 16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30,
@@ -22,6 +25,10 @@ const NOISE_PERIOD_TABLE: [u16; 16] = [
     4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
 ];
 
+const NOISE_PERIOD_TABLE_PAL: [u16; 16] = [
+    4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778,
+];
+
 struct Pulse {
     enabled: bool,
     duty: u8,
@@ -111,6 +118,27 @@ impl Pulse {
         }
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.enabled); w.u8(self.duty); w.u8(self.duty_pos);
+        w.bool(self.length_halt); w.u8(self.length_counter);
+        w.bool(self.constant_volume); w.u8(self.volume);
+        w.bool(self.envelope_start); w.u8(self.envelope_divider); w.u8(self.envelope_decay);
+        w.bool(self.sweep_enabled); w.u8(self.sweep_period); w.bool(self.sweep_negate);
+        w.u8(self.sweep_shift); w.bool(self.sweep_reload); w.u8(self.sweep_divider);
+        w.u16(self.timer); w.u16(self.timer_period);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.enabled = r.bool()?; self.duty = r.u8()? & 0x03; self.duty_pos = r.u8()? & 0x07;
+        self.length_halt = r.bool()?; self.length_counter = r.u8()?;
+        self.constant_volume = r.bool()?; self.volume = r.u8()?;
+        self.envelope_start = r.bool()?; self.envelope_divider = r.u8()?; self.envelope_decay = r.u8()?;
+        self.sweep_enabled = r.bool()?; self.sweep_period = r.u8()?; self.sweep_negate = r.bool()?;
+        self.sweep_shift = r.u8()?; self.sweep_reload = r.bool()?; self.sweep_divider = r.u8()?;
+        self.timer = r.u16()?; self.timer_period = r.u16()?;
+        Ok(())
+    }
+
     fn output(&self) -> u8 {
         if !self.enabled || self.length_counter == 0 || self.timer_period < 8 || self.timer_period > 0x7FF {
             return 0;
@@ -171,6 +199,19 @@ impl Triangle {
         }
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.enabled); w.bool(self.length_halt); w.u8(self.length_counter);
+        w.u8(self.linear_counter); w.u8(self.linear_reload_value); w.bool(self.linear_reload);
+        w.u16(self.timer); w.u16(self.timer_period); w.u8(self.sequence_pos);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.enabled = r.bool()?; self.length_halt = r.bool()?; self.length_counter = r.u8()?;
+        self.linear_counter = r.u8()?; self.linear_reload_value = r.u8()?; self.linear_reload = r.bool()?;
+        self.timer = r.u16()?; self.timer_period = r.u16()?; self.sequence_pos = r.u8()? & 0x1F;
+        Ok(())
+    }
+
     fn output(&self) -> u8 {
         if !self.enabled || self.length_counter == 0 || self.linear_counter == 0 || self.timer_period < 2 {
             return 0;
@@ -241,6 +282,21 @@ impl Noise {
         }
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.enabled); w.bool(self.length_halt); w.u8(self.length_counter);
+        w.bool(self.constant_volume); w.u8(self.volume);
+        w.bool(self.envelope_start); w.u8(self.envelope_divider); w.u8(self.envelope_decay);
+        w.bool(self.mode); w.u16(self.timer); w.u16(self.timer_period); w.u16(self.shift);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.enabled = r.bool()?; self.length_halt = r.bool()?; self.length_counter = r.u8()?;
+        self.constant_volume = r.bool()?; self.volume = r.u8()?;
+        self.envelope_start = r.bool()?; self.envelope_divider = r.u8()?; self.envelope_decay = r.u8()?;
+        self.mode = r.bool()?; self.timer = r.u16()?; self.timer_period = r.u16()?; self.shift = r.u16()?;
+        Ok(())
+    }
+
     fn output(&self) -> u8 {
         if !self.enabled || self.length_counter == 0 || (self.shift & 1) != 0 {
             return 0;
@@ -253,9 +309,18 @@ const DMC_RATE_TABLE: [u16; 16] = [
     428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
 ];
 
+const DMC_RATE_TABLE_PAL: [u16; 16] = [
+    398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+];
+
+// Passos do frame counter em APU cycles (2 CPU cycles): 4 quartos + fim do modo 5-step
+const FRAME_STEPS: [u32; 5] = [3729, 7457, 11186, 14915, 18641];
+const FRAME_STEPS_PAL: [u32; 5] = [4157, 8314, 12470, 16627, 20783];
+
 struct Dmc {
     enabled: bool,
     irq_enabled: bool,
+    irq_flag: bool,
     loop_flag: bool,
     timer: u16,
     timer_period: u16,
@@ -274,7 +339,7 @@ struct Dmc {
 impl Dmc {
     fn new() -> Self {
         Dmc {
-            enabled: false, irq_enabled: false, loop_flag: false,
+            enabled: false, irq_enabled: false, irq_flag: false, loop_flag: false,
             timer: 0, timer_period: 0, output_level: 0,
             sample_addr: 0xC000, sample_length: 0, current_addr: 0xC000,
             bytes_remaining: 0, sample_buffer: 0, sample_buffer_empty: true,
@@ -311,6 +376,25 @@ impl Dmc {
         }
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.enabled); w.bool(self.irq_enabled); w.bool(self.irq_flag); w.bool(self.loop_flag);
+        w.u16(self.timer); w.u16(self.timer_period); w.u8(self.output_level);
+        w.u16(self.sample_addr); w.u16(self.sample_length);
+        w.u16(self.current_addr); w.u16(self.bytes_remaining);
+        w.u8(self.sample_buffer); w.bool(self.sample_buffer_empty);
+        w.u8(self.shift_register); w.u8(self.bits_remaining); w.bool(self.silence);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.enabled = r.bool()?; self.irq_enabled = r.bool()?; self.irq_flag = r.bool()?; self.loop_flag = r.bool()?;
+        self.timer = r.u16()?; self.timer_period = r.u16()?; self.output_level = r.u8()?;
+        self.sample_addr = r.u16()?; self.sample_length = r.u16()?;
+        self.current_addr = r.u16()?; self.bytes_remaining = r.u16()?;
+        self.sample_buffer = r.u8()?; self.sample_buffer_empty = r.bool()?;
+        self.shift_register = r.u8()?; self.bits_remaining = r.u8()?; self.silence = r.bool()?;
+        Ok(())
+    }
+
     fn output(&self) -> u8 {
         self.output_level
     }
@@ -325,6 +409,7 @@ pub struct Apu {
     frame_counter_mode: u8,
     frame_clock: u32,
     irq_inhibit: bool,
+    frame_irq: bool,
     cpu_clock: u64,
 
     // Buffer de audio
@@ -340,6 +425,9 @@ pub struct Apu {
 
     // DMC precisa ler da memória da CPU
     pub dmc_read_addr: Option<u16>,
+
+    // Tabelas de noise/DMC/frame counter e clock da CPU
+    pub region: Region,
 }
 
 impl Apu {
@@ -353,6 +441,7 @@ impl Apu {
             frame_counter_mode: 0,
             frame_clock: 0,
             irq_inhibit: false,
+            frame_irq: false,
             cpu_clock: 0,
             sample_buffer: Vec::with_capacity(1024),
             sample_rate: 44100.0,
@@ -362,6 +451,7 @@ impl Apu {
             hp2_prev_in: 0.0,
             hp2_prev_out: 0.0,
             dmc_read_addr: None,
+            region: Region::Ntsc,
         }
     }
 
@@ -447,7 +537,11 @@ impl Apu {
             },
             0x400E => {
                 self.noise.mode = (data & 0x80) != 0;
-                self.noise.timer_period = NOISE_PERIOD_TABLE[(data & 0x0F) as usize];
+                self.noise.timer_period = if self.region == Region::Pal {
+                    NOISE_PERIOD_TABLE_PAL[(data & 0x0F) as usize]
+                } else {
+                    NOISE_PERIOD_TABLE[(data & 0x0F) as usize]
+                };
             },
             0x400F => {
                 if self.noise.enabled {
@@ -459,8 +553,15 @@ impl Apu {
             // DMC
             0x4010 => {
                 self.dmc.irq_enabled = (data & 0x80) != 0;
+                if !self.dmc.irq_enabled {
+                    self.dmc.irq_flag = false;
+                }
                 self.dmc.loop_flag = (data & 0x40) != 0;
-                self.dmc.timer_period = DMC_RATE_TABLE[(data & 0x0F) as usize];
+                self.dmc.timer_period = if self.region == Region::Pal {
+                    DMC_RATE_TABLE_PAL[(data & 0x0F) as usize]
+                } else {
+                    DMC_RATE_TABLE[(data & 0x0F) as usize]
+                };
             },
             0x4011 => {
                 self.dmc.output_level = data & 0x7F;
@@ -479,6 +580,7 @@ impl Apu {
                 self.triangle.enabled = (data & 0x04) != 0;
                 self.noise.enabled = (data & 0x08) != 0;
                 self.dmc.enabled = (data & 0x10) != 0;
+                self.dmc.irq_flag = false; // qualquer escrita no $4015 dá ack no IRQ do DMC
                 if !self.pulse1.enabled { self.pulse1.length_counter = 0; }
                 if !self.pulse2.enabled { self.pulse2.length_counter = 0; }
                 if !self.triangle.enabled { self.triangle.length_counter = 0; }
@@ -495,6 +597,9 @@ impl Apu {
             0x4017 => {
                 self.frame_counter_mode = (data >> 7) & 1;
                 self.irq_inhibit = (data & 0x40) != 0;
+                if self.irq_inhibit {
+                    self.frame_irq = false;
+                }
                 if self.frame_counter_mode == 1 {
                     self.clock_quarter_frame();
                     self.clock_half_frame();
@@ -505,20 +610,35 @@ impl Apu {
         }
     }
 
-    pub fn cpu_read(&self, addr: u16) -> u8 {
+    // Ler $4015 dá ack no frame IRQ (o do DMC só sai escrevendo)
+    pub fn cpu_read(&mut self, addr: u16) -> u8 {
         if addr == 0x4015 {
-            let mut status = 0u8;
-            if self.pulse1.length_counter > 0 { status |= 0x01; }
-            if self.pulse2.length_counter > 0 { status |= 0x02; }
-            if self.triangle.length_counter > 0 { status |= 0x04; }
-            if self.noise.length_counter > 0 { status |= 0x08; }
-            if self.dmc.bytes_remaining > 0 { status |= 0x10; }
+            let status = self.peek_status();
+            self.frame_irq = false;
             status
         } else {
             0
         }
     }
 
+    // $4015 sem side effects (debug)
+    pub fn peek_status(&self) -> u8 {
+        let mut status = 0u8;
+        if self.pulse1.length_counter > 0 { status |= 0x01; }
+        if self.pulse2.length_counter > 0 { status |= 0x02; }
+        if self.triangle.length_counter > 0 { status |= 0x04; }
+        if self.noise.length_counter > 0 { status |= 0x08; }
+        if self.dmc.bytes_remaining > 0 { status |= 0x10; }
+        if self.frame_irq { status |= 0x40; }
+        if self.dmc.irq_flag { status |= 0x80; }
+        status
+    }
+
+    // Nível da linha IRQ vindo da APU (frame counter ou DMC)
+    pub fn irq_line(&self) -> bool {
+        self.frame_irq || self.dmc.irq_flag
+    }
+
     fn clock_quarter_frame(&mut self) {
         self.pulse1.clock_envelope();
         self.pulse2.clock_envelope();
@@ -535,7 +655,7 @@ impl Apu {
         self.noise.clock_length();
     }
 
-    // Chamado a cada CPU clock (~1.789MHz)
+    // Chamado a cada CPU clock (~1.789MHz NTSC, ~1.663MHz PAL)
     pub fn clock(&mut self) {
         // Triangle cloca a cada CPU cycle
         self.triangle.clock_timer();
@@ -547,34 +667,37 @@ impl Apu {
             self.noise.clock_timer();
             self.dmc.clock_timer();
 
-            // Frame counter (~240Hz, a cada 3728.5 APU cycles)
+            // Frame counter (~240Hz NTSC / ~200Hz PAL)
+            let steps = if self.region == Region::Pal { &FRAME_STEPS_PAL } else { &FRAME_STEPS };
             self.frame_clock += 1;
+            let fc = self.frame_clock;
             match self.frame_counter_mode {
                 0 => {
                     // 4-step
-                    match self.frame_clock {
-                        3729 => self.clock_quarter_frame(),
-                        7457 => { self.clock_quarter_frame(); self.clock_half_frame(); },
-                        11186 => self.clock_quarter_frame(),
-                        14915 => {
-                            self.clock_quarter_frame();
-                            self.clock_half_frame();
-                            self.frame_clock = 0;
-                        },
-                        _ => {}
+                    if fc == steps[0] || fc == steps[2] {
+                        self.clock_quarter_frame();
+                    } else if fc == steps[1] {
+                        self.clock_quarter_frame();
+                        self.clock_half_frame();
+                    } else if fc == steps[3] {
+                        self.clock_quarter_frame();
+                        self.clock_half_frame();
+                        self.frame_clock = 0;
+                        // Só o modo 4-step gera IRQ
+                        if !self.irq_inhibit {
+                            self.frame_irq = true;
+                        }
                     }
                 },
                 1 => {
                     // 5-step
-                    match self.frame_clock {
-                        3729 => self.clock_quarter_frame(),
-                        7457 => { self.clock_quarter_frame(); self.clock_half_frame(); },
-                        11186 => self.clock_quarter_frame(),
-                        14915 => { self.clock_quarter_frame(); self.clock_half_frame(); },
-                        18641 => {
-                            self.frame_clock = 0;
-                        },
-                        _ => {}
+                    if fc == steps[0] || fc == steps[2] {
+                        self.clock_quarter_frame();
+                    } else if fc == steps[1] || fc == steps[3] {
+                        self.clock_quarter_frame();
+                        self.clock_half_frame();
+                    } else if fc == steps[4] {
+                        self.frame_clock = 0;
                     }
                 },
                 _ => {}
@@ -588,7 +711,7 @@ impl Apu {
         }
 
         // Gerar sample na taxa certa
-        self.sample_clock += self.sample_rate as f64 / 1789773.0;
+        self.sample_clock += self.sample_rate as f64 / self.region.cpu_clock_hz();
         if self.sample_clock >= 1.0 {
             self.sample_clock -= 1.0;
             let raw = self.mix();
@@ -636,12 +759,60 @@ impl Apu {
         self.dmc.sample_buffer_empty = false;
         self.dmc.current_addr = self.dmc.current_addr.wrapping_add(1) | 0x8000;
         self.dmc.bytes_remaining -= 1;
-        if self.dmc.bytes_remaining == 0 && self.dmc.loop_flag {
-            self.dmc.current_addr = self.dmc.sample_addr;
-            self.dmc.bytes_remaining = self.dmc.sample_length;
+        if self.dmc.bytes_remaining == 0 {
+            if self.dmc.loop_flag {
+                self.dmc.current_addr = self.dmc.sample_addr;
+                self.dmc.bytes_remaining = self.dmc.sample_length;
+            } else if self.dmc.irq_enabled {
+                self.dmc.irq_flag = true;
+            }
         }
     }
 
+    // sample_rate e sample_buffer são do host, não entram no save state
+    pub fn save_state(&self, w: &mut StateWriter) {
+        self.pulse1.save_state(w);
+        self.pulse2.save_state(w);
+        self.triangle.save_state(w);
+        self.noise.save_state(w);
+        self.dmc.save_state(w);
+        w.u8(self.frame_counter_mode);
+        w.u32(self.frame_clock);
+        w.bool(self.irq_inhibit);
+        w.bool(self.frame_irq);
+        w.u64(self.cpu_clock);
+        w.f64(self.sample_clock);
+        w.f32(self.hp1_prev_in);
+        w.f32(self.hp1_prev_out);
+        w.f32(self.hp2_prev_in);
+        w.f32(self.hp2_prev_out);
+        w.bool(self.dmc_read_addr.is_some());
+        w.u16(self.dmc_read_addr.unwrap_or(0));
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.pulse1.load_state(r)?;
+        self.pulse2.load_state(r)?;
+        self.triangle.load_state(r)?;
+        self.noise.load_state(r)?;
+        self.dmc.load_state(r)?;
+        self.frame_counter_mode = r.u8()? & 1;
+        self.frame_clock = r.u32()?;
+        self.irq_inhibit = r.bool()?;
+        self.frame_irq = r.bool()?;
+        self.cpu_clock = r.u64()?;
+        self.sample_clock = r.f64()?;
+        self.hp1_prev_in = r.f32()?;
+        self.hp1_prev_out = r.f32()?;
+        self.hp2_prev_in = r.f32()?;
+        self.hp2_prev_out = r.f32()?;
+        let pending = r.bool()?;
+        let addr = r.u16()?;
+        self.dmc_read_addr = if pending { Some(addr) } else { None };
+        self.sample_buffer.clear();
+        Ok(())
+    }
+
     pub fn reset(&mut self) {
         self.pulse1 = Pulse::new(0);
         self.pulse2 = Pulse::new(1);
@@ -650,6 +821,7 @@ impl Apu {
         self.dmc = Dmc::new();
         self.frame_counter_mode = 0;
         self.frame_clock = 0;
+        self.frame_irq = false;
         self.cpu_clock = 0;
         self.sample_buffer.clear();
         self.sample_clock = 0.0;
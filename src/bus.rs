@@ -1,22 +1,25 @@
 use crate::ppu::Ppu;
 use crate::apu::Apu;
 use crate::cartridge::Cartridge;
+use crate::dma::Dma;
+use crate::savestate::{StateReader, StateResult, StateWriter};
+use crate::watch::{WatchSpace, WatchUnit, WATCH_READ, WATCH_WRITE};
 
 pub struct Bus {
     pub ppu: Ppu,
     pub apu: Apu,
     pub cartridge: Option<Cartridge>,
     pub ram: [u8; 2048],
-    pub dma_page: u8,
-    pub dma_addr: u8,
-    pub dma_data: u8,
-    pub dma_transfer: bool,
-    pub dma_dummy: bool,
+    pub dma: Dma,
     // Controllers
     // Bits: A B Select Start Up Down Left Right
     pub controller: [u8; 2],
     controller_state: [u8; 2],
     controller_strobe: bool,
+    // Último valor no barramento de dados da CPU (open bus)
+    pub open_bus: u8,
+    // Watchpoints do debugger (ver watch.rs)
+    pub watch: WatchUnit,
 }
 
 impl Bus {
@@ -28,29 +31,23 @@ impl Bus {
             apu: Apu::new(),
             cartridge: None,
             ram,
-            dma_page: 0x00,
-            dma_addr: 0x00,
-            dma_data: 0x00,
-            dma_transfer: false,
-            dma_dummy: true,
+            dma: Dma::new(),
             controller: [0; 2],
             controller_state: [0; 2],
             controller_strobe: false,
+            open_bus: 0,
+            watch: WatchUnit::new(),
         }
     }
 
     pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
-        self.ppu.load_chr(cartridge.get_chr_data());
-        self.ppu.mirror_mode = match cartridge.get_mirror() {
-            crate::cartridge::Mirror::Vertical => 0,
-            crate::cartridge::Mirror::Horizontal => 1,
-            crate::cartridge::Mirror::OneScreenLo => 2,
-            crate::cartridge::Mirror::OneScreenHi => 3,
-        };
+        self.ppu.mirror_mode = cartridge.get_mirror().to_u8();
         self.cartridge = Some(cartridge);
     }
 
     pub fn cpu_write(&mut self, addr: u16, data: u8) {
+        self.open_bus = data;
+        self.watch.check(WatchSpace::Cpu, WATCH_WRITE, addr, data);
         if let Some(ref mut cartridge) = self.cartridge {
             if cartridge.cpu_write(addr, data) {
                 return;
@@ -62,15 +59,16 @@ impl Bus {
                 self.ram[(addr & 0x07FF) as usize] = data;
             },
             0x2000..=0x3FFF => {
-                self.ppu.cpu_write(addr & 0x0007, data);
+                if addr & 0x0007 == 0x0007 {
+                    self.watch.check(WatchSpace::Ppu, WATCH_WRITE, self.ppu.vram_addr & 0x3FFF, data);
+                }
+                self.ppu.cpu_write(addr & 0x0007, data, &mut self.cartridge);
             },
             0x4000..=0x4013 | 0x4015 => {
                 self.apu.cpu_write(addr, data);
             },
             0x4014 => {
-                self.dma_page = data;
-                self.dma_addr = 0x00;
-                self.dma_transfer = true;
+                self.dma.request_oam(data);
             },
             0x4016 => {
                 if data & 0x01 != 0 {
@@ -92,6 +90,26 @@ impl Bus {
     }
 
     pub fn cpu_read(&mut self, addr: u16, _read_only: bool) -> u8 {
+        let data = self.read_port(addr, _read_only);
+        // $4015 é interno do 2A03, não passa pelo barramento externo
+        if addr != 0x4015 {
+            self.open_bus = data;
+        }
+        self.watch.check(WatchSpace::Cpu, WATCH_READ, addr, data);
+        data
+    }
+
+    // Leitura da DMA (OAM, DMC e o halt repetido): mexe no barramento igual,
+    // mas não é a CPU lendo, então não dispara watchpoint de leitura
+    pub fn dma_read(&mut self, addr: u16) -> u8 {
+        let data = self.read_port(addr, false);
+        if addr != 0x4015 {
+            self.open_bus = data;
+        }
+        data
+    }
+
+    fn read_port(&mut self, addr: u16, _read_only: bool) -> u8 {
         if let Some(ref cartridge) = self.cartridge {
             if let Some(data) = cartridge.cpu_read(addr) {
                 return data;
@@ -103,32 +121,49 @@ impl Bus {
                 self.ram[(addr & 0x07FF) as usize]
             },
             0x2000..=0x3FFF => {
-                self.ppu.cpu_read(addr & 0x0007, _read_only)
+                let vaddr = self.ppu.vram_addr & 0x3FFF;
+                let data = self.ppu.cpu_read(addr & 0x0007, _read_only, &mut self.cartridge);
+                if addr & 0x0007 == 0x0007 && self.watch.wants(WatchSpace::Ppu, WATCH_READ) {
+                    // Fora da paleta o byte lido da VRAM vai pro buffer, não pra CPU
+                    let value = if vaddr >= 0x3F00 { data } else { self.ppu.ppu_data_buffer };
+                    self.watch.check(WatchSpace::Ppu, WATCH_READ, vaddr, value);
+                }
+                data
             },
-            0x4000..=0x4013 | 0x4015 => self.apu.cpu_read(addr),
+            // Bit 5 do $4015 não é ligado a nada
+            0x4015 => (self.apu.cpu_read(addr) & !0x20) | (self.open_bus & 0x20),
+            // Controles só dirigem os bits de baixo, os 3 de cima são open bus
             0x4016 => {
-                if self.controller_strobe {
+                let bit = if self.controller_strobe {
                     // Durante strobe, retorna estado do botão A
                     self.controller[0] >> 7
                 } else {
                     let data = (self.controller_state[0] & 0x80) >> 7;
                     self.controller_state[0] <<= 1;
                     data
-                }
+                };
+                (self.open_bus & 0xE0) | bit
             },
             0x4017 => {
-                if self.controller_strobe {
+                let bit = if self.controller_strobe {
                     self.controller[1] >> 7
                 } else {
                     let data = (self.controller_state[1] & 0x80) >> 7;
                     self.controller_state[1] <<= 1;
                     data
-                }
+                };
+                (self.open_bus & 0xE0) | bit
             },
-            _ => 0x00,
+            // Registradores write-only da APU e o que não está mapeado
+            _ => self.open_bus,
         }
     }
 
+    // Posição na PRG ROM do que está em addr agora (None = não é ROM)
+    pub fn prg_offset(&self, addr: u16) -> Option<usize> {
+        self.cartridge.as_ref()?.prg_offset(addr)
+    }
+
     // Read sem side effects (pra debug)
     pub fn cpu_read_debug(&self, addr: u16) -> u8 {
         if let Some(ref cartridge) = self.cartridge {
@@ -139,28 +174,125 @@ impl Bus {
         match addr {
             0x0000..=0x1FFF => self.ram[(addr & 0x07FF) as usize],
             0x2000..=0x3FFF => self.ppu.cpu_read_debug(addr & 0x0007),
-            _ => 0x00,
+            0x4015 => (self.apu.peek_status() & !0x20) | (self.open_bus & 0x20),
+            0x4016 | 0x4017 => self.open_bus & 0xE0,
+            _ => self.open_bus,
+        }
+    }
+
+    // Um ciclo de CPU com a CPU parada. `get` = ciclo de leitura da APU (par)
+    pub fn clock_dma(&mut self, get: bool) {
+        let halt_addr = self.dma.halt_addr;
+        // Leitura repetida de $4016/$4017 não clocka o controle de novo
+        let skip_dummy = halt_addr == 0x4016 || halt_addr == 0x4017;
+
+        if get {
+            if self.dma.dmc_active && !self.dma.need_halt && !self.dma.need_dummy {
+                // DMC já fez halt e dummy: busca o byte
+                self.dma.process_cycle();
+                let data = self.dma_read(self.dma.dmc_addr);
+                self.apu.dmc_feed_sample(data);
+                self.dma.dmc_active = false;
+            } else if self.dma.oam_active {
+                self.dma.process_cycle();
+                let addr = (self.dma.oam_page as u16) << 8 | (self.dma.oam_count >> 1);
+                self.dma.oam_data = self.dma_read(addr);
+                self.dma.oam_count += 1;
+            } else {
+                self.dma.process_cycle();
+                if !skip_dummy {
+                    self.dma_read(halt_addr);
+                }
+            }
+        } else if self.dma.oam_active && self.dma.oam_count & 1 == 1 {
+            self.dma.process_cycle();
+            self.ppu.cpu_write(0x0004, self.dma.oam_data, &mut self.cartridge);
+            self.dma.oam_count += 1;
+            if self.dma.oam_count == 512 {
+                self.dma.oam_active = false;
+            }
+        } else {
+            // Alinhamento (ou dummy do DMC)
+            self.dma.process_cycle();
+            if !skip_dummy {
+                self.dma_read(halt_addr);
+            }
+        }
+
+        if !self.dma.oam_active && !self.dma.dmc_active {
+            self.dma.halted = false;
         }
     }
 
+    // O cartucho vai separado (Nes::save_state), aqui só o que é da placa.
+    // controller não entra: é o input ao vivo do host.
+    pub fn save_state(&self, w: &mut StateWriter) {
+        self.ppu.save_state(w);
+        self.apu.save_state(w);
+        w.bytes(&self.ram);
+        self.dma.save_state(w);
+        w.bytes(&self.controller_state);
+        w.bool(self.controller_strobe);
+        w.u8(self.open_bus);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.ppu.load_state(r)?;
+        self.apu.load_state(r)?;
+        r.bytes(&mut self.ram)?;
+        self.dma.load_state(r)?;
+        r.bytes(&mut self.controller_state)?;
+        self.controller_strobe = r.bool()?;
+        self.open_bus = r.u8()?;
+        Ok(())
+    }
+
     pub fn reset(&mut self) {
         if let Some(ref mut cartridge) = self.cartridge {
             cartridge.reset();
         }
         let mirror = self.ppu.mirror_mode;
+        let region = self.ppu.region;
         self.ppu = Ppu::new();
         self.ppu.mirror_mode = mirror;
-        if let Some(ref cartridge) = self.cartridge {
-            self.ppu.load_chr(cartridge.get_chr_data());
-        }
-        self.dma_page = 0x00;
-        self.dma_addr = 0x00;
-        self.dma_data = 0x00;
-        self.dma_transfer = false;
-        self.dma_dummy = true;
+        self.ppu.region = region;
+        self.dma = Dma::new();
         self.apu.reset();
         self.controller = [0; 2];
         self.controller_state = [0; 2];
         self.controller_strobe = false;
+        self.open_bus = 0;
+        self.watch.hits.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartridge::test_rom;
+    use crate::nes::Nes;
+    use crate::watch::Watchpoint;
+
+    // LDA #$02 / STA $4014 / LDA $0300 / JMP pra si mesmo. A DMA para a CPU na
+    // busca do LDA $0300 e fica relendo esse endereço; o LDA $00 na frente muda
+    // a paridade, pra passar também pelo ciclo de alinhamento
+    #[test]
+    fn oam_dma_does_not_hit_read_watchpoints() {
+        for prefix in [&[][..], &[0xA5, 0x00]] {
+            let lda = 0xC005 + prefix.len() as u16;
+            let mut code = prefix.to_vec();
+            code.extend([0xA9, 0x02, 0x8D, 0x14, 0x40, 0xAD, 0x00, 0x03, 0x4C]);
+            code.extend((lda + 3).to_le_bytes());
+            let rom = test_rom(1, [0; 3], &[(0xC000, &code)]);
+
+            let mut nes = Nes::new();
+            nes.load_rom(&rom).unwrap();
+            for spec in [format!("{:04X}:r:log", lda), "0200-02FF:r:log".into(), "0300:r:log".into()] {
+                nes.bus.watch.add(Watchpoint::parse(&spec).unwrap());
+            }
+            nes.run_frame();
+
+            let hits: Vec<u64> = nes.bus.watch.points.iter().map(|wp| wp.hits).collect();
+            assert_eq!(hits, vec![1, 0, 1], "prefix {:02X?}", prefix);
+        }
     }
 }
@@ -0,0 +1,179 @@
+// Pilha de chamadas pro debugger, montada olhando JSR/RTS/BRK/NMI/IRQ/RTI
+//
+// O Debugger chama update() no começo de cada instrução com o que a anterior
+// foi; comparando com o SP de agora dá pra saber se ela empilhou ou tirou um
+// frame. Um frame vale enquanto o endereço de retorno dele está na pilha
+// (SP <= frame.sp): PLA/PLA, TXS ou RTS "emprestado" que passam disso
+// quebram o par e ficam anotados em anomalies. TXS com $FF é o reset do
+// jogo arrumando a pilha: zera tudo sem anotar nada.
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::bus::Bus;
+use crate::cpu6502::Cpu6502;
+use crate::disasm;
+use crate::symbols::SymbolTable;
+
+const MAX_FRAMES: usize = 64;      // pilha do 6502 tem 256 bytes, mais que isso é lixo
+const MAX_ANOMALIES: usize = 16;   // últimas quebras de par guardadas
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameKind {
+    Jsr,
+    Brk,
+    Nmi,
+    Irq,
+}
+
+impl FrameKind {
+    // Quantos bytes de retorno ficam na pilha (PC, e P nas interrupções)
+    fn ret_bytes(self) -> u8 {
+        if self == FrameKind::Jsr { 2 } else { 3 }
+    }
+}
+
+impl fmt::Display for FrameKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FrameKind::Jsr => "JSR",
+            FrameKind::Brk => "BRK",
+            FrameKind::Nmi => "NMI",
+            FrameKind::Irq => "IRQ",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    pub kind: FrameKind,
+    pub entry: u16,     // começo da rotina/handler
+    pub caller: u16,    // PC do JSR/BRK, ou da instrução interrompida
+    pub ret: u16,       // pra onde o RTS/RTI deve voltar
+    pub sp: u8,         // SP logo depois de empilhar o retorno
+}
+
+impl Frame {
+    // "NMI  $C100 (nmi) <- $C015 (forever)"
+    pub fn describe(&self, bus: &Bus, syms: &SymbolTable) -> String {
+        format!("{}  {} <- {}", self.kind, syms.describe(bus, self.entry), syms.describe(bus, self.caller))
+    }
+}
+
+#[derive(Default)]
+pub struct CallStack {
+    pub frames: Vec<Frame>,               // frames[0] = mais externo
+    pub anomalies: VecDeque<(u16, String)>, // (PC da instrução, descrição)
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        CallStack { frames: Vec::new(), anomalies: VecDeque::with_capacity(MAX_ANOMALIES) }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.anomalies.clear();
+    }
+
+    // prev_pc/prev_opcode = instrução que acabou de rodar; interrupt = ela foi
+    // a sequência de NMI/IRQ (aí o opcode não vale)
+    pub fn update(&mut self, prev_pc: u16, prev_opcode: u8, interrupt: bool, cpu: &Cpu6502, bus: &Bus) {
+        let sp = cpu.stkp;
+        let kind = if interrupt {
+            // NMI e IRQ usam a mesma sequência, o vetor diz qual foi
+            Some(if cpu.pc == nmi_vector(bus) { FrameKind::Nmi } else { FrameKind::Irq })
+        } else {
+            match prev_opcode {
+                0x20 => Some(FrameKind::Jsr),
+                // NMI no meio do BRK leva pro vetor de NMI
+                0x00 => Some(if cpu.pc == nmi_vector(bus) { FrameKind::Nmi } else { FrameKind::Brk }),
+                _ => None,
+            }
+        };
+
+        if let Some(kind) = kind {
+            let ret = match kind {
+                FrameKind::Jsr => prev_pc.wrapping_add(3),
+                FrameKind::Brk => prev_pc.wrapping_add(2),
+                _ if interrupt => prev_pc,
+                _ => prev_pc.wrapping_add(2), // BRK sequestrado por NMI
+            };
+            if self.frames.len() == MAX_FRAMES {
+                self.frames.remove(0);
+            }
+            self.frames.push(Frame { kind, entry: cpu.pc, caller: prev_pc, ret, sp });
+            return;
+        }
+
+        // LDX #$FF / TXS: pilha nova, os frames de antes (outro reset) não valem mais
+        if prev_opcode == 0x9A && sp == 0xFF {
+            self.frames.clear();
+            return;
+        }
+
+        if matches!(prev_opcode, 0x60 | 0x40) {
+            let is_rts = prev_opcode == 0x60;
+            let name = if is_rts { "RTS" } else { "RTI" };
+            match self.frames.last().copied() {
+                Some(top) if sp > top.sp => {
+                    let paired = (top.kind == FrameKind::Jsr) == is_rts && sp == top.sp.wrapping_add(top.kind.ret_bytes());
+                    if !paired {
+                        self.note(prev_pc, format!("{} fechou um frame de {} (${:04X})", name, top.kind, top.entry));
+                    } else if cpu.pc != top.ret {
+                        self.note(prev_pc, format!("{} voltou pra ${:04X}, esperado ${:04X} (retorno mexido na pilha)", name, cpu.pc, top.ret));
+                    }
+                    self.frames.pop();
+                },
+                // Não tirou nenhum frame: empilharam um endereço e usaram RTS/RTI como jump
+                _ => self.note(prev_pc, format!("{} sem {} (trampolim pra ${:04X})", name, if is_rts { "JSR" } else { "interrupção" }, cpu.pc)),
+            }
+        }
+
+        // Qualquer coisa que subiu o SP além do retorno (PLA/PLP, TXS, RTS
+        // de outro frame) descarta o frame sem o par dele
+        while let Some(top) = self.frames.last().copied() {
+            if sp <= top.sp {
+                break;
+            }
+            self.note(prev_pc, format!("frame de {} (${:04X}) descartado por {} em ${:04X}", top.kind, top.entry, disasm::name(prev_opcode), prev_pc));
+            self.frames.pop();
+        }
+    }
+
+    // Só guarda a primeira vez de cada PC, trampolim em loop não enche a lista
+    fn note(&mut self, pc: u16, msg: String) {
+        if self.anomalies.iter().any(|(a, _)| *a == pc) {
+            return;
+        }
+        if self.anomalies.len() == MAX_ANOMALIES {
+            self.anomalies.pop_front();
+        }
+        self.anomalies.push_back((pc, msg));
+    }
+}
+
+fn nmi_vector(bus: &Bus) -> u16 {
+    u16::from_le_bytes([bus.cpu_read_debug(0xFFFA), bus.cpu_read_debug(0xFFFB)])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartridge::test_rom;
+    use crate::nes::Nes;
+
+    // C000 JSR $C010 / C010 LDX #$FF, TXS, JMP $C013: reset "pulando" pra dentro de uma rotina
+    #[test]
+    fn txs_ff_starts_a_new_stack() {
+        let rom = test_rom(1, [0; 3], &[
+            (0xC000, &[0x20, 0x10, 0xC0]),
+            (0xC010, &[0xA2, 0xFF, 0x9A, 0x4C, 0x13, 0xC0]),
+        ]);
+
+        let mut nes = Nes::new();
+        nes.load_rom(&rom).unwrap();
+        nes.run_frame();
+        assert_eq!(nes.cpu.stkp, 0xFF);
+        assert!(nes.debugger.call_stack.frames.is_empty());
+        assert!(nes.debugger.call_stack.anomalies.is_empty(), "{:?}", nes.debugger.call_stack.anomalies);
+    }
+}
@@ -1,10 +1,20 @@
 use std::fs::File;
 use std::io::Read;
 use crate::mappers::{self, CartData, Mapper};
+use crate::ppu::PpuBus;
+use crate::savestate::{crc32, StateReader, StateResult, StateWriter};
 
 pub struct Cartridge {
     pub data: CartData,
-    mapper_id: u8,
+    // Caminho do .nes (None quando carregado da memória)
+    pub rom_path: Option<String>,
+    // Bit 1 do byte 6: PRG-RAM com bateria, vai pro <rom>.sav
+    pub battery: bool,
+    sram_dirty: bool,
+    pub header: RomHeader,
+    // CRC da PRG, calculado uma vez só (o savestate confere a ROM com ele)
+    pub prg_crc: u32,
+    mapper_id: u16,
     mapper: Box<dyn Mapper>,
 }
 
@@ -16,71 +26,225 @@ pub enum Mirror {
     OneScreenHi,
 }
 
-impl Cartridge {
-    pub fn new(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut file = File::open(filename)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
+impl Mirror {
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Mirror::Vertical => 0,
+            Mirror::Horizontal => 1,
+            Mirror::OneScreenLo => 2,
+            Mirror::OneScreenHi => 3,
+        }
+    }
 
-        // Parse iNES header
-        if buffer.len() < 16 || &buffer[0..4] != b"NES\x1A" {
-            return Err("Invalid NES ROM format".into());
+    pub fn from_u8(v: u8) -> Mirror {
+        match v & 0x03 {
+            0 => Mirror::Vertical,
+            1 => Mirror::Horizontal,
+            2 => Mirror::OneScreenLo,
+            _ => Mirror::OneScreenHi,
         }
+    }
+}
 
-        let prg_banks = buffer[4];
-        let chr_banks = buffer[5];
-        let mapper1 = buffer[6];
-        let mapper2 = buffer[7];
+// Timing do byte 12 (NES 2.0). No iNES 1.0 só o bit 0 do byte 9 diz PAL.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timing {
+    Ntsc,
+    Pal,
+    Multi,
+    Dendy,
+}
 
-        // Skip trainer if present
-        let mut file_offset = 16;
-        if (mapper1 & 0x04) != 0 {
-            file_offset += 512;
+// Header iNES / NES 2.0 já decodificado. Tamanhos em bytes.
+#[derive(Debug, Clone)]
+pub struct RomHeader {
+    pub nes2: bool,
+    pub mapper: u16,
+    pub submapper: u8,
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub mirror: Mirror,
+    pub battery: bool,
+    pub trainer: bool,
+    pub timing: Option<Timing>, // None = header não diz
+}
+
+impl RomHeader {
+    pub fn parse(buffer: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        if buffer.len() < 16 || &buffer[0..4] != b"NES\x1A" {
+            return Err("Invalid NES ROM format".into());
         }
 
-        let mapper_id = (mapper2 & 0xF0) | (mapper1 >> 4);
+        let flags6 = buffer[6];
+        let flags7 = buffer[7];
+        let nes2 = (flags7 & 0x0C) == 0x08;
 
         // FIX: iNES bit0=1 -> vertical mirroring, bit0=0 -> horizontal mirroring
-        let mirror = if (mapper1 & 0x01) != 0 {
+        let mirror = if (flags6 & 0x01) != 0 {
             Mirror::Vertical
         } else {
             Mirror::Horizontal
         };
 
+        let mut header = RomHeader {
+            nes2,
+            mapper: ((flags7 & 0xF0) | (flags6 >> 4)) as u16,
+            submapper: 0,
+            prg_rom_size: buffer[4] as usize * 16384,
+            chr_rom_size: buffer[5] as usize * 8192,
+            prg_ram_size: 8192,
+            prg_nvram_size: 0,
+            chr_ram_size: if buffer[5] == 0 { 8192 } else { 0 },
+            chr_nvram_size: 0,
+            mirror,
+            battery: (flags6 & 0x02) != 0,
+            trainer: (flags6 & 0x04) != 0,
+            timing: if buffer[9] & 0x01 != 0 { Some(Timing::Pal) } else { None },
+        };
+
+        if nes2 {
+            header.mapper |= ((buffer[8] & 0x0F) as u16) << 8;
+            header.submapper = buffer[8] >> 4;
+            header.prg_rom_size = rom_size(buffer[4], buffer[9] & 0x0F, 16384);
+            header.chr_rom_size = rom_size(buffer[5], buffer[9] >> 4, 8192);
+            header.prg_ram_size = shift_size(buffer[10] & 0x0F);
+            header.prg_nvram_size = shift_size(buffer[10] >> 4);
+            header.chr_ram_size = shift_size(buffer[11] & 0x0F);
+            header.chr_nvram_size = shift_size(buffer[11] >> 4);
+            header.timing = Some(match buffer[12] & 0x03 {
+                0 => Timing::Ntsc,
+                1 => Timing::Pal,
+                2 => Timing::Multi,
+                _ => Timing::Dendy,
+            });
+        } else if buffer[12..16].iter().any(|&b| b != 0) {
+            // Header sujo ("DiskDude!" etc): nibble alto do mapper e byte 9 são lixo
+            header.mapper &= 0x0F;
+            header.timing = None;
+        }
+
+        Ok(header)
+    }
+
+    // Em bancos de 16K/8K, como os mappers esperam (satura em 255)
+    pub fn prg_banks(&self) -> u8 {
+        (self.prg_rom_size / 16384).min(255) as u8
+    }
+
+    pub fn chr_banks(&self) -> u8 {
+        (self.chr_rom_size / 8192).min(255) as u8
+    }
+}
+
+// NES 2.0: MSB nibble 0xF = notação expoente-multiplicador (2^E * (2M+1))
+fn rom_size(lsb: u8, msb: u8, unit: usize) -> usize {
+    if msb == 0x0F {
+        let exp = (lsb >> 2) as u32;
+        let mult = (lsb & 0x03) as usize * 2 + 1;
+        (1usize << exp.min(40)) * mult
+    } else {
+        (((msb as usize) << 8) | lsb as usize) * unit
+    }
+}
+
+// RAM em NES 2.0: 0 = nenhuma, senão 64 << shift
+fn shift_size(shift: u8) -> usize {
+    if shift == 0 { 0 } else { 64 << shift }
+}
+
+// Sem cartucho o barramento da PPU fica flutuando (lê 0)
+impl PpuBus for Option<Cartridge> {
+    fn chr_read(&mut self, addr: u16) -> u8 {
+        self.as_mut().and_then(|c| c.ppu_read(addr)).unwrap_or(0)
+    }
+
+    fn chr_write(&mut self, addr: u16, data: u8) {
+        if let Some(cart) = self {
+            cart.ppu_write(addr, data);
+        }
+    }
+}
+
+impl Cartridge {
+    pub fn new(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(filename)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        let mut cart = Self::from_bytes(&buffer)?;
+        cart.rom_path = Some(filename.to_string());
+        cart.load_sav();
+        Ok(cart)
+    }
+
+    // Carrega a ROM direto da memória (arquivo .nes inteiro, com header)
+    pub fn from_bytes(buffer: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let header = RomHeader::parse(buffer)?;
+
+        // Skip trainer if present
+        let mut file_offset = 16;
+        if header.trainer {
+            file_offset += 512;
+        }
+
         // Read PRG ROM
-        let prg_size = prg_banks as usize * 16384;
+        let prg_size = header.prg_rom_size;
+        let chr_size = header.chr_rom_size;
+        if buffer.len() < file_offset + prg_size + chr_size {
+            return Err("NES ROM is truncated".into());
+        }
         let prg_memory = buffer[file_offset..file_offset + prg_size].to_vec();
         file_offset += prg_size;
 
         // Read CHR ROM
-        let chr_size = chr_banks as usize * 8192;
         let chr_memory = if chr_size > 0 {
             buffer[file_offset..file_offset + chr_size].to_vec()
         } else {
-            vec![0; 8192] // CHR RAM
+            // CHR RAM - pelo menos 8K, a PPU sempre enxerga $0000-$1FFF
+            vec![0; (header.chr_ram_size + header.chr_nvram_size).max(8192)]
         };
 
+        // Os mappers indexam $6000-$7FFF direto, então nunca menos que 8K
+        let prg_ram = vec![0; (header.prg_ram_size + header.prg_nvram_size).max(8192)];
+
+        let mapper_id = header.mapper;
         let supported = matches!(mapper_id, 0 | 1 | 2 | 3 | 4 | 7 | 9 | 11 | 34 | 66 | 69 | 71 | 206 | 227);
-        println!("Cartridge loaded: PRG banks: {}, CHR banks: {}, Mapper: {}, Mirror: {:?}",
-                 prg_banks, chr_banks, mapper_id, mirror);
+        println!("Cartridge loaded: {}, PRG banks: {}, CHR banks: {}, Mapper: {}.{}, Mirror: {:?}",
+                 if header.nes2 { "NES 2.0" } else { "iNES" },
+                 header.prg_banks(), header.chr_banks(), mapper_id, header.submapper, header.mirror);
         println!("PRG ROM size: {} bytes, CHR ROM size: {} bytes", prg_size, chr_size);
+        if header.nes2 {
+            println!("PRG RAM: {} + {} NV, CHR RAM: {} + {} NV, Timing: {:?}",
+                     header.prg_ram_size, header.prg_nvram_size,
+                     header.chr_ram_size, header.chr_nvram_size, header.timing);
+        }
         if !supported {
             eprintln!("WARNING: Mapper {} not supported! Game may not work.", mapper_id);
         }
 
-        let mapper = mappers::create_mapper(mapper_id, prg_banks);
+        let mapper = mappers::create_mapper(&header);
+
+        let prg_crc = crc32(&prg_memory);
 
         Ok(Cartridge {
             data: CartData {
                 prg: prg_memory,
                 chr: chr_memory,
-                prg_ram: vec![0; 8192],
-                prg_banks,
-                chr_banks,
-                mirror,
+                prg_ram,
+                prg_banks: header.prg_banks(),
+                chr_banks: header.chr_banks(),
+                mirror: header.mirror,
             },
+            rom_path: None,
+            battery: header.battery,
+            sram_dirty: false,
+            prg_crc,
             mapper_id,
             mapper,
+            header,
         })
     }
 
@@ -88,32 +252,36 @@ impl Cartridge {
         self.mapper.cpu_read(addr, &self.data)
     }
 
+    // Posição na PRG ROM do que está mapeado em addr (pros símbolos por banco)
+    pub fn prg_offset(&self, addr: u16) -> Option<usize> {
+        self.mapper.prg_offset(addr, &self.data)
+    }
+
+    // Em $6000-$7FFF o mapper só devolve true se guardou o byte (MMC6 com a
+    // RAM protegida ignora), aí não tem por que regravar o .sav
     pub fn cpu_write(&mut self, addr: u16, data: u8) -> bool {
-        self.mapper.cpu_write(addr, data, &mut self.data)
+        let handled = self.mapper.cpu_write(addr, data, &mut self.data);
+        if handled && self.battery && (0x6000..=0x7FFF).contains(&addr) {
+            self.sram_dirty = true;
+        }
+        handled
     }
 
     pub fn ppu_read(&mut self, addr: u16) -> Option<u8> {
         self.mapper.ppu_read(addr, &self.data)
     }
 
+    // Só CHR-RAM aceita escrita; CHR-ROM ignora
     pub fn ppu_write(&mut self, addr: u16, data: u8) -> bool {
-        if addr <= 0x1FFF && self.data.chr_banks == 0 {
-            self.data.chr[addr as usize] = data;
+        let idx = addr as usize;
+        if addr <= 0x1FFF && self.data.chr_banks == 0 && idx < self.data.chr.len() {
+            self.data.chr[idx] = data;
             true
         } else {
             false
         }
     }
 
-    pub fn ppu_write_chr(&mut self, addr: u16, data: u8) {
-        if addr <= 0x1FFF && self.data.chr_banks == 0 {
-            let idx = addr as usize;
-            if idx < self.data.chr.len() {
-                self.data.chr[idx] = data;
-            }
-        }
-    }
-
     pub fn get_mirror(&self) -> Mirror {
         self.data.mirror
     }
@@ -126,7 +294,7 @@ impl Cartridge {
         self.mapper.clock_scanline();
     }
 
-    pub fn mapper_irq(&mut self) -> bool {
+    pub fn mapper_irq(&self) -> bool {
         self.mapper.mapper_irq()
     }
 
@@ -134,6 +302,64 @@ impl Cartridge {
         self.mapper.reset(self.data.prg_banks);
     }
 
+    // jogo.nes -> jogo.sav
+    pub fn sav_path(&self) -> Option<std::path::PathBuf> {
+        let rom = self.rom_path.as_ref()?;
+        Some(std::path::Path::new(rom).with_extension("sav"))
+    }
+
+    fn load_sav(&mut self) {
+        if !self.battery {
+            return;
+        }
+        let Some(path) = self.sav_path() else { return };
+        if let Ok(data) = std::fs::read(&path) {
+            let n = data.len().min(self.data.prg_ram.len());
+            self.data.prg_ram[..n].copy_from_slice(&data[..n]);
+            println!("Save carregado: {}", path.display());
+        }
+    }
+
+    pub fn sram_dirty(&self) -> bool {
+        self.sram_dirty
+    }
+
+    // Grava a PRG-RAM no .sav se mudou desde o último flush
+    pub fn flush_sav(&mut self) -> std::io::Result<()> {
+        if !self.battery || !self.sram_dirty {
+            return Ok(());
+        }
+        let Some(path) = self.sav_path() else { return Ok(()) };
+        std::fs::write(&path, &self.data.prg_ram)?;
+        self.sram_dirty = false;
+        Ok(())
+    }
+
+    pub fn mapper_id(&self) -> u16 {
+        self.mapper_id
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.vec(&self.data.prg_ram);
+        // CHR-ROM não muda, só CHR-RAM precisa ir pro save state
+        if self.data.chr_banks == 0 {
+            w.vec(&self.data.chr);
+        }
+        w.u8(self.data.mirror.to_u8());
+        self.mapper.save_state(w);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        r.vec_into(&mut self.data.prg_ram)?;
+        if self.data.chr_banks == 0 {
+            r.vec_into(&mut self.data.chr)?;
+        }
+        self.data.mirror = Mirror::from_u8(r.u8()?);
+        // A PRG-RAM do state passa a ser o save do jogo
+        self.sram_dirty = self.battery;
+        self.mapper.load_state(r)
+    }
+
     pub fn print_mapper_state(&self) {
         println!("  Mapper: {}  PRG banks: {}  CHR banks: {}", self.mapper_id, self.data.prg_banks, self.data.chr_banks);
         self.mapper.print_state();
@@ -152,3 +378,21 @@ impl Cartridge {
         }
     }
 }
+
+// ROM montada na mão pros testes. flags = bytes 6, 7 e 8 do header (mapper,
+// bateria, NES 2.0/submapper); 8K de CHR. A PRG é toda NOP, com os vetores
+// apontando pra $C000, e cada patch vai no endereço da CPU do último banco
+#[cfg(test)]
+pub fn test_rom(prg_banks: u8, flags: [u8; 3], patches: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, prg_banks, 1, flags[0], flags[1], flags[2], 0, 0, 0, 0, 0, 0, 0];
+    let mut prg = vec![0xEA; prg_banks as usize * 0x4000];
+    let last = prg.len() - 0x4000;
+    prg[last + 0x3FFA..].copy_from_slice(&[0x00, 0xC0, 0x00, 0xC0, 0x00, 0xC0]);
+    for &(addr, bytes) in patches {
+        let off = last + (addr as usize - 0xC000);
+        prg[off..off + bytes.len()].copy_from_slice(bytes);
+    }
+    rom.extend(prg);
+    rom.extend(vec![0; 0x2000]);
+    rom
+}
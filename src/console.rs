@@ -0,0 +1,360 @@
+// Console de debug pelo stdin (REPL), pra quando as F-keys não estão à mão
+// (SSH, headless)
+//
+// Uma thread só lê as linhas do stdin e manda por um channel; quem executa é
+// o poll(), chamado pelo frontend a cada volta do loop igual ao GdbStub.
+// "Parar" é o mesmo hit_breakpoint do overlay e do GDB.
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::bus::Bus;
+use crate::debug::Breakpoint;
+use crate::disasm;
+use crate::nes::Nes;
+use crate::symbols::{parse_num, SymbolTable};
+use crate::watch::{Watchpoint, WatchSpace, WATCH_EXEC, WATCH_READ, WATCH_WRITE};
+
+const HELP: &str = "\
+  b [ADDR|LABEL] [log] [if COND]   breakpoint (sem argumento lista)
+  bd N|*                           apaga breakpoint
+  w [ppu:]ADDR[-END] [rwx] [log] [if COND]   watchpoint (sem argumento lista)
+  wd N|*                           apaga watchpoint
+  s / n / so                       step, step over (JSR), step out
+  sl / nl                          próxima linha de fonte (.dbg), entrando / por cima
+  c / p                            continua / para
+  r                                registradores
+  m ADDR [LEN]                     dump de memória da CPU (LEN decimal ou $hex)
+  dis [ADDR] [N]                   disassembly
+  bt                               pilha de chamadas
+  ppu / oam / nt [0-3]             PPU (com a paleta), sprites, nametable
+  mapper                           estado do mapper
+  q                                fecha o console
+  (linha vazia repete o último comando)";
+
+pub struct Console {
+    rx: Receiver<String>,
+    queue: VecDeque<String>,
+    running: bool,      // mandou continuar/step: os próximos comandos esperam parar
+    eof: bool,          // stdin fechou
+    closed: bool,       // "q", ou EOF sem nada mais pra fazer
+    last: String,
+}
+
+impl Console {
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        println!("Console de debug: digite h pra ver os comandos");
+        prompt();
+        Console { rx, queue: VecDeque::new(), running: false, eof: false, closed: false, last: String::new() }
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    // Para na próxima instrução e avisa quando chegar lá
+    pub fn halt(&mut self, nes: &mut Nes) {
+        if !nes.debugger.hit_breakpoint {
+            nes.debugger.step_instruction();
+        }
+        self.running = true;
+    }
+
+    pub fn poll(&mut self, nes: &mut Nes) {
+        loop {
+            match self.rx.try_recv() {
+                Ok(line) => self.queue.push_back(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => { self.eof = true; break },
+            }
+        }
+        // Igual ao GDB, o comando depois de um "c"/"s" espera parar (dá pra
+        // mandar um script pelo pipe). Só o "p" fura a fila
+        if self.running && self.queue.iter().any(|l| l.trim() == "p") {
+            self.queue.retain(|l| l.trim() != "p");
+            self.halt(nes);
+        }
+
+        // Parou (breakpoint, step ou F12 na janela)
+        if self.running && nes.debugger.hit_breakpoint {
+            self.running = false;
+            println!("PARADO: {}", nes.debugger.break_reason);
+            show_pc(nes);
+            prompt();
+        }
+
+        while !self.running && !self.closed {
+            let Some(line) = self.queue.pop_front() else { break };
+            let line = match line.trim() {
+                "" => self.last.clone(),
+                l => l.to_string(),
+            };
+            if line.is_empty() {
+                prompt();
+                continue;
+            }
+            if let Err(e) = self.execute(nes, &line) {
+                println!("erro: {}", e);
+            }
+            self.last = line;
+            if !self.closed && !self.running {
+                prompt();
+            }
+        }
+        // Fim do script: depois de um "c" ninguém mais manda "p", então fecha mesmo rodando
+        if self.eof && self.queue.is_empty() {
+            self.closed = true;
+        }
+    }
+
+    pub fn execute(&mut self, nes: &mut Nes, line: &str) -> Result<(), String> {
+        let (cmd, args) = match line.split_once(char::is_whitespace) {
+            Some((c, a)) => (c, a.trim()),
+            None => (line, ""),
+        };
+        let dbg = &mut nes.debugger;
+        match cmd {
+            "h" | "help" | "?" => println!("{}", HELP),
+            "q" | "quit" => self.closed = true,
+
+            "b" if args.is_empty() => {
+                for (i, bp) in dbg.breakpoints.iter().enumerate() {
+                    let cond = bp.cond.as_ref().map(|c| format!(" if {}", c)).unwrap_or_default();
+                    println!("  {}: {}{}{}  hits {}", i, describe_breakpoint(&dbg.symbols, &nes.bus, bp),
+                        if bp.brk { "" } else { " log" }, cond, bp.hits);
+                }
+                if dbg.breakpoints.is_empty() {
+                    println!("  nenhum breakpoint");
+                }
+            },
+            "b" => {
+                let bp = Breakpoint::parse(&join_spec(args))?;
+                dbg.add_breakpoint(bp, &nes.bus)?;
+                let bp = dbg.breakpoints.last().unwrap();
+                println!("  breakpoint {} em {}", dbg.breakpoints.len() - 1, describe_breakpoint(&dbg.symbols, &nes.bus, bp));
+            },
+            "bd" => {
+                match remove_index(args, dbg.breakpoints.len())? {
+                    Some(i) => { dbg.breakpoints.remove(i); },
+                    None => dbg.breakpoints.clear(),
+                }
+            },
+            "w" if args.is_empty() => {
+                for (i, wp) in nes.bus.watch.points.iter().enumerate() {
+                    let space = if wp.space == WatchSpace::Ppu { "ppu:" } else { "" };
+                    let end = if wp.end != wp.start { format!("-{:04X}", wp.end) } else { String::new() };
+                    let cond = wp.cond.as_ref().map(|c| format!(" if {}", c)).unwrap_or_default();
+                    println!("  {}: {}{:04X}{} {}{}{}  hits {}", i, space, wp.start, end, kinds(wp.kinds),
+                        if wp.brk { "" } else { " log" }, cond, wp.hits);
+                }
+                if nes.bus.watch.points.is_empty() {
+                    println!("  nenhum watchpoint");
+                }
+            },
+            "w" => {
+                nes.bus.watch.add(Watchpoint::parse(&join_spec(args))?);
+                println!("  watchpoint {}", nes.bus.watch.points.len() - 1);
+            },
+            "wd" => {
+                match remove_index(args, nes.bus.watch.points.len())? {
+                    Some(i) => { nes.bus.watch.remove(i); },
+                    None => nes.bus.watch.clear(),
+                }
+            },
+
+            "s" | "step" => self.resume(nes, |n| n.debugger.step_instruction()),
+            "n" | "next" => self.resume(nes, |n| n.debugger.step_over(&n.cpu, &n.bus)),
+            "so" | "finish" => self.resume(nes, |n| n.debugger.step_out(&n.cpu)),
+            "sl" => self.resume(nes, |n| n.debugger.step_line(&n.cpu, &n.bus, false)),
+            "nl" => self.resume(nes, |n| n.debugger.step_line(&n.cpu, &n.bus, true)),
+            "c" | "cont" => {
+                dbg.resume();
+                self.running = true;
+            },
+            "p" | "pause" => self.halt(nes),
+
+            "r" | "regs" => {
+                let cpu = &nes.cpu;
+                let flags: String = "NV-BDIZC".chars().enumerate()
+                    .map(|(i, c)| if cpu.status & (0x80 >> i) != 0 { c } else { c.to_ascii_lowercase() })
+                    .collect();
+                println!("  PC:{:04X}  A:{:02X}  X:{:02X}  Y:{:02X}  SP:{:02X}  P:{:02X} [{}]",
+                    cpu.pc, cpu.a, cpu.x, cpu.y, cpu.stkp, cpu.status, flags);
+                println!("  SL:{}  CYC:{}  frame {}  ciclos {}",
+                    nes.bus.ppu.scanline, nes.bus.ppu.cycle, nes.bus.ppu.frame_count, cpu.total_cycles);
+                show_pc(nes);
+            },
+            "m" | "mem" => {
+                let mut it = args.split_whitespace();
+                let addr = parse_addr(nes, it.next().ok_or("uso: m ADDR [LEN]")?)?;
+                let len = match it.next() {
+                    Some(v) => parse_num(v).ok_or(format!("tamanho invalido: {}", v))?,
+                    None => 64,
+                };
+                for row in (0..len).step_by(16) {
+                    let base = addr.wrapping_add(row as u16);
+                    let bytes: Vec<String> = (0..16.min(len - row))
+                        .map(|i| format!("{:02X}", nes.bus.cpu_read_debug(base.wrapping_add(i as u16))))
+                        .collect();
+                    println!("  {:04X}: {}", base, bytes.join(" "));
+                }
+            },
+            "dis" | "l" => {
+                let mut it = args.split_whitespace();
+                let addr = match it.next() {
+                    Some(a) => parse_addr(nes, a)?,
+                    None => nes.cpu.pc,
+                };
+                let count = it.next().and_then(parse_num).unwrap_or(10) as usize;
+                let syms = &nes.debugger.symbols;
+                for d in disasm::disassemble(&nes.bus, addr, count) {
+                    if let Some(name) = syms.lookup(&nes.bus, d.addr) {
+                        println!("{}:", name);
+                    }
+                    let mark = if d.addr == nes.cpu.pc { '>' } else { ' ' };
+                    println!("{} {:04X}  {} {}", mark, d.addr, d.bytes_hex(), d.with_symbols(&nes.bus, syms));
+                }
+            },
+            "bt" => {
+                let calls = &dbg.call_stack;
+                if calls.frames.is_empty() {
+                    println!("  pilha vazia");
+                }
+                for (i, frame) in calls.frames.iter().rev().enumerate() {
+                    println!("  #{:<2} {}  ret ${:04X}", i, frame.describe(&nes.bus, &dbg.symbols), frame.ret);
+                }
+                for (pc, msg) in &calls.anomalies {
+                    println!("  [!] ${:04X}: {}", pc, msg);
+                }
+            },
+
+            "ppu" => {
+                let ppu = &nes.bus.ppu;
+                println!("  CTRL:{:02X}  MASK:{:02X}  STATUS:{:02X}  SL:{}  CYC:{}  frame {}",
+                    ppu.control, ppu.mask, ppu.status, ppu.scanline, ppu.cycle, ppu.frame_count);
+                println!("  v:{:04X}  t:{:04X}", ppu.vram_addr, ppu.tram_addr);
+                let pal = dbg.dump_palette(&nes.bus);
+                for (name, half) in [("BG", &pal[..16]), ("SP", &pal[16..])] {
+                    let groups: Vec<String> = half.chunks(4)
+                        .map(|c| c.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" "))
+                        .collect();
+                    println!("  {}: {}", name, groups.join(" | "));
+                }
+            },
+            "oam" => {
+                let sprites = dbg.dump_oam(&nes.bus);
+                let mut hidden = 0;
+                println!("   #   Y  tile attr   X");
+                for (i, &(y, tile, attr, x)) in sprites.iter().enumerate() {
+                    // Y >= $EF fica fora da tela (jeito comum de esconder sprite)
+                    if y >= 0xEF {
+                        hidden += 1;
+                        continue;
+                    }
+                    println!("  {:2}  {:3}   {:02X}   {:02X}  {:3}", i, y, tile, attr, x);
+                }
+                println!("  {} escondidos (Y >= $EF)", hidden);
+            },
+            "nt" => {
+                let nt = if args.is_empty() { 0 } else { args.parse::<usize>().ok().filter(|&n| n < 4).ok_or("uso: nt [0-3]")? };
+                // 4 nametables lógicas, 2 físicas: segue o mirroring atual
+                let (physical, _) = nes.bus.ppu.mirror_nametable(0x2000 + nt as u16 * 0x400);
+                let data = dbg.dump_nametable(&nes.bus, physical);
+                for (row, line) in data.chunks(32).enumerate() {
+                    let tiles: Vec<String> = line.iter().map(|b| format!("{:02X}", b)).collect();
+                    println!("  {:2}: {}", row, tiles.join(""));
+                }
+            },
+            "mapper" => match nes.bus.cartridge {
+                Some(ref cart) => cart.print_mapper_state(),
+                None => println!("  nenhum cartucho"),
+            },
+
+            other => return Err(format!("comando desconhecido: {} (h pra ajuda)", other)),
+        }
+        Ok(())
+    }
+
+    fn resume(&mut self, nes: &mut Nes, f: impl FnOnce(&mut Nes)) {
+        f(nes);
+        self.running = true;
+    }
+}
+
+fn prompt() {
+    print!("(rnfe) ");
+    let _ = io::stdout().flush();
+}
+
+// Instrução no PC, com label e linha de fonte se tiver
+fn show_pc(nes: &Nes) {
+    let syms = &nes.debugger.symbols;
+    let d = disasm::decode(&nes.bus, nes.cpu.pc);
+    if let Some(name) = syms.lookup(&nes.bus, d.addr) {
+        println!("{}:", name);
+    }
+    println!("> {:04X}  {} {}", d.addr, d.bytes_hex(), d.with_symbols(&nes.bus, syms));
+    if let Some(loc) = syms.source.lookup(&nes.bus, d.addr) {
+        let text = syms.source.line_text(loc.file, loc.line).unwrap_or("");
+        println!("  {}  {}", syms.source.describe(loc), text.trim());
+    }
+}
+
+// Breakpoint de label de ROM vale só pra aquele banco: o nome vem da posição
+// na PRG, não do que está mapeado agora
+fn describe_breakpoint(syms: &SymbolTable, bus: &Bus, bp: &Breakpoint) -> String {
+    match bp.prg {
+        Some(off) => format!("${:04X} ({}) PRG ${:05X}", bp.addr, syms.prg_label(off).unwrap_or("?"), off),
+        None => syms.describe(bus, bp.addr),
+    }
+}
+
+// "$0300 rw log" -> "$0300:rw:log", o " if COND" fica como está
+fn join_spec(args: &str) -> String {
+    let (head, cond) = match args.split_once(" if ") {
+        Some((h, c)) => (h, format!(" if {}", c)),
+        None => (args, String::new()),
+    };
+    format!("{}{}", head.split_whitespace().collect::<Vec<_>>().join(":"), cond)
+}
+
+// Hex (com ou sem $) ou label
+fn parse_addr(nes: &Nes, s: &str) -> Result<u16, String> {
+    if let Ok(addr) = u16::from_str_radix(s.trim_start_matches('$'), 16) {
+        return Ok(addr);
+    }
+    nes.debugger.symbols.resolve(&nes.bus, s)
+        .map(|(addr, _)| addr)
+        .ok_or(format!("endereco ou label invalido: {}", s))
+}
+
+// "N" -> Some(N), "*" -> None (todos)
+fn remove_index(args: &str, len: usize) -> Result<Option<usize>, String> {
+    if args == "*" {
+        return Ok(None);
+    }
+    if len == 0 {
+        return Err("lista vazia".to_string());
+    }
+    match args.parse::<usize>() {
+        Ok(i) if i < len => Ok(Some(i)),
+        _ => Err(format!("indice invalido: {} (0 a {})", args, len.saturating_sub(1))),
+    }
+}
+
+fn kinds(k: u8) -> String {
+    [(WATCH_READ, 'r'), (WATCH_WRITE, 'w'), (WATCH_EXEC, 'x')].iter()
+        .filter(|(bit, _)| k & bit != 0)
+        .map(|&(_, c)| c)
+        .collect()
+}
@@ -1,4 +1,6 @@
 
+use crate::savestate::{StateReader, StateResult, StateWriter};
+
 // Flags Cpu6502
 pub enum FLAGS6502{
      C = 1 << 0,   // Transportar Bit
@@ -23,20 +25,72 @@ pub struct Cpu6502 {
     addr_abs: u16,  // Endereco Absoluto
     addr_rel: u16,  // Endereço Relativo
     opcode: u8,     // Variavel Opcode
-    cycles: u8,      // Ciclo de clock
+    cycles: u8,      // Ciclos parados (sequência de reset)
+    step: u8,        // Ciclo dentro da instrução atual (0 = buscar opcode)
+    pub total_cycles: u64, // Ciclos de CPU desde o power-on
     lookup: Vec<Instruction>,
+    kinds: Vec<OpKind>,
+
+    // Interrupções: pedidos ficam pendentes até o fim da instrução
+    nmi_pending: bool,
+    irq_line: bool,       // nível da linha IRQ (APU + mapper), atualizado todo ciclo
+    take_interrupt: bool, // decidido no último ciclo da instrução anterior
+    in_interrupt: bool,   // sequência do BRK rodando pra NMI/IRQ de hardware
+
+    pub jammed: bool,     // executou um JAM, só o reset tira daqui
+}
+
+// Registradores internos salvos antes de cada ciclo (pra desfazer um ciclo parado por DMA)
+#[derive(Clone, Copy)]
+struct CpuSnapshot {
+    a: u8, x: u8, y: u8, stkp: u8, pc: u16, status: u8,
+    fetched: u8, temp: u16, addr_abs: u16, addr_rel: u16,
+    opcode: u8, step: u8, nmi_pending: bool,
+    take_interrupt: bool, in_interrupt: bool, jammed: bool,
+}
+
+// Modo de endereçamento de cada opcode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddrMode {
+    Imp, Acc, Imm, Zp0, Zpx, Zpy, Abs, Abx, Aby, Ind, Izx, Izy, Rel,
+}
+
+// O que a instrução faz com o operando na memória - define a sequência de ciclos
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpKind {
+    Read,   // lê e calcula (LDA, ADC, CMP...)
+    Write,  // só escreve (STA, SAX...)
+    Rmw,    // lê, escreve o valor antigo (dummy), escreve o novo (ASL, INC, SLO...)
+    Other,  // implied, branches, pilha, saltos
 }
 
 pub struct Instruction {
     pub name: &'static str,                           // Nome da instrução
     pub operate: fn(&mut Cpu6502, &mut crate::bus::Bus) -> u8,
-    pub addrmode: fn(&mut Cpu6502, &mut crate::bus::Bus) -> u8,
-    pub cycles: u8,                                   // Ciclos necessários para a instrução
+    pub mode: AddrMode,                               // Modo de endereçamento, pro clock() e disassembler
+    pub official: bool,                               // false = ilegal (NOPs extras, SBC $EB, SLO...)
+}
+
+impl Instruction {
+    pub fn kind(&self) -> OpKind {
+        if matches!(self.mode, AddrMode::Imp | AddrMode::Acc | AddrMode::Rel) {
+            return OpKind::Other;
+        }
+        match self.name {
+            "STA" | "STX" | "STY" | "SAX" | "SHY" | "SHX" | "SHA" | "TAS" => OpKind::Write,
+            "ASL" | "LSR" | "ROL" | "ROR" | "INC" | "DEC"
+            | "SLO" | "RLA" | "SRE" | "RRA" | "DCP" | "ISB" => OpKind::Rmw,
+            "JMP" | "JSR" => OpKind::Other,
+            _ => OpKind::Read,
+        }
+    }
 }
 //Vector<Instruction> lookup
 
 impl Cpu6502 {
     pub fn new() -> Self {
+        let lookup = Cpu6502::instrucoes();
+        let kinds = lookup.iter().map(|i| i.kind()).collect();
         Cpu6502 {
             a:0x00,                         // Accumulator
             x:0x00,                         // X Register
@@ -50,7 +104,15 @@ impl Cpu6502 {
             addr_rel:0x0000,                // O endereço absoluto da atual instrução
             opcode:0x00,                    // Byte de instrução
             cycles:0,                       // Contagem do numero de ciclo de clocks
-            lookup: Cpu6502::instrucoes(),  // Lookup table para uinstrucoes da cpu
+            step:0,                         // Ciclo dentro da instrução
+            total_cycles:0,                 // Contador global de ciclos
+            lookup,                         // Lookup table para uinstrucoes da cpu
+            kinds,
+            nmi_pending: false,
+            irq_line: false,
+            take_interrupt: false,
+            in_interrupt: false,
+            jammed: false,
         }
     }
 
@@ -68,214 +130,26 @@ impl Cpu6502 {
             self.status &= !(flag as u8);
         }
     }
+    // Toda leitura da CPU pode virar o ciclo de halt de uma DMA pendente
     pub fn read(&self, bus: &mut crate::bus::Bus, addr: u16) -> u8 {
+        // Ciclo de halt: vai ser descartado e refeito, então não conta pro watch
+        if bus.dma.try_halt(addr) {
+            return bus.dma_read(addr);
+        }
         bus.cpu_read(addr, false)
     }
 
     pub fn write(&mut self, bus: &mut crate::bus::Bus, addr: u16, data: u8) {
         bus.cpu_write(addr, data);
     }
-    //==========================
-    //# Modos de enderecamento #
-    //==========================
-
-    // IMP: Implied
-    // Nao tem muita coisa pra falar dele
-    // Ele Faz uma coisa muito simples, como, mudar o estado
-    // De Bits, de qualquer jeito, o objetivo dele é
-    // O Acumulador, para instrucoes como PHA
-    pub fn IMP(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        0
-    }
-
-    // IMM: Imediato
-    // A instrução espera até o proximo bit para ser usado
-    // Como valor, o endereco de leitura deve apontar ao
-    // Proximo valor
-    pub fn IMM(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.addr_abs = self.pc;
-        self.pc += 1;
-        0
-    }
-
-    // ZP0: Zero Paging Adress / Modo de paginamento zero
-    // Isso é usado para economizar recursos, permitindo
-    // Que você salve o local nos primeiros 0xFF bytes
-    // De um endereço de memoria, e por isso, isto só
-    // Requer um byte de memoria ao invez de 2
-    pub fn ZP0(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.addr_abs = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        self.addr_abs &= 0x00FF;
-        0
-    }
-    // ZPX: Zero Paging X
-    // O valor do X é adicionado ao valor lido
-    // No endereço de memoria, e o resultado é
-    // O endereço de memoria que o valor lido Apontava
-    // Isso é util para interagir com areas da memória
-    // Como um array em C
-    pub fn ZPX(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let base = self.read(bus, self.pc) as u8;
-        self.pc += 1;
-        self.addr_abs = base.wrapping_add(self.x) as u16;
-        0
-    }
-    // ZPY: Zero Paging Y
-    // O Mesmo do ZPX mas com Y
-    pub fn ZPY(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let base = self.read(bus, self.pc) as u8;
-        self.pc += 1;
-        self.addr_abs = base.wrapping_add(self.y) as u16;
-        0
-    }   
-    // ABS: Absolute
-    // O endereço absoluto é formado
-    // Por dois bytes, o primeiro
-    // Serve para o banco de memoória
-    // O segundo para o endereço na
-    // Memória para formar um en1dreço de 16 bits
-    pub fn ABS(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let lo: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        let hi: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-
-        self.addr_abs = (hi << 8) | lo;
-
-        0
-    }   
-    // ABX: Absolute X
-    // Endereço absoluto com valor X
-    // Isto calcula o mesmo que o
-    // ABS, mas adiciona o endereço X
-    pub fn ABX(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let lo: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        let hi: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-
-        self.addr_abs = (hi << 8) | lo;
-        self.addr_abs += self.x as u16;
-
-        if (self.addr_abs & 0xFF00) != (hi << 8) {
-            1
-        } else {
-            0
-        }
-
-    }
-    // ABY: Absolute Y
-    // O mesmo que o ABX, mas
-    // Adiciona o valor Y ao endereço
-    pub fn ABY(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let lo: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        let hi: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-
-        self.addr_abs = (hi << 8) | lo;
-        self.addr_abs += self.y  as u16;
-
-        if (self.addr_abs & 0xFF00) != (hi << 8) {
-            1
-        } else {
-            0
-        }
-
-    }
-    // IND: Indirect Adressing
-    // Normalmente é reconhecido como um
-    // Bug, mas para funcionar correctamente
-    // É preciso ser emulador também, o que ele
-    // Faz, é, apontar para o endereço 0xFF e ler
-    // O byte mais alto, ele precisa atravesar
-    // A page boudnary
-    pub fn IND(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let ptr_lo: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        let ptr_hi: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-
-        let ptr: u16 = (ptr_hi << 8) | ptr_lo;
-
-        if ptr_lo == 0x00FF {
-            self.addr_abs = ((self.read(bus, ptr & 0xFF00) as u16) << 8) | self.read(bus, ptr) as u16;
-        } else {
-            self.addr_abs = ((self.read(bus, ptr + 1) as u16) << 8) | self.read(bus, ptr) as u16;
-        }
-        0
-    }
-    // IZX: Indirect Adressing Zero Page X
-    // Ele referencia um endereço na memória pagina
-    // Zero a partir do offset X para ler o endereço
-    // De 16 bits que precisamos para a instrução
-    pub fn IZX(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let t: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        let lo: u16 = self.read(bus, (t + (self.x  as u16)) & 0x00FF) as u16;
-        let hi: u16 = self.read(bus, (t + (self.x as u16) + 1) & 0x00FF) as u16;
-
-        self.addr_abs = (hi << 8 | lo);
-
-        0
-    } 
-    // IZY: Indirect Adressing Zero Page Y
-    // Diferentemente dos outros, onde X == Y, nesse
-    // O X !== Y, neste caso ele lê um ponteiro de 16 bits
-    // Da pagina zero e usa o offset Y para formar um
-    // Endereço final.
-    pub fn IZY(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let t: u16 = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        let lo: u16 = self.read(bus, t & 0x00FF) as u16;
-        let hi: u16 = self.read(bus, (t + 1) & 0x00FF) as u16;
-
-        let base = ((hi << 8) | lo) as u16;
-        self.addr_abs = base.wrapping_add(self.y as u16);
-
-        if (self.addr_abs & 0xFF00) != (base & 0xFF00) {
-            1
-        } else {
-            0
-        }
-    }   
-    // REL: Relative.
-    // Modo de Endereçamento Relativo, usado para
-    // Instruções de branch, ele lê um byte
-    // De memoria e o interpreta como deslocamento
-    // Assinado de 8 bits
-    // As intrucoes de branch não conseguem acessar qualquer
-    // Localização da no adress range, eles so conseguem
-    // Acessar um endereço relativo ao endereço atual
-    // Num range de 127 localizações, pois é um endereço
-    // Relativo, diferente dos demais
-    pub fn REL(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.addr_rel = self.read(bus, self.pc) as u16;
-        self.pc += 1;
-        if (self.addr_rel & 0x80) != 0 {
-            self.addr_rel |= 0xFF00;
-        }
-        0
-    }
-
-    pub fn ACC(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        0
-    }
     //==========================//
     //#      Instruções        #//
     //==========================//
 
     // Fetch
-    pub fn fetch(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        let idx = self.opcode as usize;
-        let am = self.lookup[idx].addrmode as usize;
-    
-        if am == Cpu6502::ACC as usize {
-            self.fetched = self.a;
-        } else if am != Cpu6502::IMP as usize {
-            self.fetched = self.read(bus, self.addr_abs);
-        }
+    // O operando já foi lido pelo clock() no ciclo certo (ou é o A, no ACC),
+    // aqui só devolve
+    pub fn fetch(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
         self.fetched
     }
     
@@ -290,109 +164,46 @@ impl Cpu6502 {
         return 1;
     }
 
+    // Branches: retornam 1 se o branch foi tomado; os ciclos extras ficam no clock()
+
     // BCS: Branch Carry Set
     pub fn BCS(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::C) == 1 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::C) == 1) as u8
     }
     
     // BCC: Branch Carry Clear
     pub fn BCC(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::C) == 0 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::C) == 0) as u8
     }
 
     // BEQ: Branch Equal
     pub fn BEQ(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::Z) == 1 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::Z) == 1) as u8
     }
 
     // BMI: Branch Minus
     pub fn BMI(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::N) == 1 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::N) == 1) as u8
     }
 
     // BME: Branch Not Equal
     pub fn BNE(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::Z) == 0 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::Z) == 0) as u8
     }
 
     // BPL: Branch Plus
     pub fn BPL(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::N) == 0 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::N) == 0) as u8
     }
 
     // BVC: Branch Overflow
     pub fn BVC(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::V) == 0 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::V) == 0) as u8
     }
 
     // BVS: Branch Not Overflow
     pub fn BVS(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        if self.getFlag(FLAGS6502::V) == 1 {
-            self.cycles += 1;
-            self.addr_abs = self.pc.wrapping_add(self.addr_rel);
-            if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-                self.cycles += 1;
-            }
-            self.pc = self.addr_abs;
-        }
-        return 0;
+        (self.getFlag(FLAGS6502::V) == 1) as u8
     }
 
     // CLC: Clear Carry Bit
@@ -506,14 +317,14 @@ impl Cpu6502 {
     // Funcão: Coloca o valor do acumulador no stack
     pub fn PHA(&mut self, bus: &mut crate::bus::Bus) -> u8 {
         self.write(bus, 0x100 + self.stkp as u16, self.a);
-        self.stkp -= 1;
+        self.stkp = self.stkp.wrapping_sub(1);
         0
     }
 
     // PLA: Pull Accumulator
     // Funcão: Pega o valor do stack e coloca no acumulador
     pub fn PLA(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.stkp += 1;
+        self.stkp = self.stkp.wrapping_add(1);
         self.a = self.read(bus, 0x100 + self.stkp as u16);
         self.setFlag(FLAGS6502::Z, self.a == 0x00);
         self.setFlag(FLAGS6502::N, self.a & 0x80 != 0);
@@ -521,18 +332,9 @@ impl Cpu6502 {
     }
     
     // RTI: Return from Interrupt
-    pub fn RTI(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.stkp = self.stkp.wrapping_add(1);
-        self.status = self.read(bus, 0x0100 + self.stkp as u16);
-        self.status &= !(FLAGS6502::B as u8);
-        self.status |=  (FLAGS6502::U as u8);
-    
-        self.stkp = self.stkp.wrapping_add(1);
-        let lo = self.read(bus, 0x0100 + self.stkp as u16) as u16;
-        self.stkp = self.stkp.wrapping_add(1);
-        let hi = self.read(bus, 0x0100 + self.stkp as u16) as u16;
-    
-        self.pc = (hi << 8) | lo;
+    pub fn RTI(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        // Status e PC já foram puxados da pilha pelo clock(), ciclo a ciclo
+        self.pc = self.addr_abs;
         0
     }
     
@@ -546,7 +348,7 @@ impl Cpu6502 {
         self.setFlag(FLAGS6502::C, temp > 0x00FF);
         self.setFlag(FLAGS6502::Z, (temp & 0x00FF) == 0);
         self.setFlag(FLAGS6502::N, (temp & 0x0080) != 0);
-        if self.lookup[self.opcode as usize].addrmode as usize == Cpu6502::ACC as usize {
+        if self.lookup[self.opcode as usize].mode == AddrMode::Acc {
             self.a = (temp & 0x00FF) as u8;
         } else {
             self.write(bus, self.addr_abs, (temp & 0x00FF) as u8);
@@ -565,22 +367,8 @@ impl Cpu6502 {
     }
 
     // BRK: Force Interrupt
-    pub fn BRK(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.pc += 1;
-        
-        self.setFlag(FLAGS6502::I, true);
-        self.write(bus, 0x0100 + self.stkp as u16, ((self.pc >> 8) & 0x00FF) as u8);
-        self.stkp = self.stkp.wrapping_sub(1);
-        self.write(bus, 0x0100 + self.stkp as u16, (self.pc & 0x00FF) as u8);
-        self.stkp = self.stkp.wrapping_sub(1);
-        
-        self.setFlag(FLAGS6502::B, true);
-        self.write(bus, 0x0100 + self.stkp as u16, self.status);
-        self.stkp = self.stkp.wrapping_sub(1);
-        
-        let lo = self.read(bus, 0xFFFE) as u16;
-        let hi = self.read(bus, 0xFFFF) as u16;
-        self.pc = (hi << 8) | lo;
+    pub fn BRK(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        // Pushes e vetor são feitos pelo clock() (mesma sequência do NMI/IRQ)
         0
     }
 
@@ -652,14 +440,8 @@ impl Cpu6502 {
     }
 
     // JSR: Jump to Subroutine
-    pub fn JSR(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.pc = self.pc.wrapping_sub(1);
-        
-        self.write(bus, 0x0100 + self.stkp as u16, ((self.pc >> 8) & 0x00FF) as u8);
-        self.stkp = self.stkp.wrapping_sub(1);
-        self.write(bus, 0x0100 + self.stkp as u16, (self.pc & 0x00FF) as u8);
-        self.stkp = self.stkp.wrapping_sub(1);
-        
+    pub fn JSR(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        // PC de retorno já foi empilhado pelo clock()
         self.pc = self.addr_abs;
         0
     }
@@ -698,7 +480,7 @@ impl Cpu6502 {
         let temp = self.fetched >> 1;
         self.setFlag(FLAGS6502::Z, temp == 0);
         self.setFlag(FLAGS6502::N, false);
-        if self.lookup[self.opcode as usize].addrmode as usize == Cpu6502::ACC as usize {
+        if self.lookup[self.opcode as usize].mode == AddrMode::Acc {
             self.a = temp;
         } else {
             self.write(bus, self.addr_abs, temp);
@@ -707,11 +489,9 @@ impl Cpu6502 {
     }
 
     // NOP: No Operation
+    // As variantes ilegais com operando fazem a leitura normalmente (clock())
     pub fn NOP(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
-        match self.opcode {
-            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => 1,
-            _ => 0
-        }
+        0
     }
 
     // ORA: Logical Inclusive OR
@@ -746,7 +526,7 @@ impl Cpu6502 {
         self.setFlag(FLAGS6502::C, temp > 0x00FF);
         self.setFlag(FLAGS6502::Z, (temp & 0x00FF) == 0);
         self.setFlag(FLAGS6502::N, (temp & 0x0080) != 0);
-        if self.lookup[self.opcode as usize].addrmode as usize == Cpu6502::ACC as usize {
+        if self.lookup[self.opcode as usize].mode == AddrMode::Acc {
             self.a = (temp & 0x00FF) as u8;
         } else {
             self.write(bus, self.addr_abs, (temp & 0x00FF) as u8);
@@ -761,7 +541,7 @@ impl Cpu6502 {
         self.setFlag(FLAGS6502::C, (self.fetched & 0x01) != 0);
         self.setFlag(FLAGS6502::Z, (temp & 0x00FF) == 0);
         self.setFlag(FLAGS6502::N, (temp & 0x0080) != 0);
-        if self.lookup[self.opcode as usize].addrmode as usize == Cpu6502::ACC as usize {
+        if self.lookup[self.opcode as usize].mode == AddrMode::Acc {
             self.a = (temp & 0x00FF) as u8;
         } else {
             self.write(bus, self.addr_abs, (temp & 0x00FF) as u8);
@@ -770,14 +550,9 @@ impl Cpu6502 {
     }
 
     // RTS: Return from Subroutine
-    pub fn RTS(&mut self, bus: &mut crate::bus::Bus) -> u8 {
-        self.stkp = self.stkp.wrapping_add(1);
-        let lo = self.read(bus, 0x0100 + self.stkp as u16) as u16;
-        self.stkp = self.stkp.wrapping_add(1);
-        let hi = self.read(bus, 0x0100 + self.stkp as u16) as u16;
-        
-        self.pc = (hi << 8) | lo;
-        self.pc = self.pc.wrapping_add(1);
+    pub fn RTS(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        // addr_abs = endereço puxado da pilha pelo clock()
+        self.pc = self.addr_abs.wrapping_add(1);
         0
     }
 
@@ -985,30 +760,592 @@ impl Cpu6502 {
         0
     }
 
-    // XXX: Illegal Opcode
-    pub fn XXX(&mut self, _bus: &mut crate::bus::Bus) -> u8 {0}
+    // ANC: AND imediato, C = bit 7 do resultado
+    pub fn ANC(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        self.a &= self.fetched;
+        self.setFlag(FLAGS6502::Z, self.a == 0);
+        self.setFlag(FLAGS6502::N, (self.a & 0x80) != 0);
+        self.setFlag(FLAGS6502::C, (self.a & 0x80) != 0);
+        0
+    }
+
+    // ALR: AND imediato + LSR A
+    pub fn ALR(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        let t = self.a & self.fetched;
+        self.setFlag(FLAGS6502::C, (t & 0x01) != 0);
+        self.a = t >> 1;
+        self.setFlag(FLAGS6502::Z, self.a == 0);
+        self.setFlag(FLAGS6502::N, false);
+        0
+    }
+
+    // ARR: AND imediato + ROR A, mas C e V saem dos bits 6 e 5 do resultado
+    pub fn ARR(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        let t = self.a & self.fetched;
+        self.a = (t >> 1) | (self.getFlag(FLAGS6502::C) << 7);
+        self.setFlag(FLAGS6502::Z, self.a == 0);
+        self.setFlag(FLAGS6502::N, (self.a & 0x80) != 0);
+        self.setFlag(FLAGS6502::C, (self.a & 0x40) != 0);
+        self.setFlag(FLAGS6502::V, ((self.a >> 6) ^ (self.a >> 5)) & 0x01 != 0);
+        0
+    }
+
+    // AXS (SBX): X = (A & X) - imediato, sem borrow, flags como CMP
+    pub fn AXS(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        let t = self.a & self.x;
+        self.setFlag(FLAGS6502::C, t >= self.fetched);
+        self.x = t.wrapping_sub(self.fetched);
+        self.setFlag(FLAGS6502::Z, self.x == 0);
+        self.setFlag(FLAGS6502::N, (self.x & 0x80) != 0);
+        0
+    }
+
+    // XAA (ANE) e LXA: instáveis no chip de verdade, dependem de uma
+    // "constante" que varia entre CPUs. $EE é o valor mais comum
+    pub fn XAA(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        self.a = (self.a | 0xEE) & self.x & self.fetched;
+        self.setFlag(FLAGS6502::Z, self.a == 0);
+        self.setFlag(FLAGS6502::N, (self.a & 0x80) != 0);
+        0
+    }
+
+    pub fn LXA(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        self.a = (self.a | 0xEE) & self.fetched;
+        self.x = self.a;
+        self.setFlag(FLAGS6502::Z, self.a == 0);
+        self.setFlag(FLAGS6502::N, (self.a & 0x80) != 0);
+        0
+    }
+
+    // LAS: A, X e SP = memória & SP
+    pub fn LAS(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        let v = self.fetched & self.stkp;
+        self.a = v;
+        self.x = v;
+        self.stkp = v;
+        self.setFlag(FLAGS6502::Z, v == 0);
+        self.setFlag(FLAGS6502::N, (v & 0x80) != 0);
+        0
+    }
+
+    // SHY/SHX/SHA/TAS gravam valor & (high byte do endereço base + 1).
+    // Se o índice cruzou página, o high byte do endereço vira esse valor
+    fn store_high_and(&mut self, bus: &mut crate::bus::Bus, value: u8) {
+        let h = (self.temp >> 8) as u8;
+        let v = value & h.wrapping_add(1);
+        let addr = if self.temp != self.addr_abs {
+            ((v as u16) << 8) | (self.addr_abs & 0x00FF)
+        } else {
+            self.addr_abs
+        };
+        self.write(bus, addr, v);
+    }
+
+    // SHY: Store Y & (H+1)
+    pub fn SHY(&mut self, bus: &mut crate::bus::Bus) -> u8 {
+        self.store_high_and(bus, self.y);
+        0
+    }
+
+    // SHX: Store X & (H+1)
+    pub fn SHX(&mut self, bus: &mut crate::bus::Bus) -> u8 {
+        self.store_high_and(bus, self.x);
+        0
+    }
+
+    // SHA (AHX): Store A & X & (H+1)
+    pub fn SHA(&mut self, bus: &mut crate::bus::Bus) -> u8 {
+        self.store_high_and(bus, self.a & self.x);
+        0
+    }
+
+    // TAS (SHS): SP = A & X, depois grava SP & (H+1)
+    pub fn TAS(&mut self, bus: &mut crate::bus::Bus) -> u8 {
+        self.stkp = self.a & self.x;
+        self.store_high_and(bus, self.stkp);
+        0
+    }
+
+    // JAM (KIL): trava a CPU até o reset. NMI/IRQ não destravam
+    pub fn JAM(&mut self, _bus: &mut crate::bus::Bus) -> u8 {
+        self.jammed = true;
+        self.pc = self.pc.wrapping_sub(1);
+        0
+    }
 
     pub fn is_instruction_start(&self) -> bool {
-        self.cycles == 0
+        self.step == 0 && self.cycles == 0
+    }
+
+    // No começo de instrução: true = o que vem agora é a sequência de NMI/IRQ, não o opcode em pc
+    pub fn interrupt_pending(&self) -> bool {
+        self.take_interrupt
     }
 
     // Clock
+    // Um ciclo de CPU = um acesso ao bus. Cada instrução anda `step` a step,
+    // com as leituras/escritas "dummy" que o 6502 de verdade faz (alguns
+    // registradores tipo $2007 e $4015 sentem essas leituras)
     pub fn clock(&mut self, bus: &mut crate::bus::Bus) {
-        if self.cycles == 0 {
-            self.opcode = self.read(bus, self.pc);
-            self.pc = self.pc.wrapping_add(1); // só +1
-    
-            let (addrmode, operate, base_cycles) = {
-                let ins = &self.lookup[self.opcode as usize];
-                (ins.addrmode, ins.operate, ins.cycles)
-            };
-    
-            self.cycles = base_cycles;
-            let cycle0 = addrmode(self, bus);
-            let cycle1 = operate(self, bus);
-            self.cycles = self.cycles.wrapping_add((cycle0 & cycle1) as u8);
+        self.total_cycles += 1;
+        if self.jammed {
+            return;
+        }
+        if self.cycles > 0 {
+            self.cycles -= 1;
+            return;
+        }
+
+        // O 6502 decide se atende interrupção no penúltimo ciclo; aqui
+        // olhamos no começo do último, que dá no mesmo
+        let poll = self.nmi_pending || (self.irq_line && self.getFlag(FLAGS6502::I) == 0);
+
+        // Se a leitura desse ciclo parar a CPU (DMA), o ciclo é descartado
+        // e refeito do zero quando a DMA terminar
+        let saved = self.snapshot();
+        self.exec_cycle(bus, poll);
+        if bus.dma.cpu_halted() {
+            self.restore(saved);
         }
-        self.cycles = self.cycles.wrapping_sub(1);
+    }
+
+    fn exec_cycle(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        if self.step == 0 {
+            if self.take_interrupt {
+                // Sequência do BRK, mas sem incrementar o PC
+                self.read(bus, self.pc);
+                self.opcode = 0x00;
+                self.in_interrupt = true;
+            } else {
+                self.opcode = self.read(bus, self.pc);
+                self.pc = self.pc.wrapping_add(1);
+            }
+            self.take_interrupt = false;
+            self.step = 1;
+            return;
+        }
+
+        match self.opcode {
+            0x00 => self.cycle_brk(bus, poll),
+            0x20 => self.cycle_jsr(bus, poll),
+            0x40 => self.cycle_rti(bus, poll),
+            0x60 => self.cycle_rts(bus, poll),
+            0x08 | 0x48 => self.cycle_push(bus, poll),
+            0x28 | 0x68 => self.cycle_pull(bus, poll),
+            0x4C | 0x6C => self.cycle_jmp(bus, poll),
+            _ => self.cycle_generic(bus, poll),
+        }
+    }
+
+    fn snapshot(&self) -> CpuSnapshot {
+        CpuSnapshot {
+            a: self.a, x: self.x, y: self.y, stkp: self.stkp, pc: self.pc, status: self.status,
+            fetched: self.fetched, temp: self.temp, addr_abs: self.addr_abs, addr_rel: self.addr_rel,
+            opcode: self.opcode, step: self.step, nmi_pending: self.nmi_pending,
+            take_interrupt: self.take_interrupt, in_interrupt: self.in_interrupt, jammed: self.jammed,
+        }
+    }
+
+    fn restore(&mut self, s: CpuSnapshot) {
+        self.a = s.a; self.x = s.x; self.y = s.y; self.stkp = s.stkp; self.pc = s.pc; self.status = s.status;
+        self.fetched = s.fetched; self.temp = s.temp; self.addr_abs = s.addr_abs; self.addr_rel = s.addr_rel;
+        self.opcode = s.opcode; self.step = s.step; self.nmi_pending = s.nmi_pending;
+        self.take_interrupt = s.take_interrupt; self.in_interrupt = s.in_interrupt; self.jammed = s.jammed;
+    }
+
+    fn finish(&mut self, poll: bool) {
+        self.step = 0;
+        self.take_interrupt = poll;
+    }
+
+    fn run_operate(&mut self, bus: &mut crate::bus::Bus) -> u8 {
+        let operate = self.lookup[self.opcode as usize].operate;
+        operate(self, bus)
+    }
+
+    fn push(&mut self, bus: &mut crate::bus::Bus, data: u8) {
+        self.write(bus, 0x0100 + self.stkp as u16, data);
+        self.stkp = self.stkp.wrapping_sub(1);
+    }
+
+    fn pull(&mut self, bus: &mut crate::bus::Bus) -> u8 {
+        self.stkp = self.stkp.wrapping_add(1);
+        self.read(bus, 0x0100 + self.stkp as u16)
+    }
+
+    // BRK, IRQ e NMI: 7 ciclos. O vetor é escolhido só no push do status,
+    // então um NMI que chega no meio "sequestra" um BRK/IRQ
+    fn cycle_brk(&mut self, bus: &mut crate::bus::Bus, _poll: bool) {
+        match self.step {
+            1 => {
+                self.read(bus, self.pc);
+                if !self.in_interrupt {
+                    self.pc = self.pc.wrapping_add(1); // BRK pula o byte de padding
+                }
+            }
+            2 => self.push(bus, (self.pc >> 8) as u8),
+            3 => self.push(bus, self.pc as u8),
+            4 => {
+                let mut p = self.status | FLAGS6502::U as u8;
+                if self.in_interrupt {
+                    p &= !(FLAGS6502::B as u8);
+                } else {
+                    p |= FLAGS6502::B as u8;
+                }
+                self.push(bus, p);
+                self.setFlag(FLAGS6502::I, true);
+                if self.nmi_pending {
+                    self.nmi_pending = false;
+                    self.temp = 0xFFFA;
+                } else {
+                    self.temp = 0xFFFE;
+                }
+            }
+            5 => self.addr_abs = self.read(bus, self.temp) as u16,
+            _ => {
+                let hi = self.read(bus, self.temp + 1) as u16;
+                self.pc = (hi << 8) | self.addr_abs;
+                self.in_interrupt = false;
+                // Sempre roda uma instrução do handler antes de outra interrupção
+                self.finish(false);
+                return;
+            }
+        }
+        self.step += 1;
+    }
+
+    fn cycle_jsr(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        match self.step {
+            1 => {
+                self.addr_abs = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            2 => { self.read(bus, 0x0100 + self.stkp as u16); }
+            3 => self.push(bus, (self.pc >> 8) as u8),
+            4 => self.push(bus, self.pc as u8),
+            _ => {
+                let hi = self.read(bus, self.pc) as u16;
+                self.addr_abs |= hi << 8;
+                self.run_operate(bus);
+                self.finish(poll);
+                return;
+            }
+        }
+        self.step += 1;
+    }
+
+    fn cycle_rti(&mut self, bus: &mut crate::bus::Bus, _poll: bool) {
+        match self.step {
+            1 => { self.read(bus, self.pc); }
+            2 => { self.read(bus, 0x0100 + self.stkp as u16); }
+            3 => {
+                self.status = self.pull(bus);
+                self.status &= !(FLAGS6502::B as u8);
+                self.status |= FLAGS6502::U as u8;
+            }
+            4 => self.addr_abs = self.pull(bus) as u16,
+            _ => {
+                let hi = self.pull(bus) as u16;
+                self.addr_abs |= hi << 8;
+                self.run_operate(bus);
+                // RTI restaura o I antes do poll, por isso usa o status novo
+                let poll = self.nmi_pending || (self.irq_line && self.getFlag(FLAGS6502::I) == 0);
+                self.finish(poll);
+                return;
+            }
+        }
+        self.step += 1;
+    }
+
+    fn cycle_rts(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        match self.step {
+            1 => { self.read(bus, self.pc); }
+            2 => { self.read(bus, 0x0100 + self.stkp as u16); }
+            3 => self.addr_abs = self.pull(bus) as u16,
+            4 => {
+                let hi = self.pull(bus) as u16;
+                self.addr_abs |= hi << 8;
+            }
+            _ => {
+                self.read(bus, self.addr_abs);
+                self.run_operate(bus);
+                self.finish(poll);
+                return;
+            }
+        }
+        self.step += 1;
+    }
+
+    // PHA/PHP: leitura dummy do próximo byte, depois o push (no operate)
+    fn cycle_push(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        if self.step == 1 {
+            self.read(bus, self.pc);
+            self.step += 1;
+        } else {
+            self.run_operate(bus);
+            self.finish(poll);
+        }
+    }
+
+    // PLA/PLP: dummy no PC, dummy na pilha, depois o pull (no operate)
+    fn cycle_pull(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        match self.step {
+            1 => { self.read(bus, self.pc); }
+            2 => { self.read(bus, 0x0100 + self.stkp as u16); }
+            _ => {
+                self.run_operate(bus);
+                self.finish(poll);
+                return;
+            }
+        }
+        self.step += 1;
+    }
+
+    // JMP abs (3 ciclos) e JMP (ind) (5 ciclos, com o bug do fim de página)
+    fn cycle_jmp(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        let indirect = self.opcode == 0x6C;
+        match self.step {
+            1 => {
+                self.addr_abs = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            2 => {
+                let hi = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                self.addr_abs |= hi << 8;
+                if !indirect {
+                    self.run_operate(bus);
+                    self.finish(poll);
+                    return;
+                }
+                self.temp = self.addr_abs;
+            }
+            3 => self.addr_abs = self.read(bus, self.temp) as u16,
+            _ => {
+                let ptr_hi = (self.temp & 0xFF00) | (self.temp.wrapping_add(1) & 0x00FF);
+                let hi = self.read(bus, ptr_hi) as u16;
+                self.addr_abs |= hi << 8;
+                self.run_operate(bus);
+                self.finish(poll);
+                return;
+            }
+        }
+        self.step += 1;
+    }
+
+    // Quantos ciclos (depois do opcode) o modo gasta calculando o endereço
+    fn addr_steps(mode: AddrMode) -> u8 {
+        match mode {
+            AddrMode::Zp0 => 1,
+            AddrMode::Zpx | AddrMode::Zpy | AddrMode::Abs => 2,
+            AddrMode::Abx | AddrMode::Aby => 3,
+            AddrMode::Izx | AddrMode::Izy => 4,
+            _ => 0,
+        }
+    }
+
+    fn cycle_generic(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        let mode = self.lookup[self.opcode as usize].mode;
+        let kind = self.kinds[self.opcode as usize];
+
+        match mode {
+            AddrMode::Imp | AddrMode::Acc | AddrMode::Ind => {
+                self.read(bus, self.pc);
+                self.fetched = self.a;
+                self.run_operate(bus);
+                self.finish(poll);
+                return;
+            }
+            AddrMode::Imm => {
+                self.fetched = self.read(bus, self.pc);
+                self.pc = self.pc.wrapping_add(1);
+                self.run_operate(bus);
+                self.finish(poll);
+                return;
+            }
+            AddrMode::Rel => {
+                self.cycle_branch(bus, poll);
+                return;
+            }
+            _ => {}
+        }
+
+        let n = Cpu6502::addr_steps(mode);
+        if self.step <= n {
+            if self.cycle_address(bus, mode, kind) {
+                // ABX/ABY/IZY sem cruzar página: a leitura já foi a de verdade
+                self.run_operate(bus);
+                self.finish(poll);
+                return;
+            }
+            self.step += 1;
+            return;
+        }
+
+        match (kind, self.step - n) {
+            (OpKind::Write, _) => {
+                self.run_operate(bus);
+                self.finish(poll);
+            }
+            (OpKind::Rmw, 1) => {
+                self.fetched = self.read(bus, self.addr_abs);
+                self.step += 1;
+            }
+            (OpKind::Rmw, 2) => {
+                // Escreve o valor antigo de volta antes do novo
+                self.write(bus, self.addr_abs, self.fetched);
+                self.step += 1;
+            }
+            _ => {
+                if kind != OpKind::Rmw {
+                    self.fetched = self.read(bus, self.addr_abs);
+                }
+                self.run_operate(bus);
+                self.finish(poll);
+            }
+        }
+    }
+
+    // Um ciclo do cálculo de endereço. Retorna true quando a leitura do
+    // endereço "não corrigido" já serviu como leitura do operando
+    fn cycle_address(&mut self, bus: &mut crate::bus::Bus, mode: AddrMode, kind: OpKind) -> bool {
+        match (mode, self.step) {
+            (AddrMode::Zp0, _) | (AddrMode::Abs, 1) | (AddrMode::Abx, 1) | (AddrMode::Aby, 1) => {
+                self.addr_abs = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            (AddrMode::Zpx, 1) | (AddrMode::Zpy, 1) | (AddrMode::Izx, 1) | (AddrMode::Izy, 1) => {
+                self.temp = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            (AddrMode::Zpx, _) | (AddrMode::Zpy, _) => {
+                self.read(bus, self.temp);
+                let idx = if mode == AddrMode::Zpx { self.x } else { self.y };
+                self.addr_abs = (self.temp as u8).wrapping_add(idx) as u16;
+            }
+            (AddrMode::Abs, _) => {
+                let hi = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                self.addr_abs |= hi << 8;
+            }
+            (AddrMode::Abx, 2) | (AddrMode::Aby, 2) => {
+                let hi = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                let idx = if mode == AddrMode::Abx { self.x } else { self.y };
+                self.index_base((hi << 8) | self.addr_abs, idx);
+            }
+            (AddrMode::Izx, 2) => {
+                self.read(bus, self.temp);
+                self.temp = (self.temp as u8).wrapping_add(self.x) as u16;
+            }
+            (AddrMode::Izx, 3) | (AddrMode::Izy, 2) => {
+                self.addr_abs = self.read(bus, self.temp) as u16;
+            }
+            (AddrMode::Izx, _) => {
+                let hi = self.read(bus, (self.temp as u8).wrapping_add(1) as u16) as u16;
+                self.addr_abs |= hi << 8;
+            }
+            (AddrMode::Izy, 3) => {
+                let hi = self.read(bus, (self.temp as u8).wrapping_add(1) as u16) as u16;
+                self.index_base((hi << 8) | self.addr_abs, self.y);
+            }
+            _ => {
+                // ABX/ABY/IZY: lê o endereço com o high byte ainda sem o carry
+                let v = self.read(bus, self.temp);
+                if kind == OpKind::Read && self.temp == self.addr_abs {
+                    self.fetched = v;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // Soma o índice; temp fica com o endereço sem corrigir a página
+    fn index_base(&mut self, base: u16, idx: u8) {
+        self.addr_abs = base.wrapping_add(idx as u16);
+        self.temp = (base & 0xFF00) | (self.addr_abs & 0x00FF);
+    }
+
+    // Branch: 2 ciclos, +1 se tomado, +1 se cruzar página
+    fn cycle_branch(&mut self, bus: &mut crate::bus::Bus, poll: bool) {
+        match self.step {
+            1 => {
+                self.addr_rel = self.read(bus, self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                if (self.addr_rel & 0x80) != 0 {
+                    self.addr_rel |= 0xFF00;
+                }
+                if self.run_operate(bus) == 0 {
+                    self.finish(poll);
+                    return;
+                }
+                // Branch tomado sem cruzar página não faz poll de novo
+                self.take_interrupt = poll;
+            }
+            2 => {
+                self.read(bus, self.pc);
+                self.addr_abs = self.pc.wrapping_add(self.addr_rel);
+                if (self.addr_abs & 0xFF00) == (self.pc & 0xFF00) {
+                    self.pc = self.addr_abs;
+                    let poll = self.take_interrupt;
+                    self.finish(poll);
+                    return;
+                }
+            }
+            _ => {
+                self.read(bus, (self.pc & 0xFF00) | (self.addr_abs & 0x00FF));
+                self.pc = self.addr_abs;
+                self.finish(poll);
+                return;
+            }
+        }
+        self.step += 1;
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.a);
+        w.u8(self.x);
+        w.u8(self.y);
+        w.u8(self.stkp);
+        w.u16(self.pc);
+        w.u8(self.status);
+        w.u8(self.fetched);
+        w.u16(self.temp);
+        w.u16(self.addr_abs);
+        w.u16(self.addr_rel);
+        w.u8(self.opcode);
+        w.u8(self.cycles);
+        w.u64(self.total_cycles);
+        w.u8(self.step);
+        w.bool(self.nmi_pending);
+        w.bool(self.irq_line);
+        w.bool(self.take_interrupt);
+        w.bool(self.in_interrupt);
+        w.bool(self.jammed);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.a = r.u8()?;
+        self.x = r.u8()?;
+        self.y = r.u8()?;
+        self.stkp = r.u8()?;
+        self.pc = r.u16()?;
+        self.status = r.u8()?;
+        self.fetched = r.u8()?;
+        self.temp = r.u16()?;
+        self.addr_abs = r.u16()?;
+        self.addr_rel = r.u16()?;
+        self.opcode = r.u8()?;
+        self.cycles = r.u8()?;
+        self.total_cycles = r.u64()?;
+        self.step = r.u8()?;
+        self.nmi_pending = r.bool()?;
+        self.irq_line = r.bool()?;
+        self.take_interrupt = r.bool()?;
+        self.in_interrupt = r.bool()?;
+        self.jammed = r.bool()?;
+        Ok(())
     }
     
     
@@ -1018,10 +1355,10 @@ impl Cpu6502 {
         self.x = 0;
         self.y = 0;
         self.stkp = 0xFD;
-        self.status = 0x00 | FLAGS6502::U as u8;
+        self.status = FLAGS6502::U as u8 | FLAGS6502::I as u8;
         
         self.addr_abs = 0xFFFC;
-        let lo = self.read(bus, self.addr_abs + 0);
+        let lo = self.read(bus, self.addr_abs);
         let hi = self.read(bus, self.addr_abs + 1);
 
         self.pc = ((hi as u16) << 8) | (lo as u16);
@@ -1030,347 +1367,317 @@ impl Cpu6502 {
         self.addr_abs = 0x0000;
         self.fetched = 0x00;
 
-        self.cycles = 8;
+        self.step = 0;
+        self.nmi_pending = false;
+        self.irq_line = false;
+        self.take_interrupt = false;
+        self.in_interrupt = false;
+        self.jammed = false;
+
+        self.cycles = 7;
     }
     // Interruptiuon Request
-    pub fn irq(&mut self, bus: &mut crate::bus::Bus) {
-        if self.getFlag(FLAGS6502::I) == 0 {
-            // push PC
-            self.write(bus, 0x0100 + self.stkp as u16, ((self.pc >> 8) & 0x00FF) as u8);
-            self.stkp = self.stkp.wrapping_sub(1);
-            self.write(bus, 0x0100 + self.stkp as u16, (self.pc & 0x00FF) as u8);
-            self.stkp = self.stkp.wrapping_sub(1);
-    
-            // push status (B=0, U=1) e seta I
-            self.setFlag(FLAGS6502::B, false);
-            self.setFlag(FLAGS6502::U, true);
-            self.setFlag(FLAGS6502::I, true);
-            self.write(bus, 0x0100 + self.stkp as u16, self.status);
-            self.stkp = self.stkp.wrapping_sub(1);
-    
-            // vetor IRQ
-            let lo = self.read(bus, 0xFFFE) as u16;
-            let hi = self.read(bus, 0xFFFF) as u16;
-            self.pc = (hi << 8) | lo;
-    
-            self.cycles = 7;
-        }
+    // IRQ é por nível: enquanto a linha estiver ativa e I=0, o clock()
+    // atende no fim da instrução. Quem segura a linha é que precisa de ack
+    pub fn set_irq_line(&mut self, level: bool) {
+        self.irq_line = level;
     }
     
-    pub fn nmi(&mut self, bus: &mut crate::bus::Bus) {
-        self.write(bus, 0x0100 + self.stkp as u16, ((self.pc >> 8) & 0x00FF) as u8);
-        self.stkp = self.stkp.wrapping_sub(1);
-        self.write(bus, 0x0100 + self.stkp as u16, (self.pc & 0x00FF) as u8);
-        self.stkp = self.stkp.wrapping_sub(1);
-    
-        self.setFlag(FLAGS6502::B, false);
-        self.setFlag(FLAGS6502::U, true);
-        self.setFlag(FLAGS6502::I, true);
-        self.write(bus, 0x0100 + self.stkp as u16, self.status);
-        self.stkp = self.stkp.wrapping_sub(1);
-    
-        let lo = self.read(bus, 0xFFFA) as u16;
-        let hi = self.read(bus, 0xFFFB) as u16;
-        self.pc = (hi << 8) | lo;
-    
-        self.cycles = 8;
+    pub fn nmi(&mut self) {
+        self.nmi_pending = true;
     }
 
-    // Tem algumas instrucoes que somente alguns
-    // Jogos ou nenhum usa, então nao irei emula-los
-    // Mas pode ser que com o tempo eu adicione-os
-    // Eu irei coloca-los como "???"
-    // Ou, quando for realmente em branco, mas alguns
-    // Roms modificadas podem nao funcionar
+    // Tabela completa, oficiais e ilegais. Os ilegais mais instáveis
+    // (XAA, LXA, SHx, TAS) seguem o comportamento mais comum do 2A03
     pub fn instrucoes() -> Vec<Instruction> {
         vec![
             // 0x00
-            Instruction { name: "BRK", operate: Cpu6502::BRK, addrmode: Cpu6502::IMP, cycles: 7 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SLO", operate: Cpu6502::SLO, addrmode: Cpu6502::IZX, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 3 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "ASL", operate: Cpu6502::ASL, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "SLO", operate: Cpu6502::SLO, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "PHP", operate: Cpu6502::PHP, addrmode: Cpu6502::IMP, cycles: 3 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "ASL", operate: Cpu6502::ASL, addrmode: Cpu6502::ACC, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "ASL", operate: Cpu6502::ASL, addrmode: Cpu6502::ABS, cycles: 6 },
-            Instruction { name: "SLO", operate: Cpu6502::SLO, addrmode: Cpu6502::ABS, cycles: 6 },
+            Instruction { name: "BRK", operate: Cpu6502::BRK, mode: AddrMode::Imp, official: true },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Izx, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "SLO", operate: Cpu6502::SLO, mode: AddrMode::Izx, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "ASL", operate: Cpu6502::ASL, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "SLO", operate: Cpu6502::SLO, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "PHP", operate: Cpu6502::PHP, mode: AddrMode::Imp, official: true },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Imm, official: true },
+            Instruction { name: "ASL", operate: Cpu6502::ASL, mode: AddrMode::Acc, official: true },
+            Instruction { name: "ANC", operate: Cpu6502::ANC, mode: AddrMode::Imm, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Abs, official: false },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Abs, official: true },
+            Instruction { name: "ASL", operate: Cpu6502::ASL, mode: AddrMode::Abs, official: true },
+            Instruction { name: "SLO", operate: Cpu6502::SLO, mode: AddrMode::Abs, official: false },
 
             // 0x10
-            Instruction { name: "BPL", operate: Cpu6502::BPL, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SLO", operate: Cpu6502::SLO, addrmode: Cpu6502::IZY, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "ASL", operate: Cpu6502::ASL, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "SLO", operate: Cpu6502::SLO, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "CLC", operate: Cpu6502::CLC, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SLO", operate: Cpu6502::SLO, addrmode: Cpu6502::ABY, cycles: 7 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "ORA", operate: Cpu6502::ORA, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "ASL", operate: Cpu6502::ASL, addrmode: Cpu6502::ABX, cycles: 7 },
-            Instruction { name: "SLO", operate: Cpu6502::SLO, addrmode: Cpu6502::ABX, cycles: 7 },
+            Instruction { name: "BPL", operate: Cpu6502::BPL, mode: AddrMode::Rel, official: true },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "SLO", operate: Cpu6502::SLO, mode: AddrMode::Izy, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "ASL", operate: Cpu6502::ASL, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "SLO", operate: Cpu6502::SLO, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "CLC", operate: Cpu6502::CLC, mode: AddrMode::Imp, official: true },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Aby, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imp, official: false },
+            Instruction { name: "SLO", operate: Cpu6502::SLO, mode: AddrMode::Aby, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Abx, official: false },
+            Instruction { name: "ORA", operate: Cpu6502::ORA, mode: AddrMode::Abx, official: true },
+            Instruction { name: "ASL", operate: Cpu6502::ASL, mode: AddrMode::Abx, official: true },
+            Instruction { name: "SLO", operate: Cpu6502::SLO, mode: AddrMode::Abx, official: false },
     
             // 0x20
-            Instruction { name: "JSR", operate: Cpu6502::JSR, addrmode: Cpu6502::ABS, cycles: 6 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "RLA", operate: Cpu6502::RLA, addrmode: Cpu6502::IZX, cycles: 8 },
-            Instruction { name: "BIT", operate: Cpu6502::BIT, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "ROL", operate: Cpu6502::ROL, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "RLA", operate: Cpu6502::RLA, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "PLP", operate: Cpu6502::PLP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "ROL", operate: Cpu6502::ROL, addrmode: Cpu6502::ACC, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "BIT", operate: Cpu6502::BIT, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "ROL", operate: Cpu6502::ROL, addrmode: Cpu6502::ABS, cycles: 6 },
-            Instruction { name: "RLA", operate: Cpu6502::RLA, addrmode: Cpu6502::ABS, cycles: 6 },
+            Instruction { name: "JSR", operate: Cpu6502::JSR, mode: AddrMode::Abs, official: true },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Izx, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "RLA", operate: Cpu6502::RLA, mode: AddrMode::Izx, official: false },
+            Instruction { name: "BIT", operate: Cpu6502::BIT, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "ROL", operate: Cpu6502::ROL, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "RLA", operate: Cpu6502::RLA, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "PLP", operate: Cpu6502::PLP, mode: AddrMode::Imp, official: true },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Imm, official: true },
+            Instruction { name: "ROL", operate: Cpu6502::ROL, mode: AddrMode::Acc, official: true },
+            Instruction { name: "ANC", operate: Cpu6502::ANC, mode: AddrMode::Imm, official: false },
+            Instruction { name: "BIT", operate: Cpu6502::BIT, mode: AddrMode::Abs, official: true },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Abs, official: true },
+            Instruction { name: "ROL", operate: Cpu6502::ROL, mode: AddrMode::Abs, official: true },
+            Instruction { name: "RLA", operate: Cpu6502::RLA, mode: AddrMode::Abs, official: false },
 
             // 0x30
-            Instruction { name: "BMI", operate: Cpu6502::BMI, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "RLA", operate: Cpu6502::RLA, addrmode: Cpu6502::IZY, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "ROL", operate: Cpu6502::ROL, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "RLA", operate: Cpu6502::RLA, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "SEC", operate: Cpu6502::SEC, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "RLA", operate: Cpu6502::RLA, addrmode: Cpu6502::ABY, cycles: 7 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "AND", operate: Cpu6502::AND, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "ROL", operate: Cpu6502::ROL, addrmode: Cpu6502::ABX, cycles: 7 },
-            Instruction { name: "RLA", operate: Cpu6502::RLA, addrmode: Cpu6502::ABX, cycles: 7 },
+            Instruction { name: "BMI", operate: Cpu6502::BMI, mode: AddrMode::Rel, official: true },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "RLA", operate: Cpu6502::RLA, mode: AddrMode::Izy, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "ROL", operate: Cpu6502::ROL, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "RLA", operate: Cpu6502::RLA, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "SEC", operate: Cpu6502::SEC, mode: AddrMode::Imp, official: true },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Aby, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imp, official: false },
+            Instruction { name: "RLA", operate: Cpu6502::RLA, mode: AddrMode::Aby, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Abx, official: false },
+            Instruction { name: "AND", operate: Cpu6502::AND, mode: AddrMode::Abx, official: true },
+            Instruction { name: "ROL", operate: Cpu6502::ROL, mode: AddrMode::Abx, official: true },
+            Instruction { name: "RLA", operate: Cpu6502::RLA, mode: AddrMode::Abx, official: false },
     
             // 0x40
-            Instruction { name: "RTI", operate: Cpu6502::RTI, addrmode: Cpu6502::IMP, cycles: 6 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SRE", operate: Cpu6502::SRE, addrmode: Cpu6502::IZX, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 3 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "LSR", operate: Cpu6502::LSR, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "SRE", operate: Cpu6502::SRE, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "PHA", operate: Cpu6502::PHA, addrmode: Cpu6502::IMP, cycles: 3 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "LSR", operate: Cpu6502::LSR, addrmode: Cpu6502::ACC, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "JMP", operate: Cpu6502::JMP, addrmode: Cpu6502::ABS, cycles: 3 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "LSR", operate: Cpu6502::LSR, addrmode: Cpu6502::ABS, cycles: 6 },
-            Instruction { name: "SRE", operate: Cpu6502::SRE, addrmode: Cpu6502::ABS, cycles: 6 },
+            Instruction { name: "RTI", operate: Cpu6502::RTI, mode: AddrMode::Imp, official: true },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Izx, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "SRE", operate: Cpu6502::SRE, mode: AddrMode::Izx, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "LSR", operate: Cpu6502::LSR, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "SRE", operate: Cpu6502::SRE, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "PHA", operate: Cpu6502::PHA, mode: AddrMode::Imp, official: true },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Imm, official: true },
+            Instruction { name: "LSR", operate: Cpu6502::LSR, mode: AddrMode::Acc, official: true },
+            Instruction { name: "ALR", operate: Cpu6502::ALR, mode: AddrMode::Imm, official: false },
+            Instruction { name: "JMP", operate: Cpu6502::JMP, mode: AddrMode::Abs, official: true },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Abs, official: true },
+            Instruction { name: "LSR", operate: Cpu6502::LSR, mode: AddrMode::Abs, official: true },
+            Instruction { name: "SRE", operate: Cpu6502::SRE, mode: AddrMode::Abs, official: false },
 
             // 0x50
-            Instruction { name: "BVC", operate: Cpu6502::BVC, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SRE", operate: Cpu6502::SRE, addrmode: Cpu6502::IZY, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "LSR", operate: Cpu6502::LSR, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "SRE", operate: Cpu6502::SRE, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "CLI", operate: Cpu6502::CLI, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SRE", operate: Cpu6502::SRE, addrmode: Cpu6502::ABY, cycles: 7 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "EOR", operate: Cpu6502::EOR, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "LSR", operate: Cpu6502::LSR, addrmode: Cpu6502::ABX, cycles: 7 },
-            Instruction { name: "SRE", operate: Cpu6502::SRE, addrmode: Cpu6502::ABX, cycles: 7 },
+            Instruction { name: "BVC", operate: Cpu6502::BVC, mode: AddrMode::Rel, official: true },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "SRE", operate: Cpu6502::SRE, mode: AddrMode::Izy, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "LSR", operate: Cpu6502::LSR, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "SRE", operate: Cpu6502::SRE, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "CLI", operate: Cpu6502::CLI, mode: AddrMode::Imp, official: true },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Aby, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imp, official: false },
+            Instruction { name: "SRE", operate: Cpu6502::SRE, mode: AddrMode::Aby, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Abx, official: false },
+            Instruction { name: "EOR", operate: Cpu6502::EOR, mode: AddrMode::Abx, official: true },
+            Instruction { name: "LSR", operate: Cpu6502::LSR, mode: AddrMode::Abx, official: true },
+            Instruction { name: "SRE", operate: Cpu6502::SRE, mode: AddrMode::Abx, official: false },
     
             // 0x60
-            Instruction { name: "RTS", operate: Cpu6502::RTS, addrmode: Cpu6502::IMP, cycles: 6 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "RRA", operate: Cpu6502::RRA, addrmode: Cpu6502::IZX, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 3 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "ROR", operate: Cpu6502::ROR, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "RRA", operate: Cpu6502::RRA, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "PLA", operate: Cpu6502::PLA, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "ROR", operate: Cpu6502::ROR, addrmode: Cpu6502::ACC, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "JMP", operate: Cpu6502::JMP, addrmode: Cpu6502::IND, cycles: 5 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "ROR", operate: Cpu6502::ROR, addrmode: Cpu6502::ABS, cycles: 6 },
-            Instruction { name: "RRA", operate: Cpu6502::RRA, addrmode: Cpu6502::ABS, cycles: 6 },
+            Instruction { name: "RTS", operate: Cpu6502::RTS, mode: AddrMode::Imp, official: true },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Izx, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "RRA", operate: Cpu6502::RRA, mode: AddrMode::Izx, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "ROR", operate: Cpu6502::ROR, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "RRA", operate: Cpu6502::RRA, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "PLA", operate: Cpu6502::PLA, mode: AddrMode::Imp, official: true },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Imm, official: true },
+            Instruction { name: "ROR", operate: Cpu6502::ROR, mode: AddrMode::Acc, official: true },
+            Instruction { name: "ARR", operate: Cpu6502::ARR, mode: AddrMode::Imm, official: false },
+            Instruction { name: "JMP", operate: Cpu6502::JMP, mode: AddrMode::Ind, official: true },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Abs, official: true },
+            Instruction { name: "ROR", operate: Cpu6502::ROR, mode: AddrMode::Abs, official: true },
+            Instruction { name: "RRA", operate: Cpu6502::RRA, mode: AddrMode::Abs, official: false },
 
             // 0x70
-            Instruction { name: "BVS", operate: Cpu6502::BVS, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "RRA", operate: Cpu6502::RRA, addrmode: Cpu6502::IZY, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "ROR", operate: Cpu6502::ROR, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "RRA", operate: Cpu6502::RRA, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "SEI", operate: Cpu6502::SEI, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "RRA", operate: Cpu6502::RRA, addrmode: Cpu6502::ABY, cycles: 7 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "ADC", operate: Cpu6502::ADC, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "ROR", operate: Cpu6502::ROR, addrmode: Cpu6502::ABX, cycles: 7 },
-            Instruction { name: "RRA", operate: Cpu6502::RRA, addrmode: Cpu6502::ABX, cycles: 7 },
+            Instruction { name: "BVS", operate: Cpu6502::BVS, mode: AddrMode::Rel, official: true },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "RRA", operate: Cpu6502::RRA, mode: AddrMode::Izy, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "ROR", operate: Cpu6502::ROR, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "RRA", operate: Cpu6502::RRA, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "SEI", operate: Cpu6502::SEI, mode: AddrMode::Imp, official: true },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Aby, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imp, official: false },
+            Instruction { name: "RRA", operate: Cpu6502::RRA, mode: AddrMode::Aby, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Abx, official: false },
+            Instruction { name: "ADC", operate: Cpu6502::ADC, mode: AddrMode::Abx, official: true },
+            Instruction { name: "ROR", operate: Cpu6502::ROR, mode: AddrMode::Abx, official: true },
+            Instruction { name: "RRA", operate: Cpu6502::RRA, mode: AddrMode::Abx, official: false },
     
             // 0x80
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "STA", operate: Cpu6502::STA, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SAX", operate: Cpu6502::SAX, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "STY", operate: Cpu6502::STY, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "STA", operate: Cpu6502::STA, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "STX", operate: Cpu6502::STX, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "SAX", operate: Cpu6502::SAX, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "DEY", operate: Cpu6502::DEY, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "TXA", operate: Cpu6502::TXA, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "STY", operate: Cpu6502::STY, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "STA", operate: Cpu6502::STA, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "STX", operate: Cpu6502::STX, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "SAX", operate: Cpu6502::SAX, addrmode: Cpu6502::ABS, cycles: 4 },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imm, official: false },
+            Instruction { name: "STA", operate: Cpu6502::STA, mode: AddrMode::Izx, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imm, official: false },
+            Instruction { name: "SAX", operate: Cpu6502::SAX, mode: AddrMode::Izx, official: false },
+            Instruction { name: "STY", operate: Cpu6502::STY, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "STA", operate: Cpu6502::STA, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "STX", operate: Cpu6502::STX, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "SAX", operate: Cpu6502::SAX, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "DEY", operate: Cpu6502::DEY, mode: AddrMode::Imp, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imm, official: false },
+            Instruction { name: "TXA", operate: Cpu6502::TXA, mode: AddrMode::Imp, official: true },
+            Instruction { name: "XAA", operate: Cpu6502::XAA, mode: AddrMode::Imm, official: false },
+            Instruction { name: "STY", operate: Cpu6502::STY, mode: AddrMode::Abs, official: true },
+            Instruction { name: "STA", operate: Cpu6502::STA, mode: AddrMode::Abs, official: true },
+            Instruction { name: "STX", operate: Cpu6502::STX, mode: AddrMode::Abs, official: true },
+            Instruction { name: "SAX", operate: Cpu6502::SAX, mode: AddrMode::Abs, official: false },
 
             // 0x90
-            Instruction { name: "BCC", operate: Cpu6502::BCC, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "STA", operate: Cpu6502::STA, addrmode: Cpu6502::IZY, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 6 },
-            Instruction { name: "STY", operate: Cpu6502::STY, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "STA", operate: Cpu6502::STA, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "STX", operate: Cpu6502::STX, addrmode: Cpu6502::ZPY, cycles: 4 },
-            Instruction { name: "SAX", operate: Cpu6502::SAX, addrmode: Cpu6502::ZPY, cycles: 3 },
-            Instruction { name: "TYA", operate: Cpu6502::TYA, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "STA", operate: Cpu6502::STA, addrmode: Cpu6502::ABY, cycles: 5 },
-            Instruction { name: "TXS", operate: Cpu6502::TXS, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 5 },
-            Instruction { name: "STA", operate: Cpu6502::STA, addrmode: Cpu6502::ABX, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 5 },
+            Instruction { name: "BCC", operate: Cpu6502::BCC, mode: AddrMode::Rel, official: true },
+            Instruction { name: "STA", operate: Cpu6502::STA, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "SHA", operate: Cpu6502::SHA, mode: AddrMode::Izy, official: false },
+            Instruction { name: "STY", operate: Cpu6502::STY, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "STA", operate: Cpu6502::STA, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "STX", operate: Cpu6502::STX, mode: AddrMode::Zpy, official: true },
+            Instruction { name: "SAX", operate: Cpu6502::SAX, mode: AddrMode::Zpy, official: false },
+            Instruction { name: "TYA", operate: Cpu6502::TYA, mode: AddrMode::Imp, official: true },
+            Instruction { name: "STA", operate: Cpu6502::STA, mode: AddrMode::Aby, official: true },
+            Instruction { name: "TXS", operate: Cpu6502::TXS, mode: AddrMode::Imp, official: true },
+            Instruction { name: "TAS", operate: Cpu6502::TAS, mode: AddrMode::Aby, official: false },
+            Instruction { name: "SHY", operate: Cpu6502::SHY, mode: AddrMode::Abx, official: false },
+            Instruction { name: "STA", operate: Cpu6502::STA, mode: AddrMode::Abx, official: true },
+            Instruction { name: "SHX", operate: Cpu6502::SHX, mode: AddrMode::Aby, official: false },
+            Instruction { name: "SHA", operate: Cpu6502::SHA, mode: AddrMode::Aby, official: false },
     
             // 0xA0
-            Instruction { name: "LDY", operate: Cpu6502::LDY, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "LDX", operate: Cpu6502::LDX, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "LAX", operate: Cpu6502::LAX, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "LDY", operate: Cpu6502::LDY, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "LDX", operate: Cpu6502::LDX, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "LAX", operate: Cpu6502::LAX, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "TAY", operate: Cpu6502::TAY, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "TAX", operate: Cpu6502::TAX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "LDY", operate: Cpu6502::LDY, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "LDX", operate: Cpu6502::LDX, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "LAX", operate: Cpu6502::LAX, addrmode: Cpu6502::ABS, cycles: 4 },
+            Instruction { name: "LDY", operate: Cpu6502::LDY, mode: AddrMode::Imm, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Izx, official: true },
+            Instruction { name: "LDX", operate: Cpu6502::LDX, mode: AddrMode::Imm, official: true },
+            Instruction { name: "LAX", operate: Cpu6502::LAX, mode: AddrMode::Izx, official: false },
+            Instruction { name: "LDY", operate: Cpu6502::LDY, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "LDX", operate: Cpu6502::LDX, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "LAX", operate: Cpu6502::LAX, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "TAY", operate: Cpu6502::TAY, mode: AddrMode::Imp, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Imm, official: true },
+            Instruction { name: "TAX", operate: Cpu6502::TAX, mode: AddrMode::Imp, official: true },
+            Instruction { name: "LXA", operate: Cpu6502::LXA, mode: AddrMode::Imm, official: false },
+            Instruction { name: "LDY", operate: Cpu6502::LDY, mode: AddrMode::Abs, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Abs, official: true },
+            Instruction { name: "LDX", operate: Cpu6502::LDX, mode: AddrMode::Abs, official: true },
+            Instruction { name: "LAX", operate: Cpu6502::LAX, mode: AddrMode::Abs, official: false },
 
             // 0xB0
-            Instruction { name: "BCS", operate: Cpu6502::BCS, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "LAX", operate: Cpu6502::LAX, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "LDY", operate: Cpu6502::LDY, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "LDX", operate: Cpu6502::LDX, addrmode: Cpu6502::ZPY, cycles: 4 },
-            Instruction { name: "LAX", operate: Cpu6502::LAX, addrmode: Cpu6502::ZPY, cycles: 3 },
-            Instruction { name: "CLV", operate: Cpu6502::CLV, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "TSX", operate: Cpu6502::TSX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "LDY", operate: Cpu6502::LDY, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "LDA", operate: Cpu6502::LDA, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "LDX", operate: Cpu6502::LDX, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "LAX", operate: Cpu6502::LAX, addrmode: Cpu6502::ABY, cycles: 4 },
+            Instruction { name: "BCS", operate: Cpu6502::BCS, mode: AddrMode::Rel, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "LAX", operate: Cpu6502::LAX, mode: AddrMode::Izy, official: false },
+            Instruction { name: "LDY", operate: Cpu6502::LDY, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "LDX", operate: Cpu6502::LDX, mode: AddrMode::Zpy, official: true },
+            Instruction { name: "LAX", operate: Cpu6502::LAX, mode: AddrMode::Zpy, official: false },
+            Instruction { name: "CLV", operate: Cpu6502::CLV, mode: AddrMode::Imp, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Aby, official: true },
+            Instruction { name: "TSX", operate: Cpu6502::TSX, mode: AddrMode::Imp, official: true },
+            Instruction { name: "LAS", operate: Cpu6502::LAS, mode: AddrMode::Aby, official: false },
+            Instruction { name: "LDY", operate: Cpu6502::LDY, mode: AddrMode::Abx, official: true },
+            Instruction { name: "LDA", operate: Cpu6502::LDA, mode: AddrMode::Abx, official: true },
+            Instruction { name: "LDX", operate: Cpu6502::LDX, mode: AddrMode::Aby, official: true },
+            Instruction { name: "LAX", operate: Cpu6502::LAX, mode: AddrMode::Aby, official: false },
 
             // 0xC0
-            Instruction { name: "CPY", operate: Cpu6502::CPY, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "DCP", operate: Cpu6502::DCP, addrmode: Cpu6502::IZX, cycles: 8 },
-            Instruction { name: "CPY", operate: Cpu6502::CPY, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "DEC", operate: Cpu6502::DEC, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "DCP", operate: Cpu6502::DCP, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "INY", operate: Cpu6502::INY, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "DEX", operate: Cpu6502::DEX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "CPY", operate: Cpu6502::CPY, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "DEC", operate: Cpu6502::DEC, addrmode: Cpu6502::ABS, cycles: 6 },
-            Instruction { name: "DCP", operate: Cpu6502::DCP, addrmode: Cpu6502::ABS, cycles: 6 },
+            Instruction { name: "CPY", operate: Cpu6502::CPY, mode: AddrMode::Imm, official: true },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Izx, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imm, official: false },
+            Instruction { name: "DCP", operate: Cpu6502::DCP, mode: AddrMode::Izx, official: false },
+            Instruction { name: "CPY", operate: Cpu6502::CPY, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "DEC", operate: Cpu6502::DEC, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "DCP", operate: Cpu6502::DCP, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "INY", operate: Cpu6502::INY, mode: AddrMode::Imp, official: true },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Imm, official: true },
+            Instruction { name: "DEX", operate: Cpu6502::DEX, mode: AddrMode::Imp, official: true },
+            Instruction { name: "AXS", operate: Cpu6502::AXS, mode: AddrMode::Imm, official: false },
+            Instruction { name: "CPY", operate: Cpu6502::CPY, mode: AddrMode::Abs, official: true },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Abs, official: true },
+            Instruction { name: "DEC", operate: Cpu6502::DEC, mode: AddrMode::Abs, official: true },
+            Instruction { name: "DCP", operate: Cpu6502::DCP, mode: AddrMode::Abs, official: false },
 
             // 0xD0
-            Instruction { name: "BNE", operate: Cpu6502::BNE, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "DCP", operate: Cpu6502::DCP, addrmode: Cpu6502::IZY, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "DEC", operate: Cpu6502::DEC, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "DCP", operate: Cpu6502::DCP, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "CLD", operate: Cpu6502::CLD, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "NOP", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "DCP", operate: Cpu6502::DCP, addrmode: Cpu6502::ABY, cycles: 7 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "CMP", operate: Cpu6502::CMP, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "DEC", operate: Cpu6502::DEC, addrmode: Cpu6502::ABX, cycles: 7 },
-            Instruction { name: "DCP", operate: Cpu6502::DCP, addrmode: Cpu6502::ABX, cycles: 7 },
+            Instruction { name: "BNE", operate: Cpu6502::BNE, mode: AddrMode::Rel, official: true },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "DCP", operate: Cpu6502::DCP, mode: AddrMode::Izy, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "DEC", operate: Cpu6502::DEC, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "DCP", operate: Cpu6502::DCP, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "CLD", operate: Cpu6502::CLD, mode: AddrMode::Imp, official: true },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Aby, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imp, official: false },
+            Instruction { name: "DCP", operate: Cpu6502::DCP, mode: AddrMode::Aby, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Abx, official: false },
+            Instruction { name: "CMP", operate: Cpu6502::CMP, mode: AddrMode::Abx, official: true },
+            Instruction { name: "DEC", operate: Cpu6502::DEC, mode: AddrMode::Abx, official: true },
+            Instruction { name: "DCP", operate: Cpu6502::DCP, mode: AddrMode::Abx, official: false },
     
             // 0xE0
-            Instruction { name: "CPX", operate: Cpu6502::CPX, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::IZX, cycles: 6 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "ISB", operate: Cpu6502::ISB, addrmode: Cpu6502::IZX, cycles: 8 },
-            Instruction { name: "CPX", operate: Cpu6502::CPX, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::ZP0, cycles: 3 },
-            Instruction { name: "INC", operate: Cpu6502::INC, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "ISB", operate: Cpu6502::ISB, addrmode: Cpu6502::ZP0, cycles: 5 },
-            Instruction { name: "INX", operate: Cpu6502::INX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::IMM, cycles: 2 },
-            Instruction { name: "NOP", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "???", operate: Cpu6502::SBC, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "CPX", operate: Cpu6502::CPX, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::ABS, cycles: 4 },
-            Instruction { name: "INC", operate: Cpu6502::INC, addrmode: Cpu6502::ABS, cycles: 6 },
-            Instruction { name: "ISB", operate: Cpu6502::ISB, addrmode: Cpu6502::ABS, cycles: 6 },
+            Instruction { name: "CPX", operate: Cpu6502::CPX, mode: AddrMode::Imm, official: true },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Izx, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imm, official: false },
+            Instruction { name: "ISB", operate: Cpu6502::ISB, mode: AddrMode::Izx, official: false },
+            Instruction { name: "CPX", operate: Cpu6502::CPX, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "INC", operate: Cpu6502::INC, mode: AddrMode::Zp0, official: true },
+            Instruction { name: "ISB", operate: Cpu6502::ISB, mode: AddrMode::Zp0, official: false },
+            Instruction { name: "INX", operate: Cpu6502::INX, mode: AddrMode::Imp, official: true },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Imm, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imp, official: true },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Imm, official: false },
+            Instruction { name: "CPX", operate: Cpu6502::CPX, mode: AddrMode::Abs, official: true },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Abs, official: true },
+            Instruction { name: "INC", operate: Cpu6502::INC, mode: AddrMode::Abs, official: true },
+            Instruction { name: "ISB", operate: Cpu6502::ISB, mode: AddrMode::Abs, official: false },
 
             // 0xF0
-            Instruction { name: "BEQ", operate: Cpu6502::BEQ, addrmode: Cpu6502::REL, cycles: 2 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::IZY, cycles: 5 },
-            Instruction { name: "???", operate: Cpu6502::XXX, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "ISB", operate: Cpu6502::ISB, addrmode: Cpu6502::IZY, cycles: 8 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::ZPX, cycles: 4 },
-            Instruction { name: "INC", operate: Cpu6502::INC, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "ISB", operate: Cpu6502::ISB, addrmode: Cpu6502::ZPX, cycles: 6 },
-            Instruction { name: "SED", operate: Cpu6502::SED, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::ABY, cycles: 4 },
-            Instruction { name: "NOP", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 2 },
-            Instruction { name: "ISB", operate: Cpu6502::ISB, addrmode: Cpu6502::ABY, cycles: 7 },
-            Instruction { name: "???", operate: Cpu6502::NOP, addrmode: Cpu6502::IMP, cycles: 4 },
-            Instruction { name: "SBC", operate: Cpu6502::SBC, addrmode: Cpu6502::ABX, cycles: 4 },
-            Instruction { name: "INC", operate: Cpu6502::INC, addrmode: Cpu6502::ABX, cycles: 7 },
-            Instruction { name: "ISB", operate: Cpu6502::ISB, addrmode: Cpu6502::ABX, cycles: 7 },
+            Instruction { name: "BEQ", operate: Cpu6502::BEQ, mode: AddrMode::Rel, official: true },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Izy, official: true },
+            Instruction { name: "JAM", operate: Cpu6502::JAM, mode: AddrMode::Imp, official: false },
+            Instruction { name: "ISB", operate: Cpu6502::ISB, mode: AddrMode::Izy, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "INC", operate: Cpu6502::INC, mode: AddrMode::Zpx, official: true },
+            Instruction { name: "ISB", operate: Cpu6502::ISB, mode: AddrMode::Zpx, official: false },
+            Instruction { name: "SED", operate: Cpu6502::SED, mode: AddrMode::Imp, official: true },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Aby, official: true },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Imp, official: false },
+            Instruction { name: "ISB", operate: Cpu6502::ISB, mode: AddrMode::Aby, official: false },
+            Instruction { name: "NOP", operate: Cpu6502::NOP, mode: AddrMode::Abx, official: false },
+            Instruction { name: "SBC", operate: Cpu6502::SBC, mode: AddrMode::Abx, official: true },
+            Instruction { name: "INC", operate: Cpu6502::INC, mode: AddrMode::Abx, official: true },
+            Instruction { name: "ISB", operate: Cpu6502::ISB, mode: AddrMode::Abx, official: false },
         ]
     }
 }
@@ -1,23 +1,100 @@
+use std::collections::VecDeque;
+
 use crate::bus::Bus;
-use crate::cpu6502::Cpu6502;
+use crate::callstack::CallStack;
+use crate::cpu6502::{AddrMode, Cpu6502};
+use crate::disasm;
+use crate::source::SourceLoc;
+use crate::symbols::SymbolTable;
+use crate::expr::{split_condition, Condition, EvalContext};
+use crate::watch::{WatchSpace, WATCH_EXEC};
+use crate::trace::{self, TraceCondition, TraceDiff, TraceFile, TraceRecord, TraceRing};
+
+const TRACE_RING_SIZE: usize = 4096; // linhas de histórico em memória
+const PC_HISTORY: usize = 4;          // instruções anteriores mostradas no disassembly
+const WATCH_LOG_SIZE: usize = 256;    // últimos hits de watchpoint guardados
+
+// O que fazer depois de continuar de uma parada
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepMode {
+    Run,
+    Instruction,                    // para na próxima instrução
+    Over { ret: u16, sp: u8 },      // JSR: para quando voltar pro endereço de retorno
+    Out { sp: u8 },                 // para depois do RTS/RTI que sai dessa rotina
+    Scanline(i16),                  // para na primeira instrução fora dessa scanline
+    Frame(u32),                     // para na primeira instrução do próximo frame
+    RunTo(u16),                     // breakpoint temporário
+    // para numa linha de fonte diferente de from. Com sp, pula o que for
+    // chamado de dentro dela (step over)
+    Line { from: Option<SourceLoc>, sp: Option<u8> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub addr: u16,
+    pub prg: Option<usize>,     // só para com esse pedaço da PRG mapeado (breakpoint por label de ROM)
+    pub label: Option<String>,  // label ainda não resolvido (ver Debugger::add_breakpoint)
+    pub cond: Option<Condition>,
+    pub brk: bool,      // false = só loga (no lugar de um println! temporário)
+    pub hits: u64,      // vezes que o PC passou aqui (antes da condição)
+    pub gdb: bool,      // posto pelo stub do GDB: z0 e o detach só tiram esses
+}
+
+impl Breakpoint {
+    pub fn new(addr: u16) -> Self {
+        Breakpoint { addr, prg: None, label: None, cond: None, brk: true, hits: 0, gdb: false }
+    }
+
+    // ADDR|LABEL[:log][ if COND]  ex: "C003", "nmi_handler if A == $10 && hits > 3", "E000:log if [$0300] > 5"
+    // Um label que também é hex válido (ex: "add") vira endereço; use "$" ou outro nome
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (s, cond) = split_condition(spec)?;
+        let (target, brk) = match s.split_once(':') {
+            Some((a, flag)) if flag.eq_ignore_ascii_case("log") => (a.trim(), false),
+            Some((_, flag)) => return Err(format!("opcao invalida: {} (so :log)", flag)),
+            None => (s, true),
+        };
+        let mut bp = Breakpoint { cond, brk, ..Breakpoint::new(0) };
+        match u16::from_str_radix(target.trim_start_matches('$'), 16) {
+            Ok(addr) => bp.addr = addr,
+            Err(_) if !target.starts_with('$') && !target.is_empty() => bp.label = Some(target.to_string()),
+            Err(_) => return Err(format!("endereco invalido: {}", target)),
+        }
+        Ok(bp)
+    }
+}
 
 pub struct Debugger {
     // CPU instruction coverage
     pub opcode_count: [u64; 256],     // quantas vezes cada opcode foi executado
-    pub opcode_names: [&'static str; 256],
-    pub unknown_opcodes: Vec<(u8, u16)>, // (opcode, PC) quando bate num opcode desconhecido
+    pub jam: Option<(u8, u16)>,          // (opcode, PC) do JAM que travou a CPU
 
-    // CPU trace
-    pub trace_enabled: bool,
-    pub trace_log: Vec<String>,
-    pub trace_max: usize,
+    // CPU trace (formato Nintendulator, ver trace.rs)
+    pub trace_enabled: bool,             // guarda no ring em memória
+    pub trace_log: TraceRing,
+    pub trace_file: Option<TraceFile>,
+    pub trace_cond: TraceCondition,
+    pub trace_diff: Option<TraceDiff>,   // comparando com um log de referência
 
-    // Breakpoints
-    pub breakpoints: Vec<u16>,
+    // Breakpoints / stepping. hit_breakpoint = parado; o frontend não roda
+    // frames até chamar resume() ou um dos step_*
+    pub breakpoints: Vec<Breakpoint>,
     pub hit_breakpoint: bool,
+    pub break_reason: String,
+    pub step: StepMode,
+    pub pc_history: VecDeque<u16>,
+    last_opcode: u8,
+    last_interrupt: bool,       // a última "instrução" foi a sequência de NMI/IRQ
 
-    // Memory watch
-    pub watches: Vec<(u16, u8)>, // (addr, last_value)
+    // JSR/RTS/interrupções (ver callstack.rs)
+    pub call_stack: CallStack,
+
+    // Hits dos watchpoints do Bus (ver watch.rs): (PC da instrução, descrição)
+    pub watch_log: VecDeque<(u16, String)>,
+    pending_break: Option<String>,   // watchpoint no meio da instrução: para na próxima
+
+    // Labels carregados com a ROM (ver symbols.rs)
+    pub symbols: SymbolTable,
 
     // Estatísticas
     pub total_instructions: u64,
@@ -26,145 +103,315 @@ pub struct Debugger {
 
 impl Debugger {
     pub fn new() -> Self {
-        let mut names = ["???"; 256];
-
-        // Opcodes oficiais do 6502
-        names[0x00] = "BRK"; names[0x01] = "ORA izx"; names[0x05] = "ORA zp";
-        names[0x06] = "ASL zp"; names[0x08] = "PHP"; names[0x09] = "ORA imm";
-        names[0x0A] = "ASL acc"; names[0x0D] = "ORA abs"; names[0x0E] = "ASL abs";
-        names[0x10] = "BPL"; names[0x11] = "ORA izy"; names[0x15] = "ORA zpx";
-        names[0x16] = "ASL zpx"; names[0x18] = "CLC"; names[0x19] = "ORA aby";
-        names[0x1D] = "ORA abx"; names[0x1E] = "ASL abx";
-        names[0x20] = "JSR"; names[0x21] = "AND izx"; names[0x24] = "BIT zp";
-        names[0x25] = "AND zp"; names[0x26] = "ROL zp"; names[0x28] = "PLP";
-        names[0x29] = "AND imm"; names[0x2A] = "ROL acc"; names[0x2C] = "BIT abs";
-        names[0x2D] = "AND abs"; names[0x2E] = "ROL abs";
-        names[0x30] = "BMI"; names[0x31] = "AND izy"; names[0x35] = "AND zpx";
-        names[0x36] = "ROL zpx"; names[0x38] = "SEC"; names[0x39] = "AND aby";
-        names[0x3D] = "AND abx"; names[0x3E] = "ROL abx";
-        names[0x40] = "RTI"; names[0x41] = "EOR izx"; names[0x45] = "EOR zp";
-        names[0x46] = "LSR zp"; names[0x48] = "PHA"; names[0x49] = "EOR imm";
-        names[0x4A] = "LSR acc"; names[0x4C] = "JMP abs"; names[0x4D] = "EOR abs";
-        names[0x4E] = "LSR abs";
-        names[0x50] = "BVC"; names[0x51] = "EOR izy"; names[0x55] = "EOR zpx";
-        names[0x56] = "LSR zpx"; names[0x58] = "CLI"; names[0x59] = "EOR aby";
-        names[0x5D] = "EOR abx"; names[0x5E] = "LSR abx";
-        names[0x60] = "RTS"; names[0x61] = "ADC izx"; names[0x65] = "ADC zp";
-        names[0x66] = "ROR zp"; names[0x68] = "PLA"; names[0x69] = "ADC imm";
-        names[0x6A] = "ROR acc"; names[0x6C] = "JMP ind"; names[0x6D] = "ADC abs";
-        names[0x6E] = "ROR abs";
-        names[0x70] = "BVS"; names[0x71] = "ADC izy"; names[0x75] = "ADC zpx";
-        names[0x76] = "ROR zpx"; names[0x78] = "SEI"; names[0x79] = "ADC aby";
-        names[0x7D] = "ADC abx"; names[0x7E] = "ROR abx";
-        names[0x81] = "STA izx"; names[0x84] = "STY zp"; names[0x85] = "STA zp";
-        names[0x86] = "STX zp"; names[0x88] = "DEY"; names[0x8A] = "TXA";
-        names[0x8C] = "STY abs"; names[0x8D] = "STA abs"; names[0x8E] = "STX abs";
-        names[0x90] = "BCC"; names[0x91] = "STA izy"; names[0x94] = "STY zpx";
-        names[0x95] = "STA zpx"; names[0x96] = "STX zpy"; names[0x98] = "TYA";
-        names[0x99] = "STA aby"; names[0x9A] = "TXS"; names[0x9D] = "STA abx";
-        names[0xA0] = "LDY imm"; names[0xA1] = "LDA izx"; names[0xA2] = "LDX imm";
-        names[0xA4] = "LDY zp"; names[0xA5] = "LDA zp"; names[0xA6] = "LDX zp";
-        names[0xA8] = "TAY"; names[0xA9] = "LDA imm"; names[0xAA] = "TAX";
-        names[0xAC] = "LDY abs"; names[0xAD] = "LDA abs"; names[0xAE] = "LDX abs";
-        names[0xB0] = "BCS"; names[0xB1] = "LDA izy"; names[0xB4] = "LDY zpx";
-        names[0xB5] = "LDA zpx"; names[0xB6] = "LDX zpy"; names[0xB8] = "CLV";
-        names[0xB9] = "LDA aby"; names[0xBA] = "TSX"; names[0xBC] = "LDY abx";
-        names[0xBD] = "LDA abx"; names[0xBE] = "LDX aby";
-        names[0xC0] = "CPY imm"; names[0xC1] = "CMP izx"; names[0xC4] = "CPY zp";
-        names[0xC5] = "CMP zp"; names[0xC6] = "DEC zp"; names[0xC8] = "INY";
-        names[0xC9] = "CMP imm"; names[0xCA] = "DEX"; names[0xCC] = "CPY abs";
-        names[0xCD] = "CMP abs"; names[0xCE] = "DEC abs";
-        names[0xD0] = "BNE"; names[0xD1] = "CMP izy"; names[0xD5] = "CMP zpx";
-        names[0xD6] = "DEC zpx"; names[0xD8] = "CLD"; names[0xD9] = "CMP aby";
-        names[0xDD] = "CMP abx"; names[0xDE] = "DEC abx";
-        names[0xE0] = "CPX imm"; names[0xE1] = "SBC izx"; names[0xE4] = "CPX zp";
-        names[0xE5] = "SBC zp"; names[0xE6] = "INC zp"; names[0xE8] = "INX";
-        names[0xE9] = "SBC imm"; names[0xEA] = "NOP"; names[0xEC] = "CPX abs";
-        names[0xED] = "SBC abs"; names[0xEE] = "INC abs";
-        names[0xF0] = "BEQ"; names[0xF1] = "SBC izy"; names[0xF5] = "SBC zpx";
-        names[0xF6] = "INC zpx"; names[0xF8] = "SED"; names[0xF9] = "SBC aby";
-        names[0xFD] = "SBC abx"; names[0xFE] = "INC abx";
-
-        // NOPs ilegais comuns (usados por alguns jogos)
-        for &op in &[0x04, 0x44, 0x64, 0x0C, 0x14, 0x34, 0x54, 0x74, 0xD4, 0xF4,
-                     0x1A, 0x3A, 0x5A, 0x7A, 0xDA, 0xFA, 0x80,
-                     0x1C, 0x3C, 0x5C, 0x7C, 0xDC, 0xFC] {
-            if names[op as usize] == "???" {
-                names[op as usize] = "NOP*";
-            }
-        }
-
         Debugger {
             opcode_count: [0; 256],
-            opcode_names: names,
-            unknown_opcodes: Vec::new(),
+            jam: None,
             trace_enabled: false,
-            trace_log: Vec::new(),
-            trace_max: 1000,
+            trace_log: TraceRing::new(TRACE_RING_SIZE),
+            trace_file: None,
+            trace_cond: TraceCondition::default(),
+            trace_diff: None,
             breakpoints: Vec::new(),
             hit_breakpoint: false,
-            watches: Vec::new(),
+            break_reason: String::new(),
+            step: StepMode::Run,
+            pc_history: VecDeque::with_capacity(PC_HISTORY + 1),
+            last_opcode: 0xEA,
+            last_interrupt: false,
+            call_stack: CallStack::new(),
+            watch_log: VecDeque::with_capacity(WATCH_LOG_SIZE),
+            pending_break: None,
+            symbols: SymbolTable::new(),
             total_instructions: 0,
             total_frames: 0,
         }
     }
 
-    // Chamado antes de cada instrução da CPU
-    pub fn on_instruction(&mut self, cpu: &Cpu6502, bus: &Bus) {
+    // Chamado antes de cada instrução da CPU. true = parar antes de executar
+    pub fn on_instruction(&mut self, cpu: &Cpu6502, bus: &mut Bus) -> bool {
         let pc = cpu.pc;
         let opcode = bus.cpu_read_debug(pc);
+        let interrupt = cpu.interrupt_pending();
 
-        self.opcode_count[opcode as usize] += 1;
-        self.total_instructions += 1;
+        // Pilha de chamadas: vê o que a instrução anterior empilhou/tirou
+        if let Some(&prev) = self.pc_history.back() {
+            self.call_stack.update(prev, self.last_opcode, self.last_interrupt, cpu, bus);
+        }
+
+        // Começo de NMI/IRQ: a instrução em pc não roda agora, só depois do RTI.
+        // Não entra no trace (Nintendulator/Mesen não têm essa linha), nem na
+        // contagem, nem dispara breakpoint/watch de execução duas vezes
+        if !interrupt {
+            self.opcode_count[opcode as usize] += 1;
+            self.total_instructions += 1;
+
+            // JAM trava a CPU: guarda onde foi pra mostrar no overlay
+            if disasm::name(opcode) == "JAM" && self.jam.is_none() {
+                self.jam = Some((opcode, pc));
+                eprintln!("[DEBUG] CPU JAM: opcode 0x{:02X} at PC=0x{:04X}", opcode, pc);
+            }
+
+            // CPU trace. Depois que o diff achou a diferença o ring fica congelado
+            // pra servir de contexto
+            let diff_done = self.trace_diff.as_ref().is_some_and(|d| d.finished());
+            if (self.trace_enabled || self.trace_file.is_some() || self.trace_diff.is_some()) && !diff_done {
+                self.trace(cpu, bus);
+            }
 
-        // Detectar opcodes desconhecidos
-        if self.opcode_names[opcode as usize] == "???" {
-            if self.unknown_opcodes.len() < 100 {
-                let already = self.unknown_opcodes.iter().any(|(op, _)| *op == opcode);
-                if !already {
-                    self.unknown_opcodes.push((opcode, pc));
-                    eprintln!("[DEBUG] Unknown opcode 0x{:02X} at PC=0x{:04X}", opcode, pc);
+            // Watchpoints de execução: o PC dessa instrução ainda não está no histórico
+            if bus.watch.wants(WatchSpace::Cpu, WATCH_EXEC) {
+                bus.watch.check(WatchSpace::Cpu, WATCH_EXEC, pc, opcode);
+                self.watch_hits_at(pc, cpu, bus);
+            }
+        }
+
+        // Breakpoints, watchpoints e stepping
+        if !self.hit_breakpoint {
+            let reason = match self.pending_break.take() {
+                Some(r) => Some(r),
+                None if interrupt => self.check_break(cpu, bus),
+                None => self.check_breakpoints(cpu, bus).or_else(|| self.check_break(cpu, bus)),
+            };
+            if let Some(reason) = reason {
+                self.hit_breakpoint = true;
+                self.break_reason = reason;
+                self.step = StepMode::Run;
+            }
+        }
+        // NMI/IRQ não executa o opcode em pc (step out não pode achar que foi RTS)
+        self.last_opcode = if interrupt { 0x00 } else { opcode };
+        self.last_interrupt = interrupt;
+        self.pc_history.push_back(pc);
+        if self.pc_history.len() > PC_HISTORY + 1 {
+            self.pc_history.pop_front();
+        }
+
+        self.hit_breakpoint
+    }
+
+    // Conta o hit e testa a condição de cada breakpoint nesse PC. Os de log
+    // só imprimem o estado da CPU e seguem
+    fn check_breakpoints(&mut self, cpu: &Cpu6502, bus: &Bus) -> Option<String> {
+        let pc = cpu.pc;
+        let mut reason = None;
+        let prg = bus.prg_offset(pc);
+        for bp in self.breakpoints.iter_mut().filter(|bp| bp.addr == pc && bp.prg.is_none_or(|off| prg == Some(off))) {
+            bp.hits += 1;
+            if let Some(ref cond) = bp.cond {
+                let ctx = EvalContext { addr: pc, hits: bp.hits, ..EvalContext::new(cpu, bus) };
+                if !cond.test(&ctx) {
+                    continue;
                 }
             }
+            let at = self.symbols.describe(bus, pc);
+            let what = match bp.cond {
+                Some(ref cond) => format!("Breakpoint {} [{}] #{}", at, cond, bp.hits),
+                None => format!("Breakpoint {} #{}", at, bp.hits),
+            };
+            if bp.brk {
+                reason.get_or_insert(what);
+            } else {
+                let mut line = String::new();
+                trace::format_line(cpu, bus, &self.symbols, &mut line);
+                eprintln!("[BP] {}  {}", what, line);
+            }
         }
+        reason
+    }
+
+    fn check_break(&self, cpu: &Cpu6502, bus: &Bus) -> Option<String> {
+        let pc = cpu.pc;
+        let returned = matches!(self.last_opcode, 0x60 | 0x40); // RTS/RTI
+        let hit = match self.step {
+            StepMode::Run => false,
+            StepMode::Instruction => true,
+            StepMode::Over { ret, sp } => pc == ret && cpu.stkp >= sp,
+            StepMode::Out { sp } => returned && cpu.stkp > sp,
+            StepMode::Scanline(line) => bus.ppu.scanline != line,
+            StepMode::Frame(frame) => bus.ppu.frame_count != frame,
+            StepMode::RunTo(addr) => pc == addr,
+            StepMode::Line { from, sp } => {
+                sp.is_none_or(|sp| cpu.stkp >= sp)
+                    && self.symbols.source.lookup(bus, pc).is_some_and(|loc| Some(loc) != from)
+            },
+        };
+        hit.then(|| match self.step {
+            StepMode::Scanline(_) => format!("Step scanline ({})", bus.ppu.scanline),
+            StepMode::Frame(_) => format!("Step frame ({})", bus.ppu.frame_count),
+            StepMode::RunTo(addr) => format!("Run to ${:04X}", addr),
+            StepMode::Line { .. } => match self.symbols.source.lookup(bus, pc) {
+                Some(loc) => format!("Step linha {}", self.symbols.source.describe(loc)),
+                None => "Step linha".to_string(),
+            },
+            _ => "Step".to_string(),
+        })
+    }
+
+    // Hits de leitura/escrita que o Bus anotou durante o último ciclo da CPU
+    pub fn on_watch_hits(&mut self, cpu: &Cpu6502, bus: &mut Bus) {
+        let pc = self.pc_history.back().copied().unwrap_or(0);
+        self.watch_hits_at(pc, cpu, bus);
+    }
 
-        // CPU trace
-        if self.trace_enabled {
-            let operand1 = bus.cpu_read_debug(pc.wrapping_add(1));
-            let operand2 = bus.cpu_read_debug(pc.wrapping_add(2));
-            let name = self.opcode_names[opcode as usize];
-            let line = format!(
-                "{:04X}  {:02X} {:02X} {:02X}  {:<10} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
-                pc, opcode, operand1, operand2, name,
-                cpu.a, cpu.x, cpu.y, cpu.status, cpu.stkp
-            );
-            self.trace_log.push(line);
-            if self.trace_log.len() > self.trace_max {
-                self.trace_log.remove(0);
+    // Testa a condição de cada hit; a lista é devolvida vazia pro Bus reaproveitar
+    fn watch_hits_at(&mut self, pc: u16, cpu: &Cpu6502, bus: &mut Bus) {
+        let mut hits = std::mem::take(&mut bus.watch.hits);
+        for hit in hits.drain(..) {
+            let Some(wp) = bus.watch.points.get_mut(hit.index) else { continue };
+            wp.hits += 1;
+            let count = wp.hits;
+            if let Some(ref cond) = bus.watch.points[hit.index].cond {
+                let ctx = EvalContext { value: hit.value, addr: hit.addr, hits: count, ..EvalContext::new(cpu, bus) };
+                if !cond.test(&ctx) {
+                    continue;
+                }
+            }
+
+            let msg = format!("{} #{}", hit.describe(pc), count);
+            eprintln!("[WATCH] {}", msg);
+            if hit.brk && self.pending_break.is_none() && !self.hit_breakpoint {
+                self.pending_break = Some(format!("Watch {}", msg));
             }
+            if self.watch_log.len() >= WATCH_LOG_SIZE {
+                self.watch_log.pop_front();
+            }
+            self.watch_log.push_back((pc, msg));
+        }
+        bus.watch.hits = hits;
+    }
+
+    // Para na próxima instrução (também serve de "break" com o jogo rodando)
+    pub fn step_instruction(&mut self) {
+        self.resume_with(StepMode::Instruction);
+    }
+
+    // Em JSR roda a subrotina inteira; nas outras é igual ao step normal
+    pub fn step_over(&mut self, cpu: &Cpu6502, bus: &Bus) {
+        let d = disasm::decode(bus, cpu.pc);
+        if d.name == "JSR" {
+            self.resume_with(StepMode::Over { ret: d.next_addr(), sp: cpu.stkp });
+        } else {
+            self.resume_with(StepMode::Instruction);
         }
+    }
 
-        // Breakpoints
-        if !self.breakpoints.is_empty() && self.breakpoints.contains(&pc) {
-            self.hit_breakpoint = true;
+    // Roda até a próxima linha de fonte (.dbg do ca65). Sem fonte carregada
+    // vira step de instrução. over = não entra nas subrotinas/interrupções
+    pub fn step_line(&mut self, cpu: &Cpu6502, bus: &Bus, over: bool) {
+        if self.symbols.source.is_empty() {
+            return if over { self.step_over(cpu, bus) } else { self.step_instruction() };
         }
+        let from = self.symbols.source.lookup(bus, cpu.pc);
+        self.resume_with(StepMode::Line { from, sp: over.then_some(cpu.stkp) });
+    }
+
+    pub fn step_out(&mut self, cpu: &Cpu6502) {
+        self.resume_with(StepMode::Out { sp: cpu.stkp });
+    }
+
+    pub fn step_scanline(&mut self, bus: &Bus) {
+        self.resume_with(StepMode::Scanline(bus.ppu.scanline));
+    }
+
+    pub fn step_frame(&mut self, bus: &Bus) {
+        self.resume_with(StepMode::Frame(bus.ppu.frame_count));
+    }
 
-        // Memory watches
-        for watch in &mut self.watches {
-            let val = bus.cpu_read_debug(watch.0);
-            if val != watch.1 {
-                eprintln!("[WATCH] ${:04X}: {:02X} -> {:02X}", watch.0, watch.1, val);
-                watch.1 = val;
+    pub fn run_to(&mut self, addr: u16) {
+        self.resume_with(StepMode::RunTo(addr));
+    }
+
+    pub fn resume(&mut self) {
+        self.resume_with(StepMode::Run);
+    }
+
+    fn resume_with(&mut self, step: StepMode) {
+        self.step = step;
+        self.hit_breakpoint = false;
+        self.break_reason.clear();
+    }
+
+    // true = ficou ligado. Desligar só remove os simples (os que o F9 põe):
+    // os condicionais, de log e os do GDB ficam
+    pub fn toggle_breakpoint(&mut self, addr: u16) -> bool {
+        let plain = |bp: &Breakpoint| bp.addr == addr && bp.prg.is_none() && bp.cond.is_none() && bp.brk && !bp.gdb;
+        if self.breakpoints.iter().any(plain) {
+            self.breakpoints.retain(|bp| !plain(bp));
+            false
+        } else {
+            self.breakpoints.push(Breakpoint::new(addr));
+            true
+        }
+    }
+
+    // Resolve o label (com o banco, se for de ROM) antes de colocar na lista
+    pub fn add_breakpoint(&mut self, mut bp: Breakpoint, bus: &Bus) -> Result<(), String> {
+        if let Some(name) = bp.label.take() {
+            let (addr, prg) = self.symbols.resolve(bus, &name)
+                .ok_or(format!("label desconhecido: {}", name))?;
+            bp.addr = addr;
+            bp.prg = prg;
+        }
+        self.breakpoints.push(bp);
+        Ok(())
+    }
+
+    pub fn has_breakpoint(&self, addr: u16) -> bool {
+        self.breakpoints.iter().any(|bp| bp.addr == addr)
+    }
+
+    fn trace(&mut self, cpu: &Cpu6502, bus: &Bus) {
+        let frame = self.total_frames;
+        if self.trace_cond.stop_frame.is_some_and(|f| frame >= f) && self.trace_file.is_some() {
+            self.stop_trace_file();
+        }
+        if !self.trace_cond.accepts(cpu.pc, frame) {
+            return;
+        }
+
+        let line = self.trace_log.next_slot();
+        trace::format_line(cpu, bus, &self.symbols, line);
+
+        if let Some(ref mut diff) = self.trace_diff {
+            diff.check(&TraceRecord::from_cpu(cpu, bus), line);
+            if let Some(ref m) = diff.mismatch {
+                self.hit_breakpoint = true;
+                self.break_reason = format!("Trace diff: linha {} de {}", m.line, diff.path);
             }
         }
+
+        if let Some(ref mut file) = self.trace_file
+            && let Err(e) = file.write_line(line)
+        {
+            eprintln!("[TRACE] Erro gravando {}: {}", file.path, e);
+            self.trace_file = None;
+        }
+    }
+
+    // Começa a gravar o trace em arquivo (substitui o que estava aberto)
+    pub fn start_trace_file(&mut self, path: &str, cond: TraceCondition) -> std::io::Result<()> {
+        self.stop_trace_file();
+        self.trace_file = Some(TraceFile::create(path)?);
+        self.trace_cond = cond;
+        Ok(())
+    }
+
+    // Fecha o arquivo; devolve quantas linhas foram gravadas
+    pub fn stop_trace_file(&mut self) -> Option<u64> {
+        let mut file = self.trace_file.take()?;
+        if let Err(e) = file.flush() {
+            eprintln!("[TRACE] Erro gravando {}: {}", file.path, e);
+        }
+        eprintln!("[TRACE] {} linhas -> {}", file.lines, file.path);
+        Some(file.lines)
     }
 
     // Relatório de coverage
     pub fn coverage_report(&self) -> String {
         let mut report = String::new();
-        let total_official = self.opcode_names.iter().filter(|n| **n != "???" && **n != "NOP*").count();
-        let used_official = self.opcode_count.iter().enumerate()
-            .filter(|(i, c)| **c > 0 && self.opcode_names[*i] != "???" && self.opcode_names[*i] != "NOP*")
+        let total_official = (0..=255).filter(|&op| disasm::is_official(op)).count();
+        let used_official = (0..=255u8)
+            .filter(|&op| self.opcode_count[op as usize] > 0 && disasm::is_official(op))
             .count();
 
         report.push_str(&format!("=== CPU Coverage: {}/{} opcodes usados ===\n", used_official, total_official));
@@ -172,17 +419,9 @@ impl Debugger {
 
         // Opcodes nunca executados
         report.push_str("Opcodes oficiais NAO usados:\n");
-        for (i, name) in self.opcode_names.iter().enumerate() {
-            if *name != "???" && *name != "NOP*" && self.opcode_count[i] == 0 {
-                report.push_str(&format!("  0x{:02X} {}\n", i, name));
-            }
-        }
-
-        // Opcodes desconhecidos encontrados
-        if !self.unknown_opcodes.is_empty() {
-            report.push_str(&format!("\nOpcodes DESCONHECIDOS encontrados ({}):\n", self.unknown_opcodes.len()));
-            for (op, pc) in &self.unknown_opcodes {
-                report.push_str(&format!("  0x{:02X} at PC=0x{:04X}\n", op, pc));
+        for op in 0..=255u8 {
+            if disasm::is_official(op) && self.opcode_count[op as usize] == 0 {
+                report.push_str(&format!("  0x{:02X} {}\n", op, disasm::label(op)));
             }
         }
 
@@ -192,9 +431,9 @@ impl Debugger {
             .filter(|(_, c)| **c > 0)
             .map(|(i, c)| (i, *c))
             .collect();
-        sorted.sort_by(|a, b| b.1.cmp(&a.1));
+        sorted.sort_by_key(|&(_, c)| std::cmp::Reverse(c));
         for (i, (op, count)) in sorted.iter().take(10).enumerate() {
-            report.push_str(&format!("  {}. 0x{:02X} {} = {} vezes\n", i + 1, op, self.opcode_names[*op], count));
+            report.push_str(&format!("  {}. 0x{:02X} {} = {} vezes\n", i + 1, op, disasm::label(*op as u8), count));
         }
 
         report
@@ -202,11 +441,7 @@ impl Debugger {
 
     // Dump de nametable como texto
     pub fn dump_nametable(&self, bus: &Bus, nt: usize) -> Vec<u8> {
-        let mut data = vec![0u8; 960];
-        for i in 0..960 {
-            data[i] = bus.ppu.nametable[nt][i];
-        }
-        data
+        bus.ppu.nametable[nt][..960].to_vec()
     }
 
     // Dump de paleta
@@ -229,26 +464,20 @@ impl Debugger {
 
     // Verifica se a CPU está presa num loop
     pub fn detect_stuck(&self, cpu: &Cpu6502, bus: &Bus) -> Option<String> {
-        // Ler as próximas instruções e ver se é um loop de 2-3 bytes
-        let pc = cpu.pc;
-        let op = bus.cpu_read_debug(pc);
-
-        // Detectar JMP pra si mesmo
-        if op == 0x4C { // JMP abs
-            let lo = bus.cpu_read_debug(pc.wrapping_add(1)) as u16;
-            let hi = bus.cpu_read_debug(pc.wrapping_add(2)) as u16;
-            let target = (hi << 8) | lo;
-            if target == pc {
-                return Some(format!("CPU stuck: JMP to self at ${:04X}", pc));
-            }
+        if cpu.jammed {
+            let (op, pc) = self.jam.unwrap_or((bus.cpu_read_debug(cpu.pc), cpu.pc));
+            return Some(format!("CPU JAM: opcode ${:02X} at {} (reset pra sair)", op, self.symbols.describe(bus, pc)));
         }
 
-        // Detectar branch loop infinito (2 bytes que volta pra PC)
-        if matches!(op, 0x10 | 0x30 | 0x50 | 0x70 | 0x90 | 0xB0 | 0xD0 | 0xF0) {
-            let offset = bus.cpu_read_debug(pc.wrapping_add(1)) as i8;
-            let target = pc.wrapping_add(2).wrapping_add(offset as u16);
-            if target == pc {
-                return Some(format!("CPU stuck: branch to self at ${:04X} ({})", pc, self.opcode_names[op as usize]));
+        // Instrução que salta pra ela mesma (JMP abs ou branch)
+        let pc = cpu.pc;
+        let d = disasm::decode(bus, pc);
+        if d.target == Some(pc) {
+            if d.mode == AddrMode::Rel {
+                return Some(format!("CPU stuck: branch to self at {} ({})", self.symbols.describe(bus, pc), d.name));
+            }
+            if d.mode == AddrMode::Abs && d.name == "JMP" {
+                return Some(format!("CPU stuck: JMP to self at {}", self.symbols.describe(bus, pc)));
             }
         }
 
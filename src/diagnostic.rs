@@ -1,8 +1,11 @@
 // Diagnóstico do emulador - analisa o estado e identifica problemas
 use crate::bus::Bus;
 use crate::cpu6502::Cpu6502;
+use crate::debug::Debugger;
+use crate::disasm;
 
-pub fn run_diagnostic(cpu: &Cpu6502, bus: &Bus) {
+pub fn run_diagnostic(cpu: &Cpu6502, bus: &Bus, dbg: &Debugger) {
+    let syms = &dbg.symbols;
     println!("\n========== DIAGNOSTICO DO EMULADOR ==========\n");
 
     // 1. Estado da CPU
@@ -21,11 +24,31 @@ pub fn run_diagnostic(cpu: &Cpu6502, bus: &Bus) {
     );
     println!("  Flags: {}", flags);
 
-    // Detectar stuck
-    let op = bus.cpu_read_debug(cpu.pc);
-    let op1 = bus.cpu_read_debug(cpu.pc.wrapping_add(1));
-    let op2 = bus.cpu_read_debug(cpu.pc.wrapping_add(2));
-    println!("  Next: {:02X} {:02X} {:02X}", op, op1, op2);
+    // Próximas instruções
+    let next = disasm::decode_with_cpu(cpu, bus, cpu.pc);
+    let ea = next.effective.map(|a| format!("  @${:04X}", a)).unwrap_or_default();
+    if let Some(name) = syms.lookup(bus, cpu.pc) {
+        println!("  {}:", name);
+    }
+    if let Some(loc) = syms.source.lookup(bus, cpu.pc) {
+        println!("  {}: {}", syms.source.describe(loc), syms.source.line_text(loc.file, loc.line).unwrap_or("").trim());
+    }
+    println!("  Next: {}  {}{}", next.bytes_hex(), next.with_symbols(bus, syms), ea);
+    for d in disasm::disassemble(bus, next.next_addr(), 4) {
+        println!("  ${:04X}: {}  {}", d.addr, d.bytes_hex(), d.with_symbols(bus, syms));
+    }
+
+    // Pilha de chamadas, mais interno primeiro
+    println!("\n[CALL STACK]");
+    if dbg.call_stack.frames.is_empty() {
+        println!("  (vazia)");
+    }
+    for (i, frame) in dbg.call_stack.frames.iter().rev().enumerate() {
+        println!("  #{:<2} {}  ret ${:04X}  SP=${:02X}", i, frame.describe(bus, syms), frame.ret, frame.sp);
+    }
+    for (pc, msg) in &dbg.call_stack.anomalies {
+        println!("  [!] ${:04X}: {}", pc, msg);
+    }
 
     // 2. PPU State
     println!("\n[PPU]");
@@ -61,25 +84,19 @@ pub fn run_diagnostic(cpu: &Cpu6502, bus: &Bus) {
     let most_common = tile_counts.iter().enumerate().max_by_key(|(_, c)| **c).unwrap();
     println!("  Most common tile in NT0: ${:02X} ({}x)", most_common.0, most_common.1);
 
-    // 4. Pattern table content
+    // 4. Pattern table content (CHR visto pelo mapper, bancos atuais)
     println!("\n[PATTERN TABLE]");
-    let mut pt0_nonzero = 0u32;
-    let mut pt1_nonzero = 0u32;
-    for b in &bus.ppu.pattern_table[0] { if *b != 0 { pt0_nonzero += 1; } }
-    for b in &bus.ppu.pattern_table[1] { if *b != 0 { pt1_nonzero += 1; } }
-    println!("  PT0: {}/4096 bytes nonzero", pt0_nonzero);
-    println!("  PT1: {}/4096 bytes nonzero", pt1_nonzero);
-
-    // Verificar se CHR é lido do cartridge
     if let Some(ref cart) = bus.cartridge {
-        // Tentar ler via cartridge
-        let mut cart_nonzero = 0u32;
+        let mut pt_nonzero = [0u32; 2];
         for addr in 0..0x2000u16 {
-            if let Some(b) = cart.cpu_read_chr_debug(addr) {
-                if b != 0 { cart_nonzero += 1; }
+            if let Some(b) = cart.cpu_read_chr_debug(addr) && b != 0 {
+                pt_nonzero[(addr >> 12) as usize] += 1;
             }
         }
-        println!("  Cartridge CHR (via mapper): {}/8192 bytes nonzero", cart_nonzero);
+        println!("  PT0: {}/4096 bytes nonzero", pt_nonzero[0]);
+        println!("  PT1: {}/4096 bytes nonzero", pt_nonzero[1]);
+    } else {
+        println!("  (sem cartucho)");
     }
 
     // 5. Palette
@@ -112,7 +129,7 @@ pub fn run_diagnostic(cpu: &Cpu6502, bus: &Bus) {
     println!("  Visible sprites (Y < 240): {}/64", visible_sprites);
     if visible_sprites > 0 {
         println!("  First 4 sprites:");
-        for i in 0..4.min(64) {
+        for i in 0..4 {
             let y = bus.ppu.oam[i * 4];
             let tile = bus.ppu.oam[i * 4 + 1];
             let attr = bus.ppu.oam[i * 4 + 2];
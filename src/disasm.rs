@@ -0,0 +1,223 @@
+// Disassembler 6502
+//
+// Usa a mesma tabela da CPU (Cpu6502::instrucoes), então nome, modo e
+// tamanho nunca ficam diferentes do que a CPU realmente executa.
+// Tudo lê com cpu_read_debug, sem efeito colateral em registradores.
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::bus::Bus;
+use crate::cpu6502::{AddrMode, Cpu6502, Instruction};
+use crate::symbols::SymbolTable;
+
+fn table() -> &'static [Instruction] {
+    static TABLE: OnceLock<Vec<Instruction>> = OnceLock::new();
+    TABLE.get_or_init(Cpu6502::instrucoes)
+}
+
+pub fn name(opcode: u8) -> &'static str {
+    table()[opcode as usize].name
+}
+
+pub fn mode(opcode: u8) -> AddrMode {
+    table()[opcode as usize].mode
+}
+
+// Tamanho da instrução em bytes (opcode + operando)
+pub fn length(mode: AddrMode) -> u16 {
+    match mode {
+        AddrMode::Imp | AddrMode::Acc => 1,
+        AddrMode::Imm | AddrMode::Zp0 | AddrMode::Zpx | AddrMode::Zpy
+        | AddrMode::Izx | AddrMode::Izy | AddrMode::Rel => 2,
+        AddrMode::Abs | AddrMode::Abx | AddrMode::Aby | AddrMode::Ind => 3,
+    }
+}
+
+pub fn is_official(opcode: u8) -> bool {
+    table()[opcode as usize].official
+}
+
+// Nome com * nos ilegais, como no resto do debugger
+pub fn mnemonic(opcode: u8) -> String {
+    if is_official(opcode) {
+        name(opcode).to_string()
+    } else {
+        format!("{}*", name(opcode))
+    }
+}
+
+// "LDA zpx", "NOP* abs"... pros relatórios de coverage
+pub fn label(opcode: u8) -> String {
+    let m = match mode(opcode) {
+        AddrMode::Imp | AddrMode::Rel => return mnemonic(opcode),
+        AddrMode::Acc => "acc",
+        AddrMode::Imm => "imm",
+        AddrMode::Zp0 => "zp",
+        AddrMode::Zpx => "zpx",
+        AddrMode::Zpy => "zpy",
+        AddrMode::Abs => "abs",
+        AddrMode::Abx => "abx",
+        AddrMode::Aby => "aby",
+        AddrMode::Ind => "ind",
+        AddrMode::Izx => "izx",
+        AddrMode::Izy => "izy",
+    };
+    format!("{} {}", mnemonic(opcode), m)
+}
+
+pub struct Disasm {
+    pub addr: u16,
+    pub opcode: u8,
+    pub name: &'static str,
+    pub mode: AddrMode,
+    pub bytes: Vec<u8>,             // opcode + operando, 1 a 3 bytes
+    pub operand: String,            // "#$10", "$12,X", "($34),Y"...
+    pub effective: Option<u16>,     // endereço que a instrução acessa (None se depende de registrador que não temos)
+    pub target: Option<u16>,        // destino de branch/JMP/JSR
+    pub official: bool,
+}
+
+impl Disasm {
+    pub fn size(&self) -> u16 {
+        self.bytes.len() as u16
+    }
+
+    pub fn next_addr(&self) -> u16 {
+        self.addr.wrapping_add(self.size())
+    }
+
+    // "A9 10   " (largura fixa pra alinhar as colunas)
+    pub fn bytes_hex(&self) -> String {
+        let hex: Vec<String> = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        format!("{:<8}", hex.join(" "))
+    }
+}
+
+impl Disasm {
+    // Operando com o endereço trocado pelo label, se tiver: "$0300,X" -> "buffer,X"
+    pub fn symbolic_operand(&self, bus: &Bus, syms: &SymbolTable) -> String {
+        let lo = self.bytes.get(1).copied().unwrap_or(0);
+        let word = (self.bytes.get(2).copied().unwrap_or(0) as u16) << 8 | lo as u16;
+        let (addr, hex) = match self.mode {
+            AddrMode::Zp0 | AddrMode::Zpx | AddrMode::Zpy | AddrMode::Izx | AddrMode::Izy => {
+                (lo as u16, format!("${:02X}", lo))
+            },
+            AddrMode::Abs | AddrMode::Abx | AddrMode::Aby | AddrMode::Ind => (word, format!("${:04X}", word)),
+            AddrMode::Rel => match self.target {
+                Some(t) => (t, format!("${:04X}", t)),
+                None => return self.operand.clone(),
+            },
+            _ => return self.operand.clone(),
+        };
+        match syms.lookup(bus, addr) {
+            Some(name) => self.operand.replacen(&hex, name, 1),
+            None => self.operand.clone(),
+        }
+    }
+
+    // Igual ao Display, com labels
+    pub fn with_symbols(&self, bus: &Bus, syms: &SymbolTable) -> String {
+        self.render(&self.symbolic_operand(bus, syms))
+    }
+
+    fn render(&self, operand: &str) -> String {
+        let star = if self.official { "" } else { "*" };
+        if operand.is_empty() {
+            format!("{}{}", self.name, star)
+        } else {
+            format!("{}{} {}", self.name, star, operand)
+        }
+    }
+}
+
+impl fmt::Display for Disasm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(&self.operand))
+    }
+}
+
+// Decodifica sem registradores: o endereço efetivo só sai nos modos que não
+// dependem de X/Y (zp, abs, JMP indireto)
+pub fn decode(bus: &Bus, addr: u16) -> Disasm {
+    decode_inner(bus, addr, None)
+}
+
+// Decodifica com os registradores atuais, calculando o endereço efetivo dos
+// modos indexados/indiretos. Só faz sentido no PC atual
+pub fn decode_with_cpu(cpu: &Cpu6502, bus: &Bus, addr: u16) -> Disasm {
+    decode_inner(bus, addr, Some(cpu))
+}
+
+// count instruções a partir de addr
+pub fn disassemble(bus: &Bus, addr: u16, count: usize) -> Vec<Disasm> {
+    let mut out = Vec::with_capacity(count);
+    let mut pc = addr;
+    for _ in 0..count {
+        let d = decode(bus, pc);
+        pc = d.next_addr();
+        out.push(d);
+    }
+    out
+}
+
+fn decode_inner(bus: &Bus, addr: u16, cpu: Option<&Cpu6502>) -> Disasm {
+    let opcode = bus.cpu_read_debug(addr);
+    let ins = &table()[opcode as usize];
+    let mode = ins.mode;
+    let len = length(mode);
+    let bytes: Vec<u8> = (0..len).map(|i| bus.cpu_read_debug(addr.wrapping_add(i))).collect();
+    let lo = bytes.get(1).copied().unwrap_or(0);
+    let hi = bytes.get(2).copied().unwrap_or(0);
+    let word = (hi as u16) << 8 | lo as u16;
+
+    // Ponteiro de 16 bits na zero page (dá a volta em $FF)
+    let zp_ptr = |p: u8| {
+        bus.cpu_read_debug(p as u16) as u16 | (bus.cpu_read_debug(p.wrapping_add(1) as u16) as u16) << 8
+    };
+    let x = cpu.map(|c| c.x);
+    let y = cpu.map(|c| c.y);
+
+    let (operand, effective) = match mode {
+        AddrMode::Imp => (String::new(), None),
+        AddrMode::Acc => ("A".to_string(), None),
+        AddrMode::Imm => (format!("#${:02X}", lo), None),
+        AddrMode::Zp0 => (format!("${:02X}", lo), Some(lo as u16)),
+        AddrMode::Zpx => (format!("${:02X},X", lo), x.map(|x| lo.wrapping_add(x) as u16)),
+        AddrMode::Zpy => (format!("${:02X},Y", lo), y.map(|y| lo.wrapping_add(y) as u16)),
+        AddrMode::Abs => (format!("${:04X}", word), Some(word)),
+        AddrMode::Abx => (format!("${:04X},X", word), x.map(|x| word.wrapping_add(x as u16))),
+        AddrMode::Aby => (format!("${:04X},Y", word), y.map(|y| word.wrapping_add(y as u16))),
+        AddrMode::Ind => {
+            // Bug do 6502: o byte alto não cruza a página
+            let hi_addr = (word & 0xFF00) | (word.wrapping_add(1) & 0x00FF);
+            let target = bus.cpu_read_debug(word) as u16 | (bus.cpu_read_debug(hi_addr) as u16) << 8;
+            (format!("(${:04X})", word), Some(target))
+        },
+        AddrMode::Izx => (format!("(${:02X},X)", lo), x.map(|x| zp_ptr(lo.wrapping_add(x)))),
+        AddrMode::Izy => (format!("(${:02X}),Y", lo), y.map(|y| zp_ptr(lo).wrapping_add(y as u16))),
+        AddrMode::Rel => {
+            let target = addr.wrapping_add(2).wrapping_add(lo as i8 as u16);
+            (format!("${:04X}", target), None)
+        },
+    };
+
+    let target = match (mode, ins.name) {
+        (AddrMode::Rel, _) => Some(addr.wrapping_add(2).wrapping_add(lo as i8 as u16)),
+        (AddrMode::Abs, "JMP") | (AddrMode::Abs, "JSR") | (AddrMode::Ind, "JMP") => effective,
+        _ => None,
+    };
+    // Pra JMP/JSR o "efetivo" é o destino, não um acesso à memória
+    let effective = if target.is_some() { None } else { effective };
+
+    Disasm {
+        addr,
+        opcode,
+        name: ins.name,
+        mode,
+        bytes,
+        operand,
+        effective,
+        target,
+        official: is_official(opcode),
+    }
+}
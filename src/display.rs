@@ -4,12 +4,20 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
 use winit::event::{WindowEvent, ElementState, MouseButton};
-use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
 use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::window::{Fullscreen, Window, WindowAttributes, WindowId};
 use wgpu::util::DeviceExt;
 
-use crate::{font, nes::Nes, ui::Ui};
+use crate::{font, ui::Ui};
+use rnfe::Nes;
+use rnfe::disasm;
+use rnfe::debug::Breakpoint;
+use rnfe::console::Console;
+use rnfe::gdb::GdbStub;
+use rnfe::watch::Watchpoint;
+use rnfe::rewind::Rewind;
+use rnfe::Region;
 
 const NES_WIDTH: u32 = 256;
 const NES_HEIGHT: u32 = 240;
@@ -399,7 +407,23 @@ impl GpuState {
     }
 }
 
-const FRAME_DURATION: Duration = Duration::from_nanos(16_639_267); // ~60.0988 Hz (NTSC)
+// Opções da linha de comando que valem pra janela toda (inclusive ROMs abertas depois)
+pub struct Options {
+    pub rewind_secs: u32,        // 0 desliga
+    pub region: Option<Region>,  // None = detectar pelo header/nome
+    pub watches: Vec<Watchpoint>, // --watch, valem pra toda ROM carregada
+    pub breaks: Vec<Breakpoint>,  // --break
+    pub gdb_port: Option<u16>,    // --gdb, stub do GDB em 127.0.0.1
+    pub symbols: Vec<String>,     // --symbols, além dos que estão do lado da ROM
+    pub console: bool,            // --console, debugger pelo stdin
+}
+const REWIND_INTERVAL: u32 = 2; // snapshot a cada 2 frames
+const SAV_FLUSH_INTERVAL: Duration = Duration::from_secs(3);
+const DISASM_LINES: usize = 8;   // linhas de disassembly no overlay (F3)
+const SOURCE_CONTEXT: u32 = 2;   // linhas de fonte antes/depois da atual quando parado
+const PANEL_COLS: usize = 58;    // corta linha comprida (fonte, pilha) pra caber no painel
+const STACK_LINES: usize = 4;    // frames da pilha de chamadas mostrados quando parado
+const DEBUG_KEYS_HELP: &str = "F7 step  F8 over  Sh+F8 out  F9 bp  F10 run-to  F12 cont";
 
 pub struct App {
     win: Option<&'static Window>,
@@ -414,15 +438,26 @@ pub struct App {
     menu_fb: Vec<u8>,
     paused: bool,
     debug_overlay: bool,
+    debug_cursor: usize,    // linha selecionada no disassembly (0 = PC)
     fps_counter: u32,
     fps_timer: Instant,
     fps_display: u32,
     toast_msg: String,
     toast_until: Instant,
+    modifiers: ModifiersState,
+    rewind: Rewind,
+    rewinding: bool,
+    region: Option<Region>,
+    watches: Vec<Watchpoint>,
+    breaks: Vec<Breakpoint>,
+    symbol_files: Vec<String>,
+    gdb: Option<GdbStub>,
+    console: Option<Console>,
+    sav_timer: Instant,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(opts: &Options) -> Self {
         Self {
             win: None, gpu: None, nes: None,
             framebuffer: vec![0u8; (NES_WIDTH * NES_HEIGHT * 4) as usize],
@@ -434,15 +469,27 @@ impl App {
             menu_fb: Vec::new(),
             paused: false,
             debug_overlay: false,
+            debug_cursor: 0,
             fps_counter: 0,
             fps_timer: Instant::now(),
             fps_display: 0,
             toast_msg: String::new(),
             toast_until: Instant::now(),
+            modifiers: ModifiersState::empty(),
+            rewind: Rewind::new(opts.rewind_secs, REWIND_INTERVAL),
+            rewinding: false,
+            region: opts.region,
+            watches: opts.watches.clone(),
+            breaks: opts.breaks.clone(),
+            symbol_files: opts.symbols.clone(),
+            gdb: Self::bind_gdb(opts),
+            console: opts.console.then(Console::spawn),
+            sav_timer: Instant::now(),
         }
     }
 
-    pub fn new_with_nes(mut nes: Box<Nes>) -> Self {
+    pub fn new_with_nes(mut nes: Box<Nes>, opts: &Options) -> Self {
+        Self::apply_debug_options(&mut nes, &opts.symbols, &opts.watches, &opts.breaks);
         let audio_buffer = Arc::new(Mutex::new(VecDeque::with_capacity(8192)));
         let stream = Self::init_audio(audio_buffer.clone(), &mut nes);
         Self {
@@ -456,11 +503,22 @@ impl App {
             menu_fb: Vec::new(),
             paused: false,
             debug_overlay: false,
+            debug_cursor: 0,
             fps_counter: 0,
             fps_timer: Instant::now(),
             fps_display: 0,
             toast_msg: String::new(),
             toast_until: Instant::now(),
+            modifiers: ModifiersState::empty(),
+            rewind: Rewind::new(opts.rewind_secs, REWIND_INTERVAL),
+            rewinding: false,
+            region: opts.region,
+            watches: opts.watches.clone(),
+            breaks: opts.breaks.clone(),
+            symbol_files: opts.symbols.clone(),
+            gdb: Self::bind_gdb(opts),
+            console: opts.console.then(Console::spawn),
+            sav_timer: Instant::now(),
         }
     }
 
@@ -493,14 +551,82 @@ impl App {
         Some(stream)
     }
 
+    // --symbols/--watch/--break da linha de comando. Símbolos primeiro, os breakpoints podem ser por label
+    fn apply_debug_options(nes: &mut Nes, symbols: &[String], watches: &[Watchpoint], breaks: &[Breakpoint]) {
+        for path in symbols {
+            match nes.debugger.symbols.load(path) {
+                Ok(n) => println!("{} símbolos de {}", n, path),
+                Err(e) => eprintln!("Erro ao carregar símbolos '{}': {}", path, e),
+            }
+        }
+        for wp in watches {
+            nes.bus.watch.add(wp.clone());
+        }
+        for bp in breaks {
+            if let Err(e) = nes.debugger.add_breakpoint(bp.clone(), &nes.bus) {
+                eprintln!("--break: {}", e);
+            }
+        }
+    }
+
+    fn bind_gdb(opts: &Options) -> Option<GdbStub> {
+        let port = opts.gdb_port?;
+        match GdbStub::bind(port) {
+            Ok(gdb) => {
+                println!("GDB: target remote 127.0.0.1:{}", gdb.port());
+                Some(gdb)
+            },
+            Err(e) => {
+                eprintln!("Erro ao abrir a porta {} do GDB: {}", port, e);
+                None
+            }
+        }
+    }
+
     fn toast(&mut self, msg: &str) {
         self.toast_msg = msg.to_string();
         self.toast_until = Instant::now() + Duration::from_secs(2);
     }
 
+    // Slots 0-9 ficam do lado da ROM: jogo.nes -> jogo.ss0 .. jogo.ss9
+    fn state_path(nes: &Nes, slot: usize) -> Option<std::path::PathBuf> {
+        let rom = nes.bus.cartridge.as_ref()?.rom_path.as_ref()?;
+        Some(std::path::Path::new(rom).with_extension(format!("ss{}", slot)))
+    }
+
+    fn save_slot(nes: &Nes, slot: usize) -> String {
+        let Some(path) = Self::state_path(nes, slot) else { return "ROM sem arquivo, sem save state".into() };
+        match std::fs::write(&path, nes.save_state()) {
+            Ok(()) => format!("State salvo no slot {}", slot),
+            Err(e) => {
+                eprintln!("Erro ao salvar {}: {}", path.display(), e);
+                format!("Erro ao salvar slot {}", slot)
+            }
+        }
+    }
+
+    fn load_slot(nes: &mut Nes, slot: usize) -> String {
+        let Some(path) = Self::state_path(nes, slot) else { return "ROM sem arquivo, sem save state".into() };
+        let data = match std::fs::read(&path) {
+            Ok(d) => d,
+            Err(_) => return format!("Slot {} vazio", slot),
+        };
+        match nes.load_state(&data) {
+            Ok(()) => format!("State carregado do slot {}", slot),
+            Err(e) => {
+                eprintln!("Erro ao carregar {}: {}", path.display(), e);
+                format!("Slot {}: {}", slot, e)
+            }
+        }
+    }
+
     fn open_rom(&mut self) {
         if let Some(path) = crate::pick_rom() {
-            if let Some(mut new_nes) = crate::load_rom(&path) {
+            // Salvar o .sav da ROM atual antes (pode ser a mesma ROM de novo)
+            if let Some(ref mut old_nes) = self.nes {
+                old_nes.flush_sav();
+            }
+            if let Some(mut new_nes) = crate::load_rom(&path, self.region) {
                 // Configurar audio
                 if self._audio_stream.is_none() {
                     self._audio_stream = Self::init_audio(self.audio_buffer.clone(), &mut new_nes);
@@ -510,9 +636,19 @@ impl App {
                 // Preservar estado do debugger
                 if let Some(ref old_nes) = self.nes {
                     new_nes.debugger.trace_enabled = old_nes.debugger.trace_enabled;
+                    new_nes.debugger.breakpoints = old_nes.debugger.breakpoints.clone();
+                    for wp in &old_nes.bus.watch.points {
+                        new_nes.bus.watch.add(Watchpoint { hits: 0, ..wp.clone() });
+                    }
+                    for bp in &mut new_nes.debugger.breakpoints {
+                        bp.hits = 0;
+                    }
+                } else {
+                    Self::apply_debug_options(&mut new_nes, &self.symbol_files, &self.watches, &self.breaks);
                 }
                 self.nes = Some(new_nes);
                 self.paused = false;
+                self.rewind.clear();
                 if let Ok(mut buf) = self.audio_buffer.lock() {
                     buf.clear();
                 }
@@ -524,6 +660,14 @@ impl App {
         let Some(gpu) = self.gpu.as_mut() else { return };
 
         if let Some(ref mut nes) = self.nes {
+            // .sav sujo vai pro disco a cada poucos segundos (crash perde pouco)
+            if self.sav_timer.elapsed() >= SAV_FLUSH_INTERVAL {
+                self.sav_timer = Instant::now();
+                if nes.bus.cartridge.as_ref().is_some_and(|c| c.sram_dirty()) {
+                    nes.flush_sav();
+                }
+            }
+
             if self.paused {
                 // Tela de pausa com menu
                 let mw = gpu.menu_w;
@@ -562,31 +706,61 @@ impl App {
 
             // Frame timing
             let elapsed = self.last_frame.elapsed();
-            if elapsed < FRAME_DURATION {
-                std::thread::sleep(FRAME_DURATION - elapsed);
+            // ~60.0988 Hz (NTSC) ou ~50.007 Hz (PAL/Dendy)
+            let frame_duration = Duration::from_secs_f64(1.0 / nes.region.frame_rate());
+            if elapsed < frame_duration {
+                std::thread::sleep(frame_duration - elapsed);
             }
             self.last_frame = Instant::now();
 
-            loop {
-                nes.clock();
-                if nes.bus.ppu.frame_complete {
-                    nes.bus.ppu.frame_complete = false;
-                    break;
+            if let Some(ref mut gdb) = self.gdb {
+                gdb.poll(nes);
+            }
+            if let Some(ref mut console) = self.console {
+                console.poll(nes);
+                if console.closed() {
+                    self.console = None;
+                }
+            }
+
+            if self.rewinding {
+                // Volta um snapshot e roda 1 frame só pra ter imagem; áudio mudo.
+                // No fim do histórico fica parado na última imagem
+                if self.rewind.step_back(nes) {
+                    self.toast_msg = "<< Rewind".into();
+                    nes.run_frame();
+                } else {
+                    self.toast_msg = "Rewind: fim do histórico".into();
+                }
+                self.toast_until = Instant::now() + Duration::from_millis(300);
+                nes.drain_audio();
+                if let Ok(mut buf) = self.audio_buffer.lock() {
+                    buf.clear();
+                }
+            } else if !nes.debugger.hit_breakpoint {
+                // Frame cortado por breakpoint/step não vira snapshot
+                if nes.run_frame() {
+                    self.rewind.on_frame(nes);
+                }
+                // Parou num breakpoint/step: mostra o overlay com o disassembly
+                if nes.debugger.hit_breakpoint {
+                    self.debug_overlay = true;
+                    self.debug_cursor = 0;
+                    self.toast_msg = nes.debugger.break_reason.clone();
+                    self.toast_until = Instant::now() + Duration::from_secs(2);
                 }
             }
 
             // Enviar samples de audio
-            if !nes.bus.apu.sample_buffer.is_empty() {
+            let samples = if self.rewinding { Vec::new() } else { nes.drain_audio() };
+            if !samples.is_empty() {
                 if let Ok(mut buf) = self.audio_buffer.lock() {
-                    for &s in &nes.bus.apu.sample_buffer {
-                        buf.push_back(s);
-                    }
+                    buf.extend(samples);
                     // Limitar buffer pra não acumular latência
                     while buf.len() > 4096 {
                         buf.pop_front();
                     }
                 }
-                nes.bus.apu.sample_buffer.clear();
             }
 
             // FPS counter
@@ -598,8 +772,9 @@ impl App {
             }
 
             // PPU screen (RGB) -> framebuffer (RGBA)
+            let screen = nes.framebuffer();
             for i in 0..(NES_WIDTH * NES_HEIGHT) as usize {
-                let color = nes.bus.ppu.screen[i];
+                let color = screen[i];
                 let fb_idx = i * 4;
                 self.framebuffer[fb_idx] = color[0];
                 self.framebuffer[fb_idx + 1] = color[1];
@@ -620,6 +795,7 @@ impl App {
                 let gray = [200u8, 200, 200, 255];
                 let yellow = [255u8, 255, 80, 255];
                 let red = [255u8, 80, 80, 255];
+                let dim = [120u8, 120, 120, 255];
                 let sz = 13.0f32;
 
                 let mut y = 8i32;
@@ -627,12 +803,34 @@ impl App {
 
                 // Stuck detection
                 let stuck = nes.debugger.detect_stuck(&nes.cpu, &nes.bus);
-                // Unknown opcodes
-                let has_unknown = !nes.debugger.unknown_opcodes.is_empty();
                 if stuck.is_some() { panel_h += 18; }
-                if has_unknown { panel_h += 18; }
+                let halted = nes.debugger.hit_breakpoint;
+                if halted { panel_h += 36; }
 
-                self.ui.fill_rect_pub(&mut self.menu_fb, mw, mh, 4, 4, 420, panel_h, bg);
+                // Disassembly em volta do PC: últimas instruções executadas + as próximas
+                let mut history: Vec<u16> = nes.debugger.pc_history.iter().copied().collect();
+                if history.last() == Some(&nes.cpu.pc) {
+                    history.pop();
+                }
+                let mut listing: Vec<disasm::Disasm> = history.iter().map(|&a| disasm::decode(&nes.bus, a)).collect();
+                let pc_line = listing.len();
+                listing.extend(disasm::disassemble(&nes.bus, nes.cpu.pc, DISASM_LINES));
+                let syms = &nes.debugger.symbols;
+                let labels = listing.iter().filter(|d| syms.lookup(&nes.bus, d.addr).is_some()).count();
+                panel_h += 18 * (listing.len() + labels) as i32 + 6;
+
+                // Fonte em volta do PC, se veio um .dbg com as linhas
+                let source = if halted { syms.source.lookup(&nes.bus, nes.cpu.pc) } else { None };
+                let context = source.map(|loc| syms.source.context(loc, SOURCE_CONTEXT, SOURCE_CONTEXT)).unwrap_or_default();
+                if source.is_some() { panel_h += 18 * (1 + context.len()) as i32 + 6; }
+
+                // Pilha de chamadas (mais interno em cima) e a última quebra de JSR/RTS
+                let calls = &nes.debugger.call_stack;
+                let frames: Vec<_> = if halted { calls.frames.iter().rev().take(STACK_LINES).collect() } else { Vec::new() };
+                let anomaly = if halted { calls.anomalies.back() } else { None };
+                if halted { panel_h += 18 * (1 + frames.len() + anomaly.is_some() as usize) as i32 + 6; }
+
+                self.ui.fill_rect_pub(&mut self.menu_fb, mw, mh, 4, 4, 460, panel_h, bg);
 
                 let fps = format!("FPS: {}  Instrs: {}", self.fps_display, nes.debugger.total_instructions);
                 self.ui.draw_text(&mut self.menu_fb, mw, mh, &fps, sz, 12, y, green);
@@ -650,10 +848,11 @@ impl App {
                 y += 18;
 
                 // Opcodes coverage
-                let used = nes.debugger.opcode_count.iter().enumerate()
-                    .filter(|(i, c)| **c > 0 && nes.debugger.opcode_names[*i] != "???" && nes.debugger.opcode_names[*i] != "NOP*")
+                let used = (0..=255u8)
+                    .filter(|&op| nes.debugger.opcode_count[op as usize] > 0 && disasm::is_official(op))
                     .count();
-                let coverage = format!("Coverage: {}/56 opcodes  F4=report  F5=trace", used);
+                let total = (0..=255u8).filter(|&op| disasm::is_official(op)).count();
+                let coverage = format!("Coverage: {}/{} opcodes  F4=report  F5=trace", used, total);
                 self.ui.draw_text(&mut self.menu_fb, mw, mh, &coverage, sz, 12, y, gray);
                 y += 18;
 
@@ -662,10 +861,59 @@ impl App {
                     y += 18;
                 }
 
-                if has_unknown {
-                    let unk = format!("Unknown opcodes: {:?}",
-                        nes.debugger.unknown_opcodes.iter().map(|(op, _)| format!("${:02X}", op)).collect::<Vec<_>>());
-                    self.ui.draw_text(&mut self.menu_fb, mw, mh, &unk, sz, 12, y, yellow);
+                if halted {
+                    let msg = format!("PARADO: {}", nes.debugger.break_reason);
+                    self.ui.draw_text(&mut self.menu_fb, mw, mh, &msg, sz, 12, y, red);
+                    y += 18;
+                    self.ui.draw_text(&mut self.menu_fb, mw, mh, DEBUG_KEYS_HELP, sz, 12, y, gray);
+                    y += 18;
+                }
+
+                if let Some(loc) = source {
+                    y += 6;
+                    let title = format!("{}  Alt+F7 linha  Alt+F8 linha over", syms.source.describe(loc));
+                    self.ui.draw_text(&mut self.menu_fb, mw, mh, &title, sz, 12, y, green);
+                    y += 18;
+                    for &(n, text) in &context {
+                        let mark = if n == loc.line { '>' } else { ' ' };
+                        let line: String = format!("{}{:5}  {}", mark, n, text).chars().take(PANEL_COLS).collect();
+                        let color = if n == loc.line { yellow } else { gray };
+                        self.ui.draw_text(&mut self.menu_fb, mw, mh, &line, sz, 12, y, color);
+                        y += 18;
+                    }
+                }
+
+                if halted {
+                    y += 6;
+                    let title = format!("Call stack: {} frame(s)", calls.frames.len());
+                    self.ui.draw_text(&mut self.menu_fb, mw, mh, &title, sz, 12, y, green);
+                    y += 18;
+                    for frame in &frames {
+                        let line: String = format!("  {}  ret ${:04X}", frame.describe(&nes.bus, syms), frame.ret).chars().take(PANEL_COLS).collect();
+                        self.ui.draw_text(&mut self.menu_fb, mw, mh, &line, sz, 12, y, gray);
+                        y += 18;
+                    }
+                    if let Some((pc, msg)) = anomaly {
+                        let line: String = format!("  ! ${:04X} {}", pc, msg).chars().take(PANEL_COLS).collect();
+                        self.ui.draw_text(&mut self.menu_fb, mw, mh, &line, sz, 12, y, red);
+                        y += 18;
+                    }
+                }
+
+                // > = PC, * = breakpoint, - = cursor (F9/F10)
+                y += 6;
+                for (i, d) in listing.iter().enumerate() {
+                    if let Some(name) = syms.lookup(&nes.bus, d.addr) {
+                        self.ui.draw_text(&mut self.menu_fb, mw, mh, &format!("    {}:", name), sz, 12, y, green);
+                        y += 18;
+                    }
+                    let pc = if i == pc_line { '>' } else { ' ' };
+                    let bp = if nes.debugger.has_breakpoint(d.addr) { '*' } else { ' ' };
+                    let cur = if halted && i == pc_line + self.debug_cursor { '-' } else { ' ' };
+                    let line = format!("{}{}{} {:04X}  {} {}", pc, bp, cur, d.addr, d.bytes_hex(), d.with_symbols(&nes.bus, syms));
+                    let color = if i == pc_line { yellow } else if i < pc_line { dim } else { gray };
+                    self.ui.draw_text(&mut self.menu_fb, mw, mh, &line, sz, 12, y, color);
+                    y += 18;
                 }
 
                 has_overlay = true;
@@ -750,6 +998,12 @@ impl App {
 }
 
 impl ApplicationHandler for App {
+    fn exiting(&mut self, _el: &ActiveEventLoop) {
+        if let Some(ref mut nes) = self.nes {
+            nes.flush_sav();
+        }
+    }
+
     fn resumed(&mut self, el: &ActiveEventLoop) {
         if self.win.is_some() { return; }
         let attrs = WindowAttributes::default()
@@ -802,6 +1056,7 @@ impl ApplicationHandler for App {
                     }
                 }
             }
+            WindowEvent::ModifiersChanged(m) => self.modifiers = m.state(),
             WindowEvent::KeyboardInput { event, .. } => {
                 if let Some(ref mut nes) = self.nes {
                     let pressed = event.state == ElementState::Pressed;
@@ -819,6 +1074,25 @@ impl ApplicationHandler for App {
                     if let Some(b) = bit {
                         if pressed { nes.bus.controller[0] |= b; } else { nes.bus.controller[0] &= !b; }
                     }
+                    // Backspace segurado = rewind
+                    if event.physical_key == PhysicalKey::Code(KeyCode::Backspace) {
+                        self.rewinding = pressed && self.rewind.max_steps > 0;
+                    }
+                    // 0-9 carrega, Shift+0-9 salva
+                    if pressed && !event.repeat
+                        && let PhysicalKey::Code(code) = event.physical_key
+                        && let Some(slot) = digit_slot(code) {
+                        let msg = if self.modifiers.shift_key() {
+                            Self::save_slot(nes, slot)
+                        } else {
+                            Self::load_slot(nes, slot)
+                        };
+                        self.toast_msg = msg;
+                        self.toast_until = Instant::now() + Duration::from_secs(2);
+                        if let Ok(mut buf) = self.audio_buffer.lock() {
+                            buf.clear();
+                        }
+                    }
                     if pressed {
                         match event.physical_key {
                             PhysicalKey::Code(KeyCode::Escape) => { self.paused = !self.paused; },
@@ -840,15 +1114,61 @@ impl ApplicationHandler for App {
                                 println!("CPU Trace: {}", if nes.debugger.trace_enabled { "ON" } else { "OFF" });
                                 if !nes.debugger.trace_enabled && !nes.debugger.trace_log.is_empty() {
                                     println!("--- Last {} instructions ---", nes.debugger.trace_log.len());
-                                    for line in nes.debugger.trace_log.iter().rev().take(20).rev() {
+                                    for line in nes.debugger.trace_log.recent(20) {
                                         println!("{}", line);
                                     }
                                 }
                                 self.toast_msg = if nes.debugger.trace_enabled { "Trace ON".into() } else { "Trace OFF -> terminal".into() };
                                 self.toast_until = Instant::now() + Duration::from_secs(2);
                             }
+                            // Debugger: F7 step (Shift = scanline, Ctrl = frame, Alt = linha de fonte),
+                            // F8 step over (Shift = step out, Alt = linha de fonte por cima), F9 breakpoint no cursor, F10 roda até o cursor,
+                            // F12 continua/para, PageUp/PageDown movem o cursor
+                            PhysicalKey::Code(KeyCode::F7) => {
+                                if self.modifiers.shift_key() {
+                                    nes.debugger.step_scanline(&nes.bus);
+                                } else if self.modifiers.control_key() {
+                                    nes.debugger.step_frame(&nes.bus);
+                                } else if self.modifiers.alt_key() {
+                                    nes.debugger.step_line(&nes.cpu, &nes.bus, false);
+                                } else {
+                                    nes.debugger.step_instruction();
+                                }
+                            }
+                            PhysicalKey::Code(KeyCode::F8) => {
+                                if self.modifiers.shift_key() {
+                                    nes.debugger.step_out(&nes.cpu);
+                                } else if self.modifiers.alt_key() {
+                                    nes.debugger.step_line(&nes.cpu, &nes.bus, true);
+                                } else {
+                                    nes.debugger.step_over(&nes.cpu, &nes.bus);
+                                }
+                            }
+                            PhysicalKey::Code(KeyCode::F9) => {
+                                let addr = cursor_addr(nes, self.debug_cursor);
+                                let on = nes.debugger.toggle_breakpoint(addr);
+                                self.toast(&format!("Breakpoint ${:04X} {}", addr, if on { "ON" } else { "OFF" }));
+                            }
+                            PhysicalKey::Code(KeyCode::F10) => {
+                                let addr = cursor_addr(nes, self.debug_cursor);
+                                nes.debugger.run_to(addr);
+                                self.debug_cursor = 0;
+                            }
+                            PhysicalKey::Code(KeyCode::F12) => {
+                                if nes.debugger.hit_breakpoint {
+                                    nes.debugger.resume();
+                                } else {
+                                    nes.debugger.step_instruction();
+                                }
+                            }
+                            PhysicalKey::Code(KeyCode::PageUp) => {
+                                self.debug_cursor = self.debug_cursor.saturating_sub(1);
+                            }
+                            PhysicalKey::Code(KeyCode::PageDown) => {
+                                self.debug_cursor = (self.debug_cursor + 1).min(DISASM_LINES - 1);
+                            }
                             PhysicalKey::Code(KeyCode::F6) => {
-                                crate::diagnostic::run_diagnostic(&nes.cpu, &nes.bus);
+                                rnfe::diagnostic::run_diagnostic(&nes.cpu, &nes.bus, &nes.debugger);
                                 self.toast_msg = "Diagnostic -> terminal".into();
                                 self.toast_until = Instant::now() + Duration::from_secs(2);
                             }
@@ -895,12 +1215,27 @@ impl ApplicationHandler for App {
     }
 }
 
-pub fn run() -> Result<(), winit::error::EventLoopError> {
+// Endereço da linha do cursor no disassembly (contando a partir do PC)
+fn cursor_addr(nes: &Nes, cursor: usize) -> u16 {
+    disasm::disassemble(&nes.bus, nes.cpu.pc, cursor + 1).last().map_or(nes.cpu.pc, |d| d.addr)
+}
+
+fn digit_slot(code: KeyCode) -> Option<usize> {
+    match code {
+        KeyCode::Digit0 => Some(0), KeyCode::Digit1 => Some(1), KeyCode::Digit2 => Some(2),
+        KeyCode::Digit3 => Some(3), KeyCode::Digit4 => Some(4), KeyCode::Digit5 => Some(5),
+        KeyCode::Digit6 => Some(6), KeyCode::Digit7 => Some(7), KeyCode::Digit8 => Some(8),
+        KeyCode::Digit9 => Some(9),
+        _ => None,
+    }
+}
+
+pub fn run(opts: &Options) -> Result<(), winit::error::EventLoopError> {
     let el: EventLoop<()> = EventLoop::new()?;
-    el.run_app(&mut App::new())
+    el.run_app(&mut App::new(opts))
 }
 
-pub fn run_with_nes(nes: Box<Nes>) -> Result<(), winit::error::EventLoopError> {
+pub fn run_with_nes(nes: Box<Nes>, opts: &Options) -> Result<(), winit::error::EventLoopError> {
     let el: EventLoop<()> = EventLoop::new()?;
-    el.run_app(&mut App::new_with_nes(nes))
+    el.run_app(&mut App::new_with_nes(nes, opts))
 }
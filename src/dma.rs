@@ -0,0 +1,108 @@
+// DMA unit do 2A03: OAM DMA ($4014) e busca de samples do DMC
+//
+// Os dois só conseguem parar a CPU num ciclo de leitura (RDY). O ciclo em
+// que a CPU para ("halt") é descartado e repetido depois, e enquanto ela
+// está parada o endereço dela continua no bus: os ciclos de dummy/alinhamento
+// relêem esse endereço. Daí os glitches de $4016/$2007 quando um fetch do DMC
+// cai em cima de uma leitura desses registradores.
+//
+//   OAM: halt + (alinhamento) + 256 x (get, put)   = 513 ou 514 ciclos
+//   DMC: halt + dummy + (alinhamento) + get         = 3 ou 4 ciclos
+//        (1 ou 2 quando cai no meio de um OAM DMA, que já está parado)
+
+use crate::savestate::{StateReader, StateResult, StateWriter};
+
+#[derive(Default)]
+pub struct Dma {
+    // OAM
+    pub oam_active: bool,
+    pub oam_page: u8,
+    pub oam_count: u16,   // 0..512, ímpar = próximo é put
+    pub oam_data: u8,
+
+    // DMC
+    pub dmc_active: bool,
+    pub dmc_addr: u16,
+    pub need_halt: bool,
+    pub need_dummy: bool,
+
+    // CPU parada esperando a DMA acabar
+    pub halted: bool,
+    pub halt_addr: u16,
+}
+
+impl Dma {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request_oam(&mut self, page: u8) {
+        self.oam_page = page;
+        self.oam_count = 0;
+        self.oam_active = true;
+        self.need_halt = true;
+    }
+
+    // A APU pede toda vez que o buffer está vazio; só conta o primeiro pedido
+    pub fn request_dmc(&mut self, addr: u16) {
+        if self.dmc_active {
+            return;
+        }
+        self.dmc_addr = addr;
+        self.dmc_active = true;
+        self.need_halt = true;
+        self.need_dummy = true;
+    }
+
+    // Chamado pela CPU em toda leitura: se tem DMA esperando, essa leitura
+    // vira o ciclo de halt e a CPU vai repetir o ciclo depois
+    pub fn try_halt(&mut self, addr: u16) -> bool {
+        if !self.need_halt || self.halted {
+            return false;
+        }
+        self.need_halt = false;
+        self.halted = true;
+        self.halt_addr = addr;
+        true
+    }
+
+    pub fn cpu_halted(&self) -> bool {
+        self.halted
+    }
+
+    // Ciclos de OAM contam como halt/dummy pro DMC quando os dois rodam juntos
+    pub fn process_cycle(&mut self) {
+        if self.need_halt {
+            self.need_halt = false;
+        } else if self.need_dummy {
+            self.need_dummy = false;
+        }
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.bool(self.oam_active);
+        w.u8(self.oam_page);
+        w.u16(self.oam_count);
+        w.u8(self.oam_data);
+        w.bool(self.dmc_active);
+        w.u16(self.dmc_addr);
+        w.bool(self.need_halt);
+        w.bool(self.need_dummy);
+        w.bool(self.halted);
+        w.u16(self.halt_addr);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.oam_active = r.bool()?;
+        self.oam_page = r.u8()?;
+        self.oam_count = r.u16()? & 0x3FF;
+        self.oam_data = r.u8()?;
+        self.dmc_active = r.bool()?;
+        self.dmc_addr = r.u16()?;
+        self.need_halt = r.bool()?;
+        self.need_dummy = r.bool()?;
+        self.halted = r.bool()?;
+        self.halt_addr = r.u16()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,496 @@
+// Expressões pras condições de breakpoint/watchpoint
+//
+//   A == $10 && [$0300] > 5 && scanline < 20
+//
+// Números: decimal (10), hex ($10 ou 0x10), binário (%1010)
+// Variáveis: a x y sp pc p, flags c z i d v n (0/1), scanline cycle frame,
+//            value/addr (acesso do watchpoint) e hits (vezes que o ponto bateu)
+// Memória: [expr] lê um byte com cpu_read_debug (sem efeito colateral)
+// Operadores como em C: || && | ^ & == != < <= > >= << >> + - * / % ! - ~
+use std::fmt;
+
+use crate::bus::Bus;
+use crate::cpu6502::{Cpu6502, FLAGS6502};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Var {
+    A, X, Y, Sp, Pc, P,
+    Flag(u8),
+    Scanline, Cycle, Frame,
+    Value, Addr, Hits,
+}
+
+impl Var {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "a" => Var::A,
+            "x" => Var::X,
+            "y" => Var::Y,
+            "sp" | "s" => Var::Sp,
+            "pc" => Var::Pc,
+            "p" => Var::P,
+            "c" => Var::Flag(FLAGS6502::C as u8),
+            "z" => Var::Flag(FLAGS6502::Z as u8),
+            "i" => Var::Flag(FLAGS6502::I as u8),
+            "d" => Var::Flag(FLAGS6502::D as u8),
+            "v" => Var::Flag(FLAGS6502::V as u8),
+            "n" => Var::Flag(FLAGS6502::N as u8),
+            "scanline" | "sl" => Var::Scanline,
+            "cycle" | "dot" => Var::Cycle,
+            "frame" => Var::Frame,
+            "value" | "val" => Var::Value,
+            "addr" => Var::Addr,
+            "hits" => Var::Hits,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnOp { Not, Neg, BitNot }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Or, And,
+    BitOr, BitXor, BitAnd,
+    Eq, Ne, Lt, Le, Gt, Ge,
+    Shl, Shr,
+    Add, Sub,
+    Mul, Div, Rem,
+}
+
+impl BinOp {
+    // Maior = liga mais forte
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Or => 1,
+            BinOp::And => 2,
+            BinOp::BitOr => 3,
+            BinOp::BitXor => 4,
+            BinOp::BitAnd => 5,
+            BinOp::Eq | BinOp::Ne => 6,
+            BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => 7,
+            BinOp::Shl | BinOp::Shr => 8,
+            BinOp::Add | BinOp::Sub => 9,
+            BinOp::Mul | BinOp::Div | BinOp::Rem => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(i64),
+    Var(Var),
+    Mem(Box<Expr>),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+// O que a expressão enxerga. value/addr/hits só fazem sentido em watchpoint/breakpoint
+pub struct EvalContext<'a> {
+    pub cpu: &'a Cpu6502,
+    pub bus: &'a Bus,
+    pub value: u8,
+    pub addr: u16,
+    pub hits: u64,
+}
+
+impl<'a> EvalContext<'a> {
+    pub fn new(cpu: &'a Cpu6502, bus: &'a Bus) -> Self {
+        EvalContext { cpu, bus, value: 0, addr: 0, hits: 0 }
+    }
+}
+
+impl Expr {
+    pub fn eval(&self, ctx: &EvalContext) -> i64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Var(v) => match *v {
+                Var::A => ctx.cpu.a as i64,
+                Var::X => ctx.cpu.x as i64,
+                Var::Y => ctx.cpu.y as i64,
+                Var::Sp => ctx.cpu.stkp as i64,
+                Var::Pc => ctx.cpu.pc as i64,
+                Var::P => ctx.cpu.status as i64,
+                Var::Flag(mask) => (ctx.cpu.status & mask != 0) as i64,
+                Var::Scanline => ctx.bus.ppu.scanline as i64,
+                Var::Cycle => ctx.bus.ppu.cycle as i64,
+                Var::Frame => ctx.bus.ppu.frame_count as i64,
+                Var::Value => ctx.value as i64,
+                Var::Addr => ctx.addr as i64,
+                Var::Hits => ctx.hits as i64,
+            },
+            Expr::Mem(addr) => ctx.bus.cpu_read_debug(addr.eval(ctx) as u16) as i64,
+            Expr::Unary(op, e) => {
+                let v = e.eval(ctx);
+                match op {
+                    UnOp::Not => (v == 0) as i64,
+                    UnOp::Neg => v.wrapping_neg(),
+                    UnOp::BitNot => !v,
+                }
+            },
+            Expr::Binary(op, l, r) => {
+                let a = l.eval(ctx);
+                // && e || não avaliam o lado direito à toa
+                match op {
+                    BinOp::And => return (a != 0 && r.eval(ctx) != 0) as i64,
+                    BinOp::Or => return (a != 0 || r.eval(ctx) != 0) as i64,
+                    _ => {},
+                }
+                let b = r.eval(ctx);
+                match op {
+                    BinOp::BitOr => a | b,
+                    BinOp::BitXor => a ^ b,
+                    BinOp::BitAnd => a & b,
+                    BinOp::Eq => (a == b) as i64,
+                    BinOp::Ne => (a != b) as i64,
+                    BinOp::Lt => (a < b) as i64,
+                    BinOp::Le => (a <= b) as i64,
+                    BinOp::Gt => (a > b) as i64,
+                    BinOp::Ge => (a >= b) as i64,
+                    BinOp::Shl => a.wrapping_shl(b as u32),
+                    BinOp::Shr => a.wrapping_shr(b as u32),
+                    BinOp::Add => a.wrapping_add(b),
+                    BinOp::Sub => a.wrapping_sub(b),
+                    BinOp::Mul => a.wrapping_mul(b),
+                    // Divisão por zero dá 0 em vez de derrubar o emulador
+                    BinOp::Div => a.checked_div(b).unwrap_or(0),
+                    BinOp::Rem => a.checked_rem(b).unwrap_or(0),
+                    BinOp::And | BinOp::Or => unreachable!(),
+                }
+            },
+        }
+    }
+}
+
+// Expressão + o texto original, pra mostrar no overlay/log do jeito que o usuário escreveu
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub text: String,
+    pub expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        let mut p = Parser { tokens, pos: 0 };
+        let expr = p.expr(0)?;
+        if let Some(t) = p.tokens.get(p.pos) {
+            return Err(format!("sobrou '{}' no fim da expressao", t));
+        }
+        Ok(Condition { text: text.trim().to_string(), expr })
+    }
+
+    pub fn test(&self, ctx: &EvalContext) -> bool {
+        self.expr.eval(ctx) != 0
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+// "ADDR... if COND" -> ("ADDR...", Some(cond)). Usado pelos specs de breakpoint e watchpoint
+pub fn split_condition(spec: &str) -> Result<(&str, Option<Condition>), String> {
+    match spec.split_once(" if ") {
+        Some((head, cond)) => Ok((head.trim(), Some(Condition::parse(cond)?))),
+        None => Ok((spec.trim(), None)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+    LBracket, RBracket,
+    LParen, RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{}", n),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+// Os de dois caracteres primeiro, senão "<=" vira "<" "="
+const OPERATORS: [&str; 21] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>",
+    "|", "^", "&", "<", ">", "+", "-", "*", "/", "%", "!", "~", "=",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '[' => { tokens.push(Token::LBracket); i += 1; continue; },
+            ']' => { tokens.push(Token::RBracket); i += 1; continue; },
+            '(' => { tokens.push(Token::LParen); i += 1; continue; },
+            ')' => { tokens.push(Token::RParen); i += 1; continue; },
+            _ => {},
+        }
+
+        // Números: $hex, 0xhex, %bin, decimal. % depois de um valor é resto da divisão
+        let after_value = matches!(tokens.last(), Some(Token::Num(_) | Token::Ident(_) | Token::RBracket | Token::RParen));
+        let (radix, skip) = match c {
+            '$' => (16, 1),
+            '0' if matches!(chars.get(i + 1), Some('x') | Some('X')) => (16, 2),
+            '%' if !after_value && chars.get(i + 1).is_some_and(|d| *d == '0' || *d == '1') => (2, 1),
+            d if d.is_ascii_digit() => (10, 0),
+            _ => (0, 0),
+        };
+        if radix != 0 {
+            let start = i + skip;
+            let mut end = start;
+            while end < chars.len() && chars[end].is_digit(radix) {
+                end += 1;
+            }
+            let digits: String = chars[start..end].iter().collect();
+            let n = i64::from_str_radix(&digits, radix).map_err(|_| format!("numero invalido na posicao {}", i))?;
+            tokens.push(Token::Num(n));
+            i = end;
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            Some(op) => {
+                tokens.push(Token::Op(op));
+                i += op.len();
+            },
+            None => return Err(format!("caractere inesperado '{}' na posicao {}", c, i)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn peek_binop(&self) -> Option<BinOp> {
+        let op = match self.tokens.get(self.pos)? {
+            Token::Op(op) => *op,
+            _ => return None,
+        };
+        Some(match op {
+            "||" => BinOp::Or,
+            "&&" => BinOp::And,
+            "|" => BinOp::BitOr,
+            "^" => BinOp::BitXor,
+            "&" => BinOp::BitAnd,
+            "==" | "=" => BinOp::Eq,
+            "!=" => BinOp::Ne,
+            "<" => BinOp::Lt,
+            "<=" => BinOp::Le,
+            ">" => BinOp::Gt,
+            ">=" => BinOp::Ge,
+            "<<" => BinOp::Shl,
+            ">>" => BinOp::Shr,
+            "+" => BinOp::Add,
+            "-" => BinOp::Sub,
+            "*" => BinOp::Mul,
+            "/" => BinOp::Div,
+            "%" => BinOp::Rem,
+            _ => return None,
+        })
+    }
+
+    // Precedence climbing: só consome operadores que ligam mais forte que min_prec
+    fn expr(&mut self, min_prec: u8) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = self.peek_binop() {
+            let prec = op.precedence();
+            if prec <= min_prec {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.expr(prec)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Ident(name)) => Var::from_name(&name)
+                .map(Expr::Var)
+                .ok_or(format!("variavel desconhecida: {}", name)),
+            Some(Token::LBracket) => {
+                let addr = self.expr(0)?;
+                self.expect(Token::RBracket)?;
+                Ok(Expr::Mem(Box::new(addr)))
+            },
+            Some(Token::LParen) => {
+                let e = self.expr(0)?;
+                self.expect(Token::RParen)?;
+                Ok(e)
+            },
+            Some(Token::Op("!")) => Ok(Expr::Unary(UnOp::Not, Box::new(self.unary()?))),
+            Some(Token::Op("-")) => Ok(Expr::Unary(UnOp::Neg, Box::new(self.unary()?))),
+            Some(Token::Op("~")) => Ok(Expr::Unary(UnOp::BitNot, Box::new(self.unary()?))),
+            Some(t) => Err(format!("esperava um valor, veio '{}'", t)),
+            None => Err("expressao incompleta".to_string()),
+        }
+    }
+
+    fn expect(&mut self, want: Token) -> Result<(), String> {
+        match self.next() {
+            Some(ref t) if *t == want => Ok(()),
+            Some(t) => Err(format!("esperava '{}', veio '{}'", want, t)),
+            None => Err(format!("esperava '{}' no fim", want)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nes::Nes;
+
+    fn eval(nes: &Nes, text: &str) -> i64 {
+        Condition::parse(text).unwrap().expr.eval(&EvalContext::new(&nes.cpu, &nes.bus))
+    }
+
+    fn parse(text: &str) -> Expr {
+        Condition::parse(text).unwrap().expr
+    }
+
+    #[test]
+    fn example_from_the_docs() {
+        let mut nes = Nes::new();
+        nes.cpu.a = 0x10;
+        nes.bus.cpu_write(0x0300, 6);
+        nes.bus.ppu.scanline = 10;
+        let cond = "A == $10 && [$0300] > 5 && scanline < 20";
+        assert_eq!(eval(&nes, cond), 1);
+
+        nes.bus.ppu.scanline = 20;
+        assert_eq!(eval(&nes, cond), 0);
+        nes.bus.ppu.scanline = 10;
+        nes.bus.cpu_write(0x0300, 5);
+        assert_eq!(eval(&nes, cond), 0);
+        nes.bus.cpu_write(0x0300, 6);
+        nes.cpu.a = 0x11;
+        assert_eq!(eval(&nes, cond), 0);
+    }
+
+    #[test]
+    fn numbers_and_percent() {
+        let nes = Nes::new();
+        assert_eq!(eval(&nes, "$ff + 0x10 + %101 + 9"), 255 + 16 + 5 + 9);
+        // % depois de um valor é resto, antes é binário
+        assert_eq!(eval(&nes, "%1010 % %11"), 10 % 3);
+        assert_eq!(eval(&nes, "7%10"), 7);
+        assert_eq!(eval(&nes, "7 % 10"), 7);
+        assert_eq!(eval(&nes, "(%11)%2"), 1);
+        assert_eq!(eval(&nes, "7 / 0"), 0);
+        assert_eq!(eval(&nes, "7 % 0"), 0);
+    }
+
+    #[test]
+    fn single_equals_is_comparison() {
+        let mut nes = Nes::new();
+        nes.cpu.x = 3;
+        assert_eq!(parse("x = 3"), parse("x == 3"));
+        assert_eq!(eval(&nes, "x = 3"), 1);
+        assert_eq!(eval(&nes, "x = 4"), 0);
+    }
+
+    #[test]
+    fn memory_reads() {
+        let mut nes = Nes::new();
+        nes.bus.cpu_write(0x0010, 0x20);
+        nes.bus.cpu_write(0x0020, 0x42);
+        nes.cpu.y = 0x10;
+        assert_eq!(eval(&nes, "[$10]"), 0x20);
+        assert_eq!(eval(&nes, "[[$10]]"), 0x42);
+        assert_eq!(eval(&nes, "[y + $10] == $42"), 1);
+        // Espelho da RAM
+        assert_eq!(eval(&nes, "[$0810]"), 0x20);
+    }
+
+    #[test]
+    fn precedence() {
+        let nes = Nes::new();
+        assert_eq!(eval(&nes, "1 + 2 * 3"), 7);
+        assert_eq!(eval(&nes, "(1 + 2) * 3"), 9);
+        assert_eq!(eval(&nes, "10 - 4 - 3"), 3);
+        assert_eq!(eval(&nes, "64 / 4 / 2"), 8);
+        assert_eq!(eval(&nes, "1 << 2 + 1"), 8);
+        assert_eq!(eval(&nes, "6 & 3 == 3"), 0);
+        assert_eq!(eval(&nes, "(6 & 3) == 2"), 1);
+        assert_eq!(eval(&nes, "1 | 2 ^ 3 & 1"), 1 | (2 ^ (3 & 1)));
+        assert_eq!(eval(&nes, "-2 * -3"), 6);
+        assert_eq!(eval(&nes, "~0"), -1);
+        assert_eq!(eval(&nes, "!5 + 1"), 1);
+        assert_eq!(parse("1 || 0 && 0"), parse("1 || (0 && 0)"));
+        let lt = Expr::Binary(BinOp::Lt, Box::new(Expr::Var(Var::A)), Box::new(Expr::Num(1)));
+        assert_eq!(parse("a < 1 == x"), Expr::Binary(BinOp::Eq, Box::new(lt), Box::new(Expr::Var(Var::X))));
+    }
+
+    #[test]
+    fn logic_operators() {
+        let nes = Nes::new();
+        assert_eq!(eval(&nes, "0 && 5"), 0);
+        assert_eq!(eval(&nes, "2 && 5"), 1);
+        assert_eq!(eval(&nes, "0 || 0"), 0);
+        assert_eq!(eval(&nes, "3 || 0"), 1);
+        // Resultado é sempre 0/1, não o valor do lado que decidiu
+        assert_eq!(eval(&nes, "5 || 7"), 1);
+        assert_eq!(eval(&nes, "(2 && 5) + (0 || 9)"), 2);
+    }
+
+    #[test]
+    fn errors() {
+        for bad in ["", "a ==", "[$10", "(1 + 2", "1 + )", "foo > 1", "1 2", "a # 1", "$"] {
+            assert!(Condition::parse(bad).is_err(), "devia falhar: {:?}", bad);
+        }
+        assert_eq!(Condition::parse("foo > 1").unwrap_err(), "variavel desconhecida: foo");
+        assert_eq!(Condition::parse("1 2").unwrap_err(), "sobrou '2' no fim da expressao");
+    }
+
+    #[test]
+    fn split_spec() {
+        let (head, cond) = split_condition("C000:log if hits > 3").unwrap();
+        assert_eq!(head, "C000:log");
+        assert_eq!(cond.unwrap().text, "hits > 3");
+        assert!(split_condition("C000").unwrap().1.is_none());
+        assert!(split_condition("C000 if a ==").is_err());
+    }
+}
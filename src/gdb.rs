@@ -0,0 +1,536 @@
+// Stub do GDB remote serial protocol (RSP) por TCP
+//
+// Não tem thread: o frontend chama poll() a cada volta do loop e continua
+// rodando frames enquanto o Debugger não estiver parado. "Parar" aqui é o
+// mesmo hit_breakpoint do overlay, então F7/F12 e o GDB mexem na mesma coisa.
+//
+// Registradores (g/G/p/P), na ordem do target.xml:
+//   0 a, 1 x, 2 y, 3 p, 4 sp (8 bits)   5 pc (16 bits, little endian)
+// Memória: m lê com cpu_read_debug, M escreve com cpu_write (com efeito
+// colateral, igual a CPU escrevendo). Z0 = breakpoint, Z2/Z3/Z4 = watchpoint.
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::debug::Breakpoint;
+use crate::nes::Nes;
+use crate::watch::{Watchpoint, WatchSpace, WATCH_READ, WATCH_WRITE};
+
+const PACKET_SIZE: usize = 0x1000;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rnfe.6502.core">
+    <reg name="a" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="x" bitsize="8" type="uint8"/>
+    <reg name="y" bitsize="8" type="uint8"/>
+    <reg name="p" bitsize="8" type="uint8"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+struct Conn {
+    stream: TcpStream,
+    buf: Vec<u8>,
+    no_ack: bool,
+    running: bool,      // mandou continuar/step: falta o stop reply
+    interrupted: bool,  // parou por Ctrl-C (SIGINT) e não por trap
+}
+
+pub struct GdbStub {
+    listener: TcpListener,
+    conn: Option<Conn>,
+}
+
+impl GdbStub {
+    // Só loopback: o protocolo não tem autenticação nenhuma
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(GdbStub { listener, conn: None })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map(|a| a.port()).unwrap_or(0)
+    }
+
+    pub fn connected(&self) -> bool {
+        self.conn.is_some()
+    }
+
+    pub fn poll(&mut self, nes: &mut Nes) {
+        if self.conn.is_none() {
+            self.accept(nes);
+        }
+        let Some(conn) = self.conn.as_mut() else { return };
+
+        // Lê tudo que chegou sem bloquear
+        let mut closed = false;
+        let mut tmp = [0u8; 1024];
+        loop {
+            match conn.stream.read(&mut tmp) {
+                Ok(0) => { closed = true; break; },
+                Ok(n) => conn.buf.extend_from_slice(&tmp[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => { closed = true; break; },
+            }
+        }
+
+        let mut ok = !closed && conn.process(nes).is_ok();
+        // Parou (breakpoint, step, Ctrl-C ou o usuário apertou F12): avisa o GDB
+        if ok && conn.running && nes.debugger.hit_breakpoint {
+            ok = conn.stop_reply().is_ok();
+        }
+        if !ok {
+            self.disconnect(nes);
+        }
+    }
+
+    fn accept(&mut self, nes: &mut Nes) {
+        let Ok((stream, addr)) = self.listener.accept() else { return };
+        if stream.set_nonblocking(true).is_err() {
+            return;
+        }
+        let _ = stream.set_nodelay(true);
+        eprintln!("[GDB] conectado: {}", addr);
+        self.conn = Some(Conn {
+            stream,
+            buf: Vec::new(),
+            no_ack: false,
+            running: false,
+            interrupted: false,
+        });
+        // O GDB espera o alvo parado quando conecta
+        halt(nes);
+    }
+
+    // Tira o que o GDB colocou (só os dele, não os do console/overlay) e deixa o jogo rodando
+    fn disconnect(&mut self, nes: &mut Nes) {
+        if self.conn.take().is_none() {
+            return;
+        }
+        nes.debugger.breakpoints.retain(|bp| !bp.gdb);
+        while let Some(i) = nes.bus.watch.points.iter().position(|p| p.gdb) {
+            nes.bus.watch.remove(i);
+        }
+        nes.debugger.resume();
+        eprintln!("[GDB] desconectado");
+    }
+}
+
+fn halt(nes: &mut Nes) {
+    if !nes.debugger.hit_breakpoint {
+        // Para no começo da próxima instrução, nunca no meio de uma
+        nes.debugger.step_instruction();
+    }
+}
+
+fn remove_breakpoint(nes: &mut Nes, addr: u16) {
+    let bps = &mut nes.debugger.breakpoints;
+    if let Some(i) = bps.iter().position(|bp| bp.gdb && bp.addr == addr) {
+        bps.remove(i);
+    }
+}
+
+fn remove_watch(nes: &mut Nes, wp: &Watchpoint) {
+    if let Some(i) = nes.bus.watch.points.iter().position(|p| p.gdb && p.space == wp.space
+        && p.start == wp.start && p.end == wp.end && p.kinds == wp.kinds) {
+        nes.bus.watch.remove(i);
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, b| acc.wrapping_add(*b))
+}
+
+fn hex_bytes(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s, 16).ok()
+}
+
+fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+impl Conn {
+    fn send(&mut self, payload: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", payload, checksum(payload.as_bytes()));
+        self.stream.write_all(packet.as_bytes())
+    }
+
+    fn stop_reply(&mut self) -> io::Result<()> {
+        self.running = false;
+        let signal = if self.interrupted { 2 } else { 5 };   // SIGINT / SIGTRAP
+        self.interrupted = false;
+        self.send(&format!("S{:02x}", signal))
+    }
+
+    // Separa os pacotes do buffer: $payload#xx, ack (+/-) e Ctrl-C (0x03)
+    fn process(&mut self, nes: &mut Nes) -> io::Result<()> {
+        loop {
+            let Some(start) = self.buf.iter().position(|&b| b == b'$' || b == 0x03) else {
+                self.buf.clear();   // só acks
+                return Ok(());
+            };
+            if self.buf[start] == 0x03 {
+                self.buf.drain(..=start);
+                self.interrupted = !nes.debugger.hit_breakpoint;
+                self.running = true;
+                halt(nes);
+                continue;
+            }
+            let Some(hash) = self.buf[start..].iter().position(|&b| b == b'#').map(|p| start + p) else {
+                return Ok(());  // pacote incompleto, espera o resto
+            };
+            if self.buf.len() < hash + 3 {
+                return Ok(());
+            }
+            let payload = self.buf[start + 1..hash].to_vec();
+            let sum = std::str::from_utf8(&self.buf[hash + 1..hash + 3]).ok().and_then(|s| u8::from_str_radix(s, 16).ok());
+            self.buf.drain(..hash + 3);
+
+            if !self.no_ack {
+                if sum != Some(checksum(&payload)) {
+                    self.stream.write_all(b"-")?;
+                    continue;
+                }
+                self.stream.write_all(b"+")?;
+            }
+            let payload = String::from_utf8_lossy(&payload).to_string();
+            if let Some(reply) = self.handle(&payload, nes) {
+                self.send(&reply)?;
+            }
+            if payload == "k" || payload.starts_with('D') {
+                return Err(io::Error::new(ErrorKind::ConnectionAborted, "detach"));
+            }
+        }
+    }
+
+    // None = sem resposta agora (continue/step respondem quando parar)
+    fn handle(&mut self, packet: &str, nes: &mut Nes) -> Option<String> {
+        let (cmd, args) = packet.split_at(packet.chars().next().map_or(0, |c| c.len_utf8()));
+        let reply = match cmd {
+            "?" => {
+                if nes.debugger.hit_breakpoint {
+                    return Some("S05".to_string());
+                }
+                self.running = true;
+                return None;
+            },
+            "g" => {
+                let cpu = &nes.cpu;
+                hex_bytes(&[cpu.a, cpu.x, cpu.y, cpu.status, cpu.stkp, cpu.pc as u8, (cpu.pc >> 8) as u8])
+            },
+            "G" => match parse_hex_bytes(args) {
+                Some(regs) if regs.len() >= 7 => {
+                    let cpu = &mut nes.cpu;
+                    cpu.a = regs[0];
+                    cpu.x = regs[1];
+                    cpu.y = regs[2];
+                    cpu.status = regs[3];
+                    cpu.stkp = regs[4];
+                    cpu.pc = regs[5] as u16 | (regs[6] as u16) << 8;
+                    "OK".to_string()
+                },
+                _ => "E01".to_string(),
+            },
+            "p" => match parse_hex(args) {
+                Some(n) => read_register(nes, n).unwrap_or_else(|| "E01".to_string()),
+                None => "E01".to_string(),
+            },
+            "P" => {
+                let write = args.split_once('=').and_then(|(n, v)| Some((parse_hex(n)?, parse_hex_bytes(v)?)));
+                match write {
+                    Some((n, bytes)) if write_register(nes, n, &bytes) => "OK".to_string(),
+                    _ => "E01".to_string(),
+                }
+            },
+            "m" => {
+                let range = args.split_once(',').and_then(|(a, l)| Some((parse_hex(a)?, parse_hex(l)?)));
+                match range {
+                    Some((addr, len)) => {
+                        let len = (len as usize).min(PACKET_SIZE / 2);
+                        let data: Vec<u8> = (0..len)
+                            .map(|i| nes.bus.cpu_read_debug((addr as usize + i) as u16))
+                            .collect();
+                        hex_bytes(&data)
+                    },
+                    None => "E01".to_string(),
+                }
+            },
+            "M" => {
+                let write = args.split_once(':').and_then(|(range, data)| {
+                    let (addr, len) = range.split_once(',')?;
+                    Some((parse_hex(addr)?, parse_hex(len)?, parse_hex_bytes(data)?))
+                });
+                match write {
+                    Some((addr, len, data)) if data.len() as u64 == len => {
+                        let pending = nes.bus.watch.hits.len();
+                        for (i, b) in data.iter().enumerate() {
+                            nes.bus.cpu_write((addr as usize + i) as u16, *b);
+                        }
+                        // Não foi a CPU que escreveu: sem isso os hits iam pra conta do último PC
+                        nes.bus.watch.hits.truncate(pending);
+                        "OK".to_string()
+                    },
+                    _ => "E01".to_string(),
+                }
+            },
+            "c" | "s" => {
+                if let Some(addr) = parse_hex(args) {
+                    nes.cpu.pc = addr as u16;
+                }
+                self.resume(nes, cmd == "s");
+                return None;
+            },
+            "Z" | "z" => self.breakpoint(nes, cmd == "Z", args),
+            "H" | "T" => "OK".to_string(),    // thread única
+            "k" | "D" => "OK".to_string(),
+            "v" => {
+                if args == "Cont?" {
+                    "vCont;c;C;s;S".to_string()
+                } else if let Some(action) = args.strip_prefix("Cont;") {
+                    // Um processo só: vale a primeira ação
+                    let step = action.starts_with('s') || action.starts_with('S');
+                    self.resume(nes, step);
+                    return None;
+                } else {
+                    String::new()
+                }
+            },
+            "q" => self.query(args),
+            "Q" => {
+                if args == "StartNoAckMode" {
+                    self.no_ack = true;
+                    "OK".to_string()
+                } else {
+                    String::new()
+                }
+            },
+            _ => String::new(),     // não suportado
+        };
+        Some(reply)
+    }
+
+    fn resume(&mut self, nes: &mut Nes, step: bool) {
+        if step {
+            nes.debugger.step_instruction();
+        } else {
+            nes.debugger.resume();
+        }
+        self.running = true;
+    }
+
+    // Z0 breakpoint; Z2 escrita, Z3 leitura, Z4 acesso (o tamanho vira a faixa)
+    fn breakpoint(&mut self, nes: &mut Nes, insert: bool, args: &str) -> String {
+        let mut parts = args.split(',');
+        let (Some(kind), Some(addr), Some(len)) = (parts.next(), parts.next().and_then(parse_hex), parts.next()) else {
+            return "E01".to_string();
+        };
+        let addr = addr as u16;
+        let len = parse_hex(len.split(';').next().unwrap_or("1")).unwrap_or(1).max(1) as u16;
+
+        let kinds = match kind {
+            "0" | "1" => {
+                if insert {
+                    nes.debugger.breakpoints.push(Breakpoint { gdb: true, ..Breakpoint::new(addr) });
+                } else {
+                    remove_breakpoint(nes, addr);
+                }
+                return "OK".to_string();
+            },
+            "2" => WATCH_WRITE,
+            "3" => WATCH_READ,
+            "4" => WATCH_READ | WATCH_WRITE,
+            _ => return String::new(),
+        };
+
+        let wp = Watchpoint {
+            space: WatchSpace::Cpu,
+            start: addr,
+            end: addr.saturating_add(len - 1),
+            kinds,
+            brk: true,
+            cond: None,
+            hits: 0,
+            gdb: true,
+        };
+        if insert {
+            nes.bus.watch.add(wp);
+        } else {
+            remove_watch(nes, &wp);
+        }
+        "OK".to_string()
+    }
+
+    fn query(&mut self, args: &str) -> String {
+        if args.starts_with("Supported") {
+            return format!("PacketSize={:x};qXfer:features:read+;QStartNoAckMode+;vContSupported+", PACKET_SIZE);
+        }
+        if let Some(rest) = args.strip_prefix("Xfer:features:read:target.xml:") {
+            let range = rest.split_once(',').and_then(|(o, l)| Some((parse_hex(o)? as usize, parse_hex(l)? as usize)));
+            let Some((offset, len)) = range else { return "E01".to_string() };
+            let data = TARGET_XML.get(offset.min(TARGET_XML.len())..).unwrap_or("");
+            return if data.len() > len {
+                format!("m{}", &data[..len])
+            } else {
+                format!("l{}", data)
+            };
+        }
+        match args {
+            "Attached" => "1".to_string(),
+            "C" => "QC1".to_string(),
+            "fThreadInfo" => "m1".to_string(),
+            "sThreadInfo" => "l".to_string(),
+            "Offsets" => "Text=0;Data=0;Bss=0".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+fn read_register(nes: &Nes, n: u64) -> Option<String> {
+    let cpu = &nes.cpu;
+    let bytes = match n {
+        0 => vec![cpu.a],
+        1 => vec![cpu.x],
+        2 => vec![cpu.y],
+        3 => vec![cpu.status],
+        4 => vec![cpu.stkp],
+        5 => vec![cpu.pc as u8, (cpu.pc >> 8) as u8],
+        _ => return None,
+    };
+    Some(hex_bytes(&bytes))
+}
+
+fn write_register(nes: &mut Nes, n: u64, bytes: &[u8]) -> bool {
+    let cpu = &mut nes.cpu;
+    let Some(&lo) = bytes.first() else { return false };
+    match n {
+        0 => cpu.a = lo,
+        1 => cpu.x = lo,
+        2 => cpu.y = lo,
+        3 => cpu.status = lo,
+        4 => cpu.stkp = lo,
+        5 => cpu.pc = lo as u16 | (bytes.get(1).copied().unwrap_or(0) as u16) << 8,
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::test_rom;
+    use std::time::Duration;
+
+    // NROM com um loop só: C000 INX / JMP $C000
+    fn loop_rom() -> Vec<u8> {
+        test_rom(1, [0; 3], &[(0xC000, &[0xE8, 0x4C, 0x00, 0xC0])])
+    }
+
+    struct Client {
+        stream: TcpStream,
+        buf: Vec<u8>,
+    }
+
+    impl Client {
+        fn send(&mut self, payload: &str) {
+            let packet = format!("${}#{:02x}", payload, checksum(payload.as_bytes()));
+            self.stream.write_all(packet.as_bytes()).unwrap();
+        }
+
+        // Roda o stub (e o jogo, se não estiver parado) até chegar um pacote inteiro
+        fn reply(&mut self, stub: &mut GdbStub, nes: &mut Nes) -> String {
+            for _ in 0..200 {
+                stub.poll(nes);
+                if !nes.debugger.hit_breakpoint {
+                    nes.run_frame();
+                }
+                let mut tmp = [0u8; 1024];
+                if let Ok(n) = self.stream.read(&mut tmp) {
+                    self.buf.extend_from_slice(&tmp[..n]);
+                }
+                let Some(start) = self.buf.iter().position(|&b| b == b'$') else { continue };
+                assert!(self.buf[..start].iter().all(|&b| b == b'+'), "lixo antes do pacote: {:?}", self.buf);
+                let Some(hash) = self.buf.iter().position(|&b| b == b'#') else { continue };
+                if self.buf.len() < hash + 3 {
+                    continue;
+                }
+                let payload = self.buf[start + 1..hash].to_vec();
+                let sum = u8::from_str_radix(std::str::from_utf8(&self.buf[hash + 1..hash + 3]).unwrap(), 16).unwrap();
+                assert_eq!(sum, checksum(&payload), "checksum errado");
+                self.buf.drain(..hash + 3);
+                self.stream.write_all(b"+").unwrap();
+                return String::from_utf8(payload).unwrap();
+            }
+            panic!("sem resposta do stub");
+        }
+
+        fn ask(&mut self, stub: &mut GdbStub, nes: &mut Nes, payload: &str) -> String {
+            self.send(payload);
+            self.reply(stub, nes)
+        }
+    }
+
+    #[test]
+    fn scripted_session() {
+        let mut nes = Nes::new();
+        nes.load_rom(&loop_rom()).unwrap();
+        // Do usuário (console/CLI): iguais aos que o GDB vai pôr, não podem sumir
+        nes.debugger.breakpoints.push(Breakpoint::new(0xD000));
+        nes.bus.watch.add(Watchpoint::parse("0010:w").unwrap());
+
+        let mut stub = GdbStub::bind(0).unwrap();
+        let stream = TcpStream::connect(("127.0.0.1", stub.port())).unwrap();
+        stream.set_read_timeout(Some(Duration::from_millis(5))).unwrap();
+        let mut gdb = Client { stream, buf: Vec::new() };
+
+        assert!(gdb.ask(&mut stub, &mut nes, "qSupported:xmlRegisters=i386").starts_with("PacketSize=1000;"));
+        assert!(stub.connected());
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "?"), "S05");
+
+        let cpu = &nes.cpu;
+        let regs = hex_bytes(&[cpu.a, cpu.x, cpu.y, cpu.status, cpu.stkp, cpu.pc as u8, (cpu.pc >> 8) as u8]);
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "g"), regs);
+
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "m0000,10"), "00".repeat(16));
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "M0010,2:abcd"), "OK");
+        assert!(nes.bus.watch.hits.is_empty());
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "m0010,2"), "abcd");
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "mc000,4"), "e84c00c0");
+
+        // z0 tira o do GDB, não o do usuário no mesmo endereço
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "Z0,d000,1"), "OK");
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "z0,d000,1"), "OK");
+        assert_eq!(nes.debugger.breakpoints, vec![Breakpoint::new(0xD000)]);
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "Z0,d000,1"), "OK");
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "Z2,10,1"), "OK");
+        assert_eq!(nes.bus.watch.points.len(), 2);
+
+        let pc = nes.cpu.pc;
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "s"), "S05");
+        assert_ne!(nes.cpu.pc, pc);
+
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "Z0,c001,1"), "OK");
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "c"), "S05");
+        assert_eq!(nes.cpu.pc, 0xC001);
+
+        // Detach: só o que o GDB pôs sai, e o jogo volta a rodar
+        assert_eq!(gdb.ask(&mut stub, &mut nes, "D"), "OK");
+        stub.poll(&mut nes);
+        assert!(!stub.connected());
+        assert!(!nes.debugger.hit_breakpoint);
+        assert_eq!(nes.debugger.breakpoints, vec![Breakpoint::new(0xD000)]);
+        assert_eq!(nes.bus.watch.points.len(), 1);
+        assert!(!nes.bus.watch.points[0].gdb);
+    }
+}
@@ -0,0 +1,475 @@
+// Modo headless: roda a ROM sem janela e sem device de audio.
+// Serve pra CI/servidores e pra gerar imagens de referência (golden images).
+// --test-rom usa o mesmo caminho pra rodar ROMs de teste (ver rnfe::testrom).
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use rnfe::{Nes, Region};
+use rnfe::testrom::{self, Outcome};
+use rnfe::trace::{TraceCondition, TraceDiff};
+use rnfe::debug::Breakpoint;
+use rnfe::console::Console;
+use rnfe::gdb::GdbStub;
+use rnfe::watch::Watchpoint;
+use rnfe::nes::{SCREEN_WIDTH, SCREEN_HEIGHT};
+
+const DEFAULT_FRAMES: u32 = 300;
+const TEST_ROM_FRAMES: u32 = 60 * 60; // 1 minuto de NTSC, os testes mais lentos do blargg cabem
+const DIFF_CONTEXT: usize = 5;      // linhas antes/depois da diferença
+const WAV_SAMPLE_RATE: u32 = 44100;
+
+pub struct HeadlessOptions {
+    pub rom: String,
+    pub frames: u32,
+    pub png: String,
+    pub wav: Option<String>,
+    pub region: Option<Region>,
+    pub trace: Option<String>,
+    pub trace_cond: TraceCondition,
+    pub watches: Vec<Watchpoint>,
+    pub breaks: Vec<Breakpoint>,
+    pub gdb: Option<u16>,
+    pub symbols: Vec<String>,
+    pub console: bool,
+}
+
+impl HeadlessOptions {
+    // RNFE --headless <rom> [--frames N] [--png out.png] [--wav out.wav] [--region R]
+    //                       [--trace out.log] [--trace-pc C000-C0FF] [--trace-frames 10-20]
+    //                       [--watch [cpu:|ppu:]ADDR[-END][:rwx][ if COND]] [--break ADDR[ if COND]]
+    //                       [--symbols jogo.dbg] [--gdb PORT] (espera o GDB e roda até ele desconectar, ignora --frames)
+    //                       [--console] (debugger pelo stdin, roda até o "q"; também ignora --frames)
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut rom = None;
+        let mut frames = DEFAULT_FRAMES;
+        let mut png = None;
+        let mut wav = None;
+        let mut region = None;
+        let mut trace = None;
+        let mut trace_cond = TraceCondition::default();
+        let mut watches = Vec::new();
+        let mut breaks = Vec::new();
+        let mut gdb = None;
+        let mut symbols = Vec::new();
+        let mut console = false;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--frames" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--frames precisa de um valor")?;
+                    frames = v.parse().map_err(|_| format!("--frames invalido: {}", v))?;
+                },
+                "--png" => {
+                    i += 1;
+                    png = Some(args.get(i).ok_or("--png precisa de um caminho")?.clone());
+                },
+                "--wav" => {
+                    i += 1;
+                    wav = Some(args.get(i).ok_or("--wav precisa de um caminho")?.clone());
+                },
+                "--trace" => {
+                    i += 1;
+                    trace = Some(args.get(i).ok_or("--trace precisa de um caminho")?.clone());
+                },
+                "--trace-pc" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--trace-pc precisa de um valor")?;
+                    trace_cond.pc_range = Some(TraceCondition::parse_pc_range(v)
+                        .ok_or(format!("--trace-pc invalido: {} (ex: C000-C0FF)", v))?);
+                },
+                "--watch" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--watch precisa de um valor")?;
+                    watches.push(Watchpoint::parse(v)
+                        .map_err(|e| format!("--watch invalido: {}: {} (ex: 0300-030F:w, ppu:2000-23FF:rw)", v, e))?);
+                },
+                "--break" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--break precisa de um valor")?;
+                    breaks.push(Breakpoint::parse(v)
+                        .map_err(|e| format!("--break invalido: {}: {} (ex: C003 if A == $10)", v, e))?);
+                },
+                "--symbols" => {
+                    i += 1;
+                    symbols.push(args.get(i).ok_or("--symbols precisa de um arquivo")?.clone());
+                },
+                "--gdb" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--gdb precisa de uma porta")?;
+                    gdb = Some(v.parse().map_err(|_| format!("--gdb invalido: {}", v))?);
+                },
+                "--console" => console = true,
+                "--trace-frames" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--trace-frames precisa de um valor")?;
+                    let (start, stop) = TraceCondition::parse_frames(v)
+                        .ok_or(format!("--trace-frames invalido: {} (ex: 10-20, 10-, -20)", v))?;
+                    trace_cond.start_frame = start;
+                    trace_cond.stop_frame = stop;
+                },
+                "--region" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--region precisa de um valor")?;
+                    region = Some(Region::parse(v).ok_or(format!("--region invalida: {} (ntsc, pal, dendy)", v))?);
+                },
+                other if other.starts_with("--") => return Err(format!("Opcao desconhecida: {}", other)),
+                other => rom = Some(other.to_string()),
+            }
+            i += 1;
+        }
+
+        let rom = rom.ok_or("Uso: RNFE --headless <rom> [--frames N] [--png out.png] [--wav out.wav] [--region ntsc|pal|dendy] [--trace out.log [--trace-pc A-B] [--trace-frames S-E]] [--watch SPEC] [--break SPEC] [--symbols FILE] [--gdb PORT] [--console]")?;
+        if gdb.is_some() && console {
+            return Err("use --gdb ou --console, nao os dois".to_string());
+        }
+        // Sem console nem GDB não tem quem continue depois de parar: watch/break
+        // só logam (o de breakpoint sai com o estado da CPU)
+        if !console && gdb.is_none() {
+            watches.iter_mut().for_each(|wp| wp.brk = false);
+            breaks.iter_mut().for_each(|bp| bp.brk = false);
+        }
+        // Padrão: <nome da rom>.png no diretório atual
+        let png = png.unwrap_or_else(|| {
+            let stem = Path::new(&rom).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or("screen".into());
+            format!("{}.png", stem)
+        });
+
+        Ok(HeadlessOptions { rom, frames, png, wav, region, trace, trace_cond, watches, breaks, gdb, symbols, console })
+    }
+}
+
+pub fn run(opts: &HeadlessOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let cartridge = rnfe::Cartridge::new(&opts.rom)?;
+    let mut nes = Nes::new();
+    nes.insert_cartridge(cartridge);
+    if let Some(r) = opts.region {
+        nes.set_region(r);
+    }
+    nes.reset();
+    nes.bus.apu.set_sample_rate(WAV_SAMPLE_RATE as f32);
+    if let Some(ref path) = opts.trace {
+        nes.debugger.start_trace_file(path, opts.trace_cond)?;
+    }
+    for path in &opts.symbols {
+        nes.debugger.symbols.load(path)?;
+    }
+    for wp in &opts.watches {
+        nes.bus.watch.add(wp.clone());
+    }
+    for bp in &opts.breaks {
+        nes.debugger.add_breakpoint(bp.clone(), &nes.bus)?;
+    }
+
+    let mut audio = Vec::new();
+    let frames = match opts.gdb {
+        Some(port) => serve_gdb(&mut nes, port)?,
+        None if opts.console => serve_console(&mut nes),
+        None => {
+            for _ in 0..opts.frames {
+                nes.run_frame();
+                let samples = nes.drain_audio();
+                if opts.wav.is_some() {
+                    audio.extend(samples);
+                }
+            }
+            opts.frames as u64
+        }
+    };
+
+    nes.debugger.stop_trace_file();
+
+    write_png(&opts.png, nes.framebuffer())?;
+    println!("{} frames -> {}", frames, opts.png);
+
+    if let Some(ref path) = opts.wav {
+        write_wav(path, &audio, WAV_SAMPLE_RATE)?;
+        println!("{} samples -> {}", audio.len(), path);
+    }
+
+    Ok(())
+}
+
+// Roda sem limitar a velocidade enquanto o GDB não mandar parar; volta quando
+// ele desconecta. Devolve quantos frames rodaram
+fn serve_gdb(nes: &mut Nes, port: u16) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut gdb = GdbStub::bind(port)?;
+    println!("Esperando o GDB: target remote 127.0.0.1:{}", gdb.port());
+    let mut attached = false;
+    loop {
+        gdb.poll(nes);
+        if gdb.connected() {
+            attached = true;
+        } else if attached {
+            break;
+        }
+        if attached && !nes.debugger.hit_breakpoint {
+            nes.run_frame();
+            nes.drain_audio();
+        } else {
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    }
+    Ok(nes.debugger.total_frames)
+}
+
+// Começa parado no reset; roda sem limitar a velocidade enquanto não parar
+// e volta no "q" (ou fim do stdin). Devolve quantos frames rodaram
+fn serve_console(nes: &mut Nes) -> u64 {
+    let mut console = Console::spawn();
+    console.halt(nes);
+    while !console.closed() {
+        console.poll(nes);
+        if !nes.debugger.hit_breakpoint {
+            nes.run_frame();
+            nes.drain_audio();
+        } else {
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    }
+    nes.debugger.total_frames
+}
+
+pub struct TestRomOptions {
+    pub rom: String,
+    pub frames: u32,
+    pub crc: Option<u32>,
+    pub png: Option<String>,
+    pub region: Option<Region>,
+}
+
+impl TestRomOptions {
+    // RNFE --test-rom <rom> [--frames N] [--crc XXXXXXXX] [--png out.png] [--region R]
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut rom = None;
+        let mut frames = TEST_ROM_FRAMES;
+        let mut crc = None;
+        let mut png = None;
+        let mut region = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--frames" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--frames precisa de um valor")?;
+                    frames = v.parse().map_err(|_| format!("--frames invalido: {}", v))?;
+                },
+                "--crc" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--crc precisa de um valor")?;
+                    let hex = v.trim_start_matches("0x");
+                    crc = Some(u32::from_str_radix(hex, 16).map_err(|_| format!("--crc invalido: {}", v))?);
+                },
+                "--png" => {
+                    i += 1;
+                    png = Some(args.get(i).ok_or("--png precisa de um caminho")?.clone());
+                },
+                "--region" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--region precisa de um valor")?;
+                    region = Some(Region::parse(v).ok_or(format!("--region invalida: {} (ntsc, pal, dendy)", v))?);
+                },
+                other if other.starts_with("--") => return Err(format!("Opcao desconhecida: {}", other)),
+                other => rom = Some(other.to_string()),
+            }
+            i += 1;
+        }
+
+        let rom = rom.ok_or("Uso: RNFE --test-rom <rom> [--frames N] [--crc XXXXXXXX] [--png out.png] [--region ntsc|pal|dendy]")?;
+        Ok(TestRomOptions { rom, frames, crc, png, region })
+    }
+}
+
+// Devolve o exit code: 0 passou, 1 falhou, 2 timeout sem resultado
+pub fn run_test_rom(opts: &TestRomOptions) -> Result<i32, Box<dyn std::error::Error>> {
+    let cartridge = rnfe::Cartridge::new(&opts.rom)?;
+    let mut nes = Nes::new();
+    nes.insert_cartridge(cartridge);
+    if let Some(r) = opts.region {
+        nes.set_region(r);
+    }
+    nes.reset();
+
+    let report = testrom::run(&mut nes, opts.frames, opts.crc);
+
+    if !report.message.is_empty() {
+        println!("{}", report.message);
+    }
+    let status = match report.outcome {
+        Outcome::Passed => "PASSOU".to_string(),
+        Outcome::Failed(code) if report.protocol => format!("FALHOU (codigo {})", code),
+        Outcome::Failed(_) => format!("FALHOU (CRC da tela {:08X}, esperado {:08X})", report.screen_crc, opts.crc.unwrap_or(0)),
+        Outcome::Jammed => "FALHOU (CPU travou)".to_string(),
+        Outcome::Timeout if report.protocol => "TIMEOUT (a ROM nao terminou)".to_string(),
+        Outcome::Timeout => format!("SEM RESULTADO (sem protocolo $6000, CRC da tela {:08X})", report.screen_crc),
+    };
+    println!("{}: {} em {} frames", opts.rom, status, report.frames);
+
+    if let Some(ref path) = opts.png {
+        write_png(path, nes.framebuffer())?;
+    }
+
+    Ok(report.exit_code())
+}
+
+pub struct TraceDiffOptions {
+    pub rom: String,
+    pub reference: String,
+    pub frames: u32,
+    pub start_pc: Option<u16>,
+    pub ignore: Vec<String>,
+    pub context: usize,
+    pub region: Option<Region>,
+}
+
+impl TraceDiffOptions {
+    // RNFE --trace-diff <rom> <ref.log> [--frames N] [--start-pc C000] [--ignore ppu,cyc] [--context N] [--region R]
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut files = Vec::new();
+        let mut frames = TEST_ROM_FRAMES;
+        let mut start_pc = None;
+        let mut ignore = Vec::new();
+        let mut context = DIFF_CONTEXT;
+        let mut region = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--frames" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--frames precisa de um valor")?;
+                    frames = v.parse().map_err(|_| format!("--frames invalido: {}", v))?;
+                },
+                "--start-pc" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--start-pc precisa de um endereço")?;
+                    let hex = v.trim_start_matches('$').trim_start_matches("0x");
+                    start_pc = Some(u16::from_str_radix(hex, 16).map_err(|_| format!("--start-pc invalido: {}", v))?);
+                },
+                "--ignore" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--ignore precisa de uma lista (ex: ppu,cyc)")?;
+                    ignore.extend(v.split(',').map(|f| f.trim().to_lowercase()));
+                },
+                "--context" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--context precisa de um valor")?;
+                    context = v.parse().map_err(|_| format!("--context invalido: {}", v))?;
+                },
+                "--region" => {
+                    i += 1;
+                    let v = args.get(i).ok_or("--region precisa de um valor")?;
+                    region = Some(Region::parse(v).ok_or(format!("--region invalida: {} (ntsc, pal, dendy)", v))?);
+                },
+                other if other.starts_with("--") => return Err(format!("Opcao desconhecida: {}", other)),
+                other => files.push(other.to_string()),
+            }
+            i += 1;
+        }
+
+        if files.len() != 2 {
+            return Err("Uso: RNFE --trace-diff <rom> <ref.log> [--frames N] [--start-pc C000] [--ignore pc,a,x,y,p,sp,ppu,cyc] [--context N] [--region ntsc|pal|dendy]".into());
+        }
+        let reference = files.pop().unwrap();
+        let rom = files.pop().unwrap();
+        Ok(TraceDiffOptions { rom, reference, frames, start_pc, ignore, context, region })
+    }
+}
+
+// Devolve o exit code: 0 bateu até o fim da referência, 1 achou diferença, 2 acabaram os frames
+pub fn run_trace_diff(opts: &TraceDiffOptions) -> Result<i32, Box<dyn std::error::Error>> {
+    let cartridge = rnfe::Cartridge::new(&opts.rom)?;
+    let mut nes = Nes::new();
+    nes.insert_cartridge(cartridge);
+    if let Some(r) = opts.region {
+        nes.set_region(r);
+    }
+    nes.reset();
+    // nestest.log começa em $C000 (modo automático), não no vetor de reset
+    if let Some(pc) = opts.start_pc {
+        nes.cpu.pc = pc;
+    }
+    nes.debugger.trace_diff = Some(TraceDiff::open(&opts.reference, opts.ignore.clone(), opts.context)?);
+
+    for _ in 0..opts.frames {
+        nes.run_frame();
+        if nes.debugger.trace_diff.as_ref().is_some_and(|d| d.finished()) {
+            break;
+        }
+    }
+
+    let diff = nes.debugger.trace_diff.take().ok_or("trace diff sumiu")?;
+    let Some(m) = diff.mismatch else {
+        if diff.exhausted {
+            println!("{}: igual ao {} ({} linhas)", opts.rom, diff.path, diff.line);
+            return Ok(0);
+        }
+        println!("{}: igual até a linha {} de {}, mas acabaram os {} frames", opts.rom, diff.line, diff.path, opts.frames);
+        return Ok(2);
+    };
+
+    println!("Diferença na linha {} de {}:", m.line, diff.path);
+    for f in &m.fields {
+        println!("  {:<8} esperado {:<16} obtido {}", f.field, f.expected, f.got);
+    }
+
+    println!("\nReferência:");
+    for l in &m.before {
+        println!("    {}", l);
+    }
+    println!("  > {}", m.expected);
+    for l in &m.after {
+        println!("    {}", l);
+    }
+
+    println!("\nRNFE:");
+    let ours: Vec<&str> = nes.debugger.trace_log.recent(m.before.len() + 1).collect();
+    for (i, l) in ours.iter().enumerate() {
+        let marker = if i + 1 == ours.len() { ">" } else { " " };
+        println!("  {} {}", marker, l);
+    }
+
+    Ok(1)
+}
+
+pub fn write_png(path: &str, screen: &[[u8; 3]]) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let data: Vec<u8> = screen.iter().flat_map(|p| p.iter().copied()).collect();
+    writer.write_image_data(&data)?;
+    Ok(())
+}
+
+// WAV PCM 16-bit mono
+pub fn write_wav(path: &str, samples: &[f32], sample_rate: u32) -> std::io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    let data_len = samples.len() as u32 * 2;
+
+    w.write_all(b"RIFF")?;
+    w.write_all(&(36 + data_len).to_le_bytes())?;
+    w.write_all(b"WAVE")?;
+
+    w.write_all(b"fmt ")?;
+    w.write_all(&16u32.to_le_bytes())?;          // tamanho do chunk fmt
+    w.write_all(&1u16.to_le_bytes())?;           // PCM
+    w.write_all(&1u16.to_le_bytes())?;           // mono
+    w.write_all(&sample_rate.to_le_bytes())?;
+    w.write_all(&(sample_rate * 2).to_le_bytes())?; // byte rate
+    w.write_all(&2u16.to_le_bytes())?;           // block align
+    w.write_all(&16u16.to_le_bytes())?;          // bits por sample
+
+    w.write_all(b"data")?;
+    w.write_all(&data_len.to_le_bytes())?;
+    for &s in samples {
+        let v = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        w.write_all(&v.to_le_bytes())?;
+    }
+    w.flush()
+}
@@ -0,0 +1,29 @@
+// RNFE core - emulador sem janela, sem audio e sem dependencias de frontend.
+// O binario (main.rs/display.rs) é só um dos consumidores disso aqui.
+pub mod cpu6502;
+pub mod bus;
+pub mod dma;
+pub mod ppu;
+pub mod apu;
+pub mod cartridge;
+pub mod mappers;
+pub mod debug;
+pub mod callstack;
+pub mod disasm;
+pub mod symbols;
+pub mod source;
+pub mod trace;
+pub mod expr;
+pub mod gdb;
+pub mod console;
+pub mod watch;
+pub mod diagnostic;
+pub mod nes;
+pub mod region;
+pub mod savestate;
+pub mod rewind;
+pub mod testrom;
+
+pub use nes::Nes;
+pub use cartridge::Cartridge;
+pub use region::Region;
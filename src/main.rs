@@ -1,26 +1,24 @@
-mod cpu6502;
-mod bus;
-mod ppu;
-mod apu;
-mod cartridge;
-mod mappers;
 mod display;
 mod font;
 mod ui;
-mod debug;
-mod diagnostic;
-mod nes;
+mod headless;
 
-use nes::Nes;
-use cartridge::Cartridge;
+use rnfe::{Nes, Cartridge, Region};
+use rnfe::debug::Breakpoint;
+use rnfe::watch::Watchpoint;
 use std::env;
 
-fn load_rom(path: &str) -> Option<Box<Nes>> {
+// region: override manual (senão vem do header/nome do arquivo)
+fn load_rom(path: &str, region: Option<Region>) -> Option<Box<Nes>> {
     match Cartridge::new(path) {
         Ok(cartridge) => {
             println!("ROM carregada: {}", path);
             let mut nes = Box::new(Nes::new());
             nes.insert_cartridge(cartridge);
+            if let Some(r) = region {
+                nes.set_region(r);
+            }
+            println!("Região: {:?}", nes.region);
             nes.reset();
             Some(nes)
         },
@@ -42,13 +40,71 @@ pub fn pick_rom() -> Option<String> {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() >= 2 {
-        match load_rom(&args[1]) {
-            Some(nes) => display::run_with_nes(nes)?,
-            None => display::run()?,
+    if args.len() >= 2 && args[1] == "--headless" {
+        let opts = headless::HeadlessOptions::parse(&args[2..])?;
+        return headless::run(&opts);
+    }
+
+    if args.len() >= 2 && args[1] == "--test-rom" {
+        let opts = headless::TestRomOptions::parse(&args[2..])?;
+        let code = headless::run_test_rom(&opts)?;
+        std::process::exit(code);
+    }
+
+    if args.len() >= 2 && args[1] == "--trace-diff" {
+        let opts = headless::TraceDiffOptions::parse(&args[2..])?;
+        let code = headless::run_trace_diff(&opts)?;
+        std::process::exit(code);
+    }
+
+    let mut opts = display::Options { rewind_secs: 60, region: None, watches: Vec::new(), breaks: Vec::new(), gdb_port: None, symbols: Vec::new(), console: false };
+    let mut rom: Option<String> = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--rewind" => {
+                i += 1;
+                opts.rewind_secs = args.get(i).and_then(|v| v.parse().ok())
+                    .ok_or("--rewind precisa de um número de segundos")?;
+            }
+            "--region" => {
+                i += 1;
+                opts.region = Some(args.get(i).and_then(|v| Region::parse(v))
+                    .ok_or("--region precisa ser ntsc, pal ou dendy")?);
+            }
+            // [cpu:|ppu:]ADDR[-END][:rwx][:log][ if COND], pode repetir
+            "--watch" => {
+                i += 1;
+                let v = args.get(i).ok_or("--watch precisa ser [cpu:|ppu:]ADDR[-END][:rwx][:log][ if COND]")?;
+                opts.watches.push(Watchpoint::parse(v).map_err(|e| format!("--watch {}: {}", v, e))?);
+            }
+            // ADDR[:log][ if COND], ex: --break "C003 if A == $10 && hits > 2"
+            "--break" => {
+                i += 1;
+                let v = args.get(i).ok_or("--break precisa ser ADDR[:log][ if COND]")?;
+                opts.breaks.push(Breakpoint::parse(v).map_err(|e| format!("--break {}: {}", v, e))?);
+            }
+            // .dbg (ca65), .nl (FCEUX) ou .mlb (Mesen), pode repetir
+            "--symbols" => {
+                i += 1;
+                opts.symbols.push(args.get(i).ok_or("--symbols precisa de um arquivo")?.clone());
+            }
+            // Porta do stub do GDB (0 = qualquer uma livre)
+            "--gdb" => {
+                i += 1;
+                opts.gdb_port = Some(args.get(i).and_then(|v| v.parse().ok())
+                    .ok_or("--gdb precisa de uma porta")?);
+            }
+            // Debugger pelo stdin junto com a janela (b, w, s, n, c, r, m...)
+            "--console" => opts.console = true,
+            other => rom = Some(other.to_string()),
         }
-    } else {
-        display::run()?;
+        i += 1;
+    }
+
+    match rom.and_then(|r| load_rom(&r, opts.region)) {
+        Some(nes) => display::run_with_nes(nes, &opts)?,
+        None => display::run(&opts)?,
     }
 
     Ok(())
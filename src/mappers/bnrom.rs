@@ -1,5 +1,6 @@
 // Mapper 034 (BNROM) - 32KB PRG switching, CHR RAM
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct Bnrom {
     prg_bank: u8,
@@ -11,12 +12,15 @@ impl Bnrom {
 
 impl Mapper for Bnrom {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
-        if addr >= 0x8000 {
-            let offset = self.prg_bank as usize * 0x8000 + (addr as usize - 0x8000);
-            Some(data.prg[offset % data.prg.len()])
-        } else {
-            None
+        self.prg_offset(addr, data).map(|i| data.prg[i])
+    }
+
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        if addr < 0x8000 {
+            return None;
         }
+        let offset = self.prg_bank as usize * 0x8000 + (addr as usize - 0x8000);
+        Some(offset % data.prg.len())
     }
 
     fn cpu_write(&mut self, addr: u16, val: u8, _data: &mut CartData) -> bool {
@@ -37,4 +41,13 @@ impl Mapper for Bnrom {
     }
 
     fn reset(&mut self, _prg_banks: u8) { self.prg_bank = 0; }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.prg_bank = r.u8()?;
+        Ok(())
+    }
 }
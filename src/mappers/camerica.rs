@@ -1,5 +1,6 @@
 // Mapper 071 (Camerica) - 16KB PRG switch at $8000, last bank fixed at $C000
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct Camerica {
     prg_bank: u8,
@@ -11,16 +12,19 @@ impl Camerica {
 
 impl Mapper for Camerica {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
-        if addr >= 0xC000 {
+        self.prg_offset(addr, data).map(|i| data.prg[i])
+    }
+
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        let offset = if addr >= 0xC000 {
             let bank = (data.prg_banks as usize).wrapping_sub(1);
-            let offset = bank * 0x4000 + (addr as usize - 0xC000);
-            Some(data.prg[offset % data.prg.len()])
+            bank * 0x4000 + (addr as usize - 0xC000)
         } else if addr >= 0x8000 {
-            let offset = self.prg_bank as usize * 0x4000 + (addr as usize - 0x8000);
-            Some(data.prg[offset % data.prg.len()])
+            self.prg_bank as usize * 0x4000 + (addr as usize - 0x8000)
         } else {
-            None
-        }
+            return None;
+        };
+        Some(offset % data.prg.len())
     }
 
     fn cpu_write(&mut self, addr: u16, val: u8, _data: &mut CartData) -> bool {
@@ -42,4 +46,13 @@ impl Mapper for Camerica {
     }
 
     fn reset(&mut self, _prg_banks: u8) { self.prg_bank = 0; }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.prg_bank = r.u8()?;
+        Ok(())
+    }
 }
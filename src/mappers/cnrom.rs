@@ -1,5 +1,6 @@
 // Mapper 003 (CNROM) - 8KB CHR bank switching
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct Cnrom {
     chr_bank: u8,
@@ -11,12 +12,15 @@ impl Cnrom {
 
 impl Mapper for Cnrom {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
-        if addr >= 0x8000 {
-            let masked = addr & if data.prg_banks > 1 { 0x7FFF } else { 0x3FFF };
-            Some(data.prg[masked as usize % data.prg.len()])
-        } else {
-            None
+        self.prg_offset(addr, data).map(|i| data.prg[i])
+    }
+
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        if addr < 0x8000 {
+            return None;
         }
+        let masked = addr & if data.prg_banks > 1 { 0x7FFF } else { 0x3FFF };
+        Some(masked as usize % data.prg.len())
     }
 
     fn cpu_write(&mut self, addr: u16, val: u8, _data: &mut CartData) -> bool {
@@ -38,4 +42,13 @@ impl Mapper for Cnrom {
     }
 
     fn reset(&mut self, _prg_banks: u8) { self.chr_bank = 0; }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.chr_bank = r.u8()?;
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 // Mapper 011 (Color Dreams) - bits 0-1 = PRG bank, bits 4-5 = CHR bank
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct ColorDreams {
     prg_bank: u8,
@@ -12,12 +13,15 @@ impl ColorDreams {
 
 impl Mapper for ColorDreams {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
-        if addr >= 0x8000 {
-            let offset = self.prg_bank as usize * 0x8000 + (addr as usize - 0x8000);
-            Some(data.prg[offset % data.prg.len()])
-        } else {
-            None
+        self.prg_offset(addr, data).map(|i| data.prg[i])
+    }
+
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        if addr < 0x8000 {
+            return None;
         }
+        let offset = self.prg_bank as usize * 0x8000 + (addr as usize - 0x8000);
+        Some(offset % data.prg.len())
     }
 
     fn cpu_write(&mut self, addr: u16, val: u8, _data: &mut CartData) -> bool {
@@ -40,4 +44,15 @@ impl Mapper for ColorDreams {
     }
 
     fn reset(&mut self, _prg_banks: u8) { self.prg_bank = 0; self.chr_bank = 0; }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.prg_bank);
+        w.u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.prg_bank = r.u8()?;
+        self.chr_bank = r.u8()?;
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 // Mapper 206 (DxROM) - MMC3 simplificado, sem IRQ
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct Dxrom {
     bank_select: u8,
@@ -20,24 +21,24 @@ impl Dxrom {
 impl Mapper for Dxrom {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
         if addr >= 0x8000 {
-            let bank = match addr {
-                0x8000..=0x9FFF => self.prg_banks[0],
-                0xA000..=0xBFFF => self.prg_banks[1],
-                0xC000..=0xDFFF => self.prg_banks[2],
-                0xE000..=0xFFFF => self.prg_banks[3],
-                _ => 0,
-            };
-            let offset = bank as usize * 0x2000 + (addr & 0x1FFF) as usize;
-            if offset < data.prg.len() {
-                Some(data.prg[offset])
-            } else {
-                Some(0)
-            }
+            Some(self.prg_offset(addr, data).map_or(0, |i| data.prg[i]))
         } else {
             None
         }
     }
 
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        let bank = match addr {
+            0x8000..=0x9FFF => self.prg_banks[0],
+            0xA000..=0xBFFF => self.prg_banks[1],
+            0xC000..=0xDFFF => self.prg_banks[2],
+            0xE000..=0xFFFF => self.prg_banks[3],
+            _ => return None,
+        };
+        let offset = bank as usize * 0x2000 + (addr & 0x1FFF) as usize;
+        (offset < data.prg.len()).then_some(offset)
+    }
+
     fn cpu_write(&mut self, addr: u16, val: u8, data: &mut CartData) -> bool {
         match addr {
             0x8000..=0x9FFF => {
@@ -99,4 +100,17 @@ impl Mapper for Dxrom {
         self.prg_banks = [0, 1, (prg_banks * 2).wrapping_sub(2), (prg_banks * 2).wrapping_sub(1)];
         self.chr_banks = [0, 1, 2, 3, 4, 5, 6, 7];
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.bank_select);
+        w.bytes(&self.prg_banks);
+        w.bytes(&self.chr_banks);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.bank_select = r.u8()?;
+        r.bytes(&mut self.prg_banks)?;
+        r.bytes(&mut self.chr_banks)?;
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 // Mapper 069 (FME-7/Sunsoft-5B)
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 use crate::cartridge::Mirror;
 
 pub struct Fme7 {
@@ -21,39 +22,30 @@ impl Fme7 {
 impl Mapper for Fme7 {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
         match addr {
-            0x6000..=0x7FFF => {
-                let bank = self.prg_banks[0] as usize & 0x3F;
-                let offset = bank * 0x2000 + (addr as usize - 0x6000);
-                if offset < data.prg.len() {
-                    Some(data.prg[offset])
-                } else {
-                    Some(0)
-                }
-            },
-            0x8000..=0x9FFF => {
-                let bank = self.prg_banks[1] as usize & 0x3F;
-                let offset = bank * 0x2000 + (addr as usize - 0x8000);
-                Some(data.prg[offset % data.prg.len()])
-            },
-            0xA000..=0xBFFF => {
-                let bank = self.prg_banks[2] as usize & 0x3F;
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                Some(data.prg[offset % data.prg.len()])
-            },
-            0xC000..=0xDFFF => {
-                let bank = self.prg_banks[3] as usize & 0x3F;
-                let offset = bank * 0x2000 + (addr as usize - 0xC000);
-                Some(data.prg[offset % data.prg.len()])
-            },
-            0xE000..=0xFFFF => {
-                let bank = (data.prg_banks as usize * 2).wrapping_sub(1);
-                let offset = bank * 0x2000 + (addr as usize - 0xE000);
-                Some(data.prg[offset % data.prg.len()])
-            },
+            // $6000 pode ser ROM ou RAM; aqui é sempre ROM, sem wrap (fora do tamanho lê 0)
+            0x6000..=0x7FFF => Some(self.prg_offset(addr, data).map_or(0, |i| data.prg[i])),
+            0x8000..=0xFFFF => self.prg_offset(addr, data).map(|i| data.prg[i]),
             _ => None,
         }
     }
 
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        let bank = match addr {
+            0x6000..=0x7FFF => self.prg_banks[0] as usize & 0x3F,
+            0x8000..=0x9FFF => self.prg_banks[1] as usize & 0x3F,
+            0xA000..=0xBFFF => self.prg_banks[2] as usize & 0x3F,
+            0xC000..=0xDFFF => self.prg_banks[3] as usize & 0x3F,
+            0xE000..=0xFFFF => (data.prg_banks as usize * 2).wrapping_sub(1),
+            _ => return None,
+        };
+        let offset = bank.wrapping_mul(0x2000).wrapping_add(addr as usize & 0x1FFF);
+        if addr < 0x8000 {
+            (offset < data.prg.len()).then_some(offset)
+        } else {
+            Some(offset % data.prg.len())
+        }
+    }
+
     fn cpu_write(&mut self, addr: u16, val: u8, data: &mut CartData) -> bool {
         match addr {
             0x8000..=0x9FFF => {
@@ -106,4 +98,17 @@ impl Mapper for Fme7 {
         self.prg_banks = [0; 4];
         self.chr_banks = [0; 8];
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.command);
+        w.bytes(&self.prg_banks);
+        w.bytes(&self.chr_banks);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.command = r.u8()?;
+        r.bytes(&mut self.prg_banks)?;
+        r.bytes(&mut self.chr_banks)?;
+        Ok(())
+    }
 }
@@ -4,6 +4,7 @@
 // A7: mode (0=32K, 1=16K)
 // A9: L flag
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 use crate::cartridge::Mirror;
 
 pub struct Mapper227 {
@@ -19,26 +20,29 @@ impl Mapper227 {
 
 impl Mapper for Mapper227 {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
-        if addr >= 0x8000 {
-            let reg = self.reg;
-            let p = (((reg >> 2) & 0x1F) | ((reg & 0x100) >> 3)) as usize;
-            let mode_32k = (reg >> 7) & 1;
+        self.prg_offset(addr, data).map(|i| data.prg[i])
+    }
 
-            let offset = if mode_32k != 0 {
-                let base = (p & 0x3E) * 0x4000;
-                base + (addr as usize - 0x8000)
-            } else {
-                if addr >= 0xC000 {
-                    self.fixed_bank * 0x4000 + ((addr as usize - 0xC000) & 0x3FFF)
-                } else {
-                    p * 0x4000 + (addr as usize - 0x8000)
-                }
-            };
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let reg = self.reg;
+        let p = (((reg >> 2) & 0x1F) | ((reg & 0x100) >> 3)) as usize;
+        let mode_32k = (reg >> 7) & 1;
 
-            Some(data.prg[offset % data.prg.len()])
+        let offset = if mode_32k != 0 {
+            let base = (p & 0x3E) * 0x4000;
+            base + (addr as usize - 0x8000)
         } else {
-            None
-        }
+            if addr >= 0xC000 {
+                self.fixed_bank * 0x4000 + ((addr as usize - 0xC000) & 0x3FFF)
+            } else {
+                p * 0x4000 + (addr as usize - 0x8000)
+            }
+        };
+
+        Some(offset % data.prg.len())
     }
 
     fn cpu_write(&mut self, addr: u16, _val: u8, data: &mut CartData) -> bool {
@@ -63,4 +67,15 @@ impl Mapper for Mapper227 {
         self.reg = 0;
         self.fixed_bank = 0;
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u16(self.reg);
+        w.u32(self.fixed_bank as u32);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.reg = r.u16()?;
+        self.fixed_bank = r.u32()? as usize;
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 // Mapper 001 (MMC1) - PRG/CHR bank switching via serial port
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 use crate::cartridge::Mirror;
 
 pub struct Mmc1 {
@@ -27,32 +28,7 @@ impl Mmc1 {
 impl Mapper for Mmc1 {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
         if addr >= 0x8000 {
-            let prg_mode = (self.control >> 2) & 0x03;
-            let bank = match prg_mode {
-                0 | 1 => {
-                    let b = (self.prg_bank & 0x0E) as usize;
-                    b * 0x4000 + (addr as usize - 0x8000)
-                },
-                2 => {
-                    if addr < 0xC000 {
-                        addr as usize - 0x8000
-                    } else {
-                        (self.prg_bank & 0x0F) as usize * 0x4000 + (addr as usize - 0xC000)
-                    }
-                },
-                3 | _ => {
-                    if addr < 0xC000 {
-                        (self.prg_bank & 0x0F) as usize * 0x4000 + (addr as usize - 0x8000)
-                    } else {
-                        (data.prg_banks as usize - 1) * 0x4000 + (addr as usize - 0xC000)
-                    }
-                },
-            };
-            if bank < data.prg.len() {
-                Some(data.prg[bank])
-            } else {
-                Some(0)
-            }
+            Some(self.prg_offset(addr, data).map_or(0, |i| data.prg[i]))
         } else if addr >= 0x6000 {
             Some(data.prg_ram[(addr - 0x6000) as usize])
         } else {
@@ -60,6 +36,34 @@ impl Mapper for Mmc1 {
         }
     }
 
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let prg_mode = (self.control >> 2) & 0x03;
+        let bank = match prg_mode {
+            0 | 1 => {
+                let b = (self.prg_bank & 0x0E) as usize;
+                b * 0x4000 + (addr as usize - 0x8000)
+            },
+            2 => {
+                if addr < 0xC000 {
+                    addr as usize - 0x8000
+                } else {
+                    (self.prg_bank & 0x0F) as usize * 0x4000 + (addr as usize - 0xC000)
+                }
+            },
+            _ => {  // 3: último banco fixo em $C000
+                if addr < 0xC000 {
+                    (self.prg_bank & 0x0F) as usize * 0x4000 + (addr as usize - 0x8000)
+                } else {
+                    (data.prg_banks as usize - 1) * 0x4000 + (addr as usize - 0xC000)
+                }
+            },
+        };
+        (bank < data.prg.len()).then_some(bank)
+    }
+
     fn cpu_write(&mut self, addr: u16, val: u8, data: &mut CartData) -> bool {
         if addr >= 0x6000 && addr < 0x8000 {
             data.prg_ram[(addr - 0x6000) as usize] = val;
@@ -143,4 +147,23 @@ impl Mapper for Mmc1 {
         println!("  MMC1 ctrl: ${:02X}  PRG bank: {}  CHR banks: {}/{}",
             self.control, self.prg_bank, self.chr_bank0, self.chr_bank1);
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.shift);
+        w.u8(self.shift_count);
+        w.u8(self.control);
+        w.u8(self.chr_bank0);
+        w.u8(self.chr_bank1);
+        w.u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.shift = r.u8()?;
+        self.shift_count = r.u8()?;
+        self.control = r.u8()?;
+        self.chr_bank0 = r.u8()?;
+        self.chr_bank1 = r.u8()?;
+        self.prg_bank = r.u8()?;
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 // Mapper 009 (MMC2) - Punch-Out!! (latch-based CHR switching)
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 use crate::cartridge::Mirror;
 
 pub struct Mmc2 {
@@ -20,28 +21,19 @@ impl Mmc2 {
 
 impl Mapper for Mmc2 {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
-        match addr {
-            0x8000..=0x9FFF => {
-                let offset = self.prg_bank as usize * 0x2000 + (addr as usize - 0x8000);
-                Some(data.prg[offset % data.prg.len()])
-            },
-            0xA000..=0xBFFF => {
-                let bank = (data.prg_banks as usize * 2).wrapping_sub(3);
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                Some(data.prg[offset % data.prg.len()])
-            },
-            0xC000..=0xDFFF => {
-                let bank = (data.prg_banks as usize * 2).wrapping_sub(2);
-                let offset = bank * 0x2000 + (addr as usize - 0xC000);
-                Some(data.prg[offset % data.prg.len()])
-            },
-            0xE000..=0xFFFF => {
-                let bank = (data.prg_banks as usize * 2).wrapping_sub(1);
-                let offset = bank * 0x2000 + (addr as usize - 0xE000);
-                Some(data.prg[offset % data.prg.len()])
-            },
-            _ => None,
-        }
+        self.prg_offset(addr, data).map(|i| data.prg[i])
+    }
+
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        let bank = match addr {
+            0x8000..=0x9FFF => self.prg_bank as usize,
+            0xA000..=0xBFFF => (data.prg_banks as usize * 2).wrapping_sub(3),
+            0xC000..=0xDFFF => (data.prg_banks as usize * 2).wrapping_sub(2),
+            0xE000..=0xFFFF => (data.prg_banks as usize * 2).wrapping_sub(1),
+            _ => return None,
+        };
+        let offset = bank.wrapping_mul(0x2000).wrapping_add(addr as usize & 0x1FFF);
+        Some(offset % data.prg.len())
     }
 
     fn cpu_write(&mut self, addr: u16, val: u8, data: &mut CartData) -> bool {
@@ -99,4 +91,17 @@ impl Mapper for Mmc2 {
         self.chr_banks = [0; 4];
         self.latch = [0xFE, 0xFE];
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.prg_bank);
+        w.bytes(&self.chr_banks);
+        w.bytes(&self.latch);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.prg_bank = r.u8()?;
+        r.bytes(&mut self.chr_banks)?;
+        r.bytes(&mut self.latch)?;
+        Ok(())
+    }
 }
@@ -1,9 +1,30 @@
 // Mapper 004 (MMC3) - PRG/CHR bank switching + scanline IRQ
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 use crate::cartridge::Mirror;
 
+// Submapper (NES 2.0): 0 = MMC3C, 1 = MMC6, 4 = MMC3A
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mmc3Variant {
+    Mmc3C,
+    Mmc3A, // IRQ "antigo": só dispara quando o contador chega em 0 decrementando
+    Mmc6,  // 1K de PRG-RAM em $7000-$7FFF com proteção por metade
+}
+
+impl Mmc3Variant {
+    pub fn from_submapper(submapper: u8) -> Self {
+        match submapper {
+            1 => Mmc3Variant::Mmc6,
+            4 => Mmc3Variant::Mmc3A,
+            _ => Mmc3Variant::Mmc3C,
+        }
+    }
+}
+
 pub struct Mmc3 {
+    variant: Mmc3Variant,
     bank_select: u8,
+    ram_protect: u8, // $A001
     prg_banks: [u8; 4],
     chr_banks: [u8; 8],
     irq_counter: u8,
@@ -13,9 +34,11 @@ pub struct Mmc3 {
 }
 
 impl Mmc3 {
-    pub fn new(prg_banks: u8) -> Self {
+    pub fn new(prg_banks: u8, variant: Mmc3Variant) -> Self {
         Mmc3 {
+            variant,
             bank_select: 0,
+            ram_protect: 0,
             prg_banks: [0, 1, (prg_banks * 2).wrapping_sub(2), (prg_banks * 2).wrapping_sub(1)],
             chr_banks: [0, 1, 2, 3, 4, 5, 6, 7],
             irq_counter: 0,
@@ -24,35 +47,69 @@ impl Mmc3 {
             irq_pending: false,
         }
     }
+
+    // MMC6: RAM só aparece com bit 5 do $8000 ligado; $A001 habilita
+    // leitura/escrita de cada metade de 512 bytes separadamente
+    fn mmc6_ram_read(&self, addr: u16, data: &CartData) -> Option<u8> {
+        if addr < 0x7000 || self.bank_select & 0x20 == 0 || self.ram_protect & 0xA0 == 0 {
+            return None;
+        }
+        let offset = (addr & 0x03FF) as usize;
+        let mask = if offset & 0x200 != 0 { 0x80 } else { 0x20 };
+        if self.ram_protect & mask != 0 {
+            Some(data.prg_ram[offset])
+        } else {
+            Some(0)
+        }
+    }
+
+    // false = escrita ignorada pela proteção
+    fn mmc6_ram_write(&self, addr: u16, val: u8, data: &mut CartData) -> bool {
+        if addr < 0x7000 || self.bank_select & 0x20 == 0 {
+            return false;
+        }
+        let offset = (addr & 0x03FF) as usize;
+        let mask = if offset & 0x200 != 0 { 0xC0 } else { 0x30 };
+        if self.ram_protect & mask != mask {
+            return false;
+        }
+        data.prg_ram[offset] = val;
+        true
+    }
 }
 
 impl Mapper for Mmc3 {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
         if addr >= 0x6000 && addr < 0x8000 {
+            if self.variant == Mmc3Variant::Mmc6 {
+                return self.mmc6_ram_read(addr, data);
+            }
             return Some(data.prg_ram[(addr - 0x6000) as usize]);
         }
         if addr >= 0x8000 {
-            let bank = match addr {
-                0x8000..=0x9FFF => self.prg_banks[0],
-                0xA000..=0xBFFF => self.prg_banks[1],
-                0xC000..=0xDFFF => self.prg_banks[2],
-                0xE000..=0xFFFF => self.prg_banks[3],
-                _ => 0
-            };
-            let bank_offset = (bank as usize) * 0x2000;
-            let addr_offset = (addr & 0x1FFF) as usize;
-            if bank_offset + addr_offset < data.prg.len() {
-                Some(data.prg[bank_offset + addr_offset])
-            } else {
-                Some(0)
-            }
+            Some(self.prg_offset(addr, data).map_or(0, |i| data.prg[i]))
         } else {
             None
         }
     }
 
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        let bank = match addr {
+            0x8000..=0x9FFF => self.prg_banks[0],
+            0xA000..=0xBFFF => self.prg_banks[1],
+            0xC000..=0xDFFF => self.prg_banks[2],
+            0xE000..=0xFFFF => self.prg_banks[3],
+            _ => return None,
+        };
+        let offset = (bank as usize) * 0x2000 + (addr & 0x1FFF) as usize;
+        (offset < data.prg.len()).then_some(offset)
+    }
+
     fn cpu_write(&mut self, addr: u16, val: u8, data: &mut CartData) -> bool {
         if addr >= 0x6000 && addr < 0x8000 {
+            if self.variant == Mmc3Variant::Mmc6 {
+                return self.mmc6_ram_write(addr, val, data);
+            }
             data.prg_ram[(addr - 0x6000) as usize] = val;
             return true;
         }
@@ -92,6 +149,8 @@ impl Mapper for Mmc3 {
             0xA000..=0xBFFF => {
                 if addr % 2 == 0 {
                     data.mirror = if val & 0x01 != 0 { Mirror::Horizontal } else { Mirror::Vertical };
+                } else if self.variant != Mmc3Variant::Mmc6 || self.bank_select & 0x20 != 0 {
+                    self.ram_protect = val;
                 }
                 true
             },
@@ -156,24 +215,29 @@ impl Mapper for Mmc3 {
     }
 
     fn clock_scanline(&mut self) {
+        let prev = self.irq_counter;
         if self.irq_counter == 0 {
             self.irq_counter = self.irq_reload;
         } else {
             self.irq_counter -= 1;
         }
-        if self.irq_counter == 0 && self.irq_enabled {
+        let fire = match self.variant {
+            Mmc3Variant::Mmc3A => self.irq_counter == 0 && prev != 0,
+            _ => self.irq_counter == 0,
+        };
+        if fire && self.irq_enabled {
             self.irq_pending = true;
         }
     }
 
-    fn mapper_irq(&mut self) -> bool {
-        let pending = self.irq_pending;
-        self.irq_pending = false;
-        pending
+    // Só desliga com escrita em $E000
+    fn mapper_irq(&self) -> bool {
+        self.irq_pending
     }
 
     fn reset(&mut self, prg_banks: u8) {
         self.bank_select = 0;
+        self.ram_protect = 0;
         self.prg_banks = [0, 1, (prg_banks * 2).wrapping_sub(2), (prg_banks * 2).wrapping_sub(1)];
         self.chr_banks = [0, 1, 2, 3, 4, 5, 6, 7];
         self.irq_counter = 0;
@@ -183,6 +247,7 @@ impl Mapper for Mmc3 {
     }
 
     fn print_state(&self) {
+        println!("  MMC3 variant: {:?}  RAM protect: ${:02X}", self.variant, self.ram_protect);
         println!("  MMC3 bank_select: ${:02X} (CHR_A12_inv={} PRG_mode={})",
             self.bank_select,
             if self.bank_select & 0x80 != 0 { "yes" } else { "no" },
@@ -195,4 +260,48 @@ impl Mapper for Mmc3 {
         println!("  MMC3 IRQ: counter={} reload={} enabled={} pending={}",
             self.irq_counter, self.irq_reload, self.irq_enabled, self.irq_pending);
     }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.bank_select);
+        w.u8(self.ram_protect);
+        w.bytes(&self.prg_banks);
+        w.bytes(&self.chr_banks);
+        w.u8(self.irq_counter);
+        w.u8(self.irq_reload);
+        w.bool(self.irq_enabled);
+        w.bool(self.irq_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.bank_select = r.u8()?;
+        self.ram_protect = r.u8()?;
+        r.bytes(&mut self.prg_banks)?;
+        r.bytes(&mut self.chr_banks)?;
+        self.irq_counter = r.u8()?;
+        self.irq_reload = r.u8()?;
+        self.irq_enabled = r.bool()?;
+        self.irq_pending = r.bool()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartridge::{test_rom, Cartridge};
+
+    // NES 2.0, mapper 4 submapper 1 (MMC6), com bateria
+    #[test]
+    fn mmc6_protected_write_is_not_dirty() {
+        let mut cart = Cartridge::from_bytes(&test_rom(2, [0x42, 0x08, 0x10], &[])).unwrap();
+
+        cart.cpu_write(0x8000, 0x20);   // liga a RAM, proteção ainda fechada
+        assert!(!cart.cpu_write(0x7000, 0x55));
+        assert!(!cart.sram_dirty());
+        assert_eq!(cart.cpu_read(0x7000), None);
+
+        cart.cpu_write(0xA001, 0x30);   // metade de baixo: lê e escreve
+        assert!(cart.cpu_write(0x7000, 0x55));
+        assert!(cart.sram_dirty());
+        assert_eq!(cart.cpu_read(0x7000), Some(0x55));
+    }
 }
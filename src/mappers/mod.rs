@@ -13,7 +13,8 @@ pub mod camerica;
 pub mod dxrom;
 pub mod mapper227;
 
-use crate::cartridge::Mirror;
+use crate::cartridge::{Mirror, RomHeader};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct CartData {
     pub prg: Vec<u8>,
@@ -26,21 +27,30 @@ pub struct CartData {
 
 pub trait Mapper {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8>;
+    // Índice em data.prg que está mapeado em addr agora (None = não é PRG ROM).
+    // O debugger usa pra saber em que banco o código está (símbolos por banco)
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize>;
     fn cpu_write(&mut self, addr: u16, val: u8, data: &mut CartData) -> bool;
     fn ppu_read(&mut self, addr: u16, data: &CartData) -> Option<u8>;
     fn clock_scanline(&mut self) {}
-    fn mapper_irq(&mut self) -> bool { false }
+    // Nível da linha IRQ do cartucho; fica ligada até o jogo dar ack no mapper
+    fn mapper_irq(&self) -> bool { false }
     fn reset(&mut self, prg_banks: u8);
     fn print_state(&self) {}
+    // Registradores internos do mapper (bancos, latches, IRQ) pro save state
+    fn save_state(&self, w: &mut StateWriter);
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()>;
 }
 
-pub fn create_mapper(id: u8, prg_banks: u8) -> Box<dyn Mapper> {
-    match id {
+// Recebe o header inteiro: o submapper (NES 2.0) escolhe variantes do mesmo chip
+pub fn create_mapper(header: &RomHeader) -> Box<dyn Mapper> {
+    let prg_banks = header.prg_banks();
+    match header.mapper {
         0 => Box::new(nrom::Nrom),
         1 => Box::new(mmc1::Mmc1::new()),
         2 => Box::new(uxrom::Uxrom::new()),
         3 => Box::new(cnrom::Cnrom::new()),
-        4 => Box::new(mmc3::Mmc3::new(prg_banks)),
+        4 => Box::new(mmc3::Mmc3::new(prg_banks, mmc3::Mmc3Variant::from_submapper(header.submapper))),
         7 => Box::new(axrom::Axrom::new()),
         9 => Box::new(mmc2::Mmc2::new()),
         11 => Box::new(colordreams::ColorDreams::new()),
@@ -1,21 +1,36 @@
 // Mapper 000 (NROM) - sem bank switching
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct Nrom;
 
 impl Mapper for Nrom {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
         if addr >= 0x8000 {
-            let masked = addr & if data.prg_banks > 1 { 0x7FFF } else { 0x3FFF };
-            let index = (masked & 0x3FFF) as usize;
-            Some(data.prg[index])
+            self.prg_offset(addr, data).map(|i| data.prg[i])
+        } else if addr >= 0x6000 {
+            // PRG RAM (Family Basic e as ROMs de teste que reportam em $6000)
+            Some(data.prg_ram[(addr - 0x6000) as usize])
         } else {
             None
         }
     }
 
-    fn cpu_write(&mut self, _addr: u16, _val: u8, _data: &mut CartData) -> bool {
-        false
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let masked = addr & if data.prg_banks > 1 { 0x7FFF } else { 0x3FFF };
+        Some((masked & 0x3FFF) as usize)
+    }
+
+    fn cpu_write(&mut self, addr: u16, val: u8, data: &mut CartData) -> bool {
+        if (0x6000..=0x7FFF).contains(&addr) {
+            data.prg_ram[(addr - 0x6000) as usize] = val;
+            true
+        } else {
+            false
+        }
     }
 
     fn ppu_read(&mut self, addr: u16, data: &CartData) -> Option<u8> {
@@ -27,4 +42,9 @@ impl Mapper for Nrom {
     }
 
     fn reset(&mut self, _prg_banks: u8) {}
+
+    // NROM não tem registradores
+    fn save_state(&self, _w: &mut StateWriter) {}
+
+    fn load_state(&mut self, _r: &mut StateReader) -> StateResult<()> { Ok(()) }
 }
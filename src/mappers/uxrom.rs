@@ -1,5 +1,6 @@
 // Mapper 002 (UxROM) - 16KB PRG switching, CHR RAM
 use super::{Mapper, CartData};
+use crate::savestate::{StateReader, StateResult, StateWriter};
 
 pub struct Uxrom {
     bank: u8,
@@ -11,15 +12,18 @@ impl Uxrom {
 
 impl Mapper for Uxrom {
     fn cpu_read(&self, addr: u16, data: &CartData) -> Option<u8> {
-        if addr >= 0xC000 {
-            let offset = (data.prg_banks as usize - 1) * 0x4000 + (addr as usize - 0xC000);
-            Some(data.prg[offset % data.prg.len()])
+        self.prg_offset(addr, data).map(|i| data.prg[i])
+    }
+
+    fn prg_offset(&self, addr: u16, data: &CartData) -> Option<usize> {
+        let offset = if addr >= 0xC000 {
+            (data.prg_banks as usize - 1) * 0x4000 + (addr as usize - 0xC000)
         } else if addr >= 0x8000 {
-            let offset = self.bank as usize * 0x4000 + (addr as usize - 0x8000);
-            Some(data.prg[offset % data.prg.len()])
+            self.bank as usize * 0x4000 + (addr as usize - 0x8000)
         } else {
-            None
-        }
+            return None;
+        };
+        Some(offset % data.prg.len())
     }
 
     fn cpu_write(&mut self, addr: u16, val: u8, _data: &mut CartData) -> bool {
@@ -40,4 +44,13 @@ impl Mapper for Uxrom {
     }
 
     fn reset(&mut self, _prg_banks: u8) { self.bank = 0; }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.u8(self.bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        self.bank = r.u8()?;
+        Ok(())
+    }
 }
@@ -2,12 +2,22 @@ use crate::cpu6502::Cpu6502;
 use crate::bus::Bus;
 use crate::cartridge::Cartridge;
 use crate::debug::Debugger;
+use crate::region::Region;
+use crate::savestate::{self, StateReader, StateWriter};
+
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
 
 pub struct Nes {
     pub cpu: Cpu6502,
     pub bus: Bus,
     pub debugger: Debugger,
+    pub region: Region,
     system_clock_counter: u32,
+    // Divisor PPU -> CPU (3 no NTSC/Dendy, 3.2 no PAL) e contador de CPU cycles
+    cpu_phase: u32,
+    cpu_cycle: u64,
+    instr_reported: bool, // hook do debugger já rodou pra instrução atual
 }
 
 impl Nes {
@@ -16,30 +26,184 @@ impl Nes {
             cpu: Cpu6502::new(),
             bus: Bus::new(),
             debugger: Debugger::new(),
+            region: Region::Ntsc,
             system_clock_counter: 0,
+            cpu_phase: 0,
+            cpu_cycle: 0,
+            instr_reported: false,
         }
     }
 
+    // A região vem do header/nome do arquivo; set_region depois sobrescreve
     pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
+        let region = Region::detect(&cartridge);
+        // Símbolos do lado da ROM (jogo.dbg, jogo.mlb, jogo.nes.N.nl)
+        self.debugger.symbols.clear();
+        if let Some(ref path) = cartridge.rom_path {
+            self.debugger.symbols.set_prg_start(if cartridge.header.trainer { 16 + 512 } else { 16 });
+            self.debugger.symbols.load_for_rom(path);
+        }
         self.bus.insert_cartridge(cartridge);
+        self.set_region(region);
         self.cpu.reset(&mut self.bus);
     }
 
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+        self.bus.ppu.region = region;
+        self.bus.apu.region = region;
+        self.cpu_phase = 0;
+    }
+
+    // Carrega um arquivo .nes inteiro (header + PRG + CHR) e reseta a máquina
+    pub fn load_rom(&mut self, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let cartridge = Cartridge::from_bytes(bytes)?;
+        self.insert_cartridge(cartridge);
+        self.reset();
+        Ok(())
+    }
+
+    // Roda até a PPU terminar o frame atual, ou até o debugger parar
+    // (breakpoint/step) - aí o frame fica pela metade e continua na próxima chamada.
+    // Retorna true só se o frame terminou; parado no debugger nem anda
+    pub fn run_frame(&mut self) -> bool {
+        if self.debugger.hit_breakpoint {
+            return false;
+        }
+        loop {
+            self.clock();
+            if self.bus.ppu.frame_complete {
+                self.bus.ppu.frame_complete = false;
+                break;
+            }
+            if self.debugger.hit_breakpoint {
+                return false;
+            }
+        }
+        self.debugger.total_frames += 1;
+        true
+    }
+
+    // Tela 256x240 em RGB, linha por linha
+    pub fn framebuffer(&self) -> &[[u8; 3]] {
+        &self.bus.ppu.screen[..]
+    }
+
+    // Pega os samples gerados desde a última chamada (mono, f32, na sample rate do Apu)
+    pub fn drain_audio(&mut self) -> Vec<f32> {
+        self.bus.apu.sample_buffer.drain(..).collect()
+    }
+
+    // Bits: A B Select Start Up Down Left Right (bit 7 = A)
+    pub fn set_input(&mut self, port: usize, buttons: u8) {
+        self.bus.controller[port & 1] = buttons;
+    }
+
+    // Salva a PRG-RAM com bateria no .sav (se tiver e se mudou)
+    pub fn flush_sav(&mut self) {
+        if let Some(ref mut cart) = self.bus.cartridge && let Err(e) = cart.flush_sav() {
+            eprintln!("Erro ao gravar .sav: {}", e);
+        }
+    }
+
+    // Snapshot da máquina inteira (CPU, PPU, APU, RAM, cartucho/mapper)
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::new();
+        w.bytes(savestate::MAGIC);
+        w.u16(savestate::VERSION);
+        match self.bus.cartridge {
+            Some(ref cart) => {
+                w.u16(cart.mapper_id());
+                w.u32(cart.prg_crc);
+            }
+            None => {
+                w.u16(0xFFFF);
+                w.u32(0);
+            }
+        }
+        self.cpu.save_state(&mut w);
+        self.bus.save_state(&mut w);
+        if let Some(ref cart) = self.bus.cartridge {
+            cart.save_state(&mut w);
+        }
+        w.u32(self.system_clock_counter);
+        w.u8(self.region.to_u8());
+        w.u32(self.cpu_phase);
+        w.u64(self.cpu_cycle);
+        w.buf
+    }
+
+    // Se o state for inválido ou de outra ROM, a máquina fica como estava
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut r = StateReader::new(data);
+        let mut magic = [0u8; 4];
+        r.bytes(&mut magic)?;
+        if &magic != savestate::MAGIC {
+            return Err("Não é um save state do RNFE".into());
+        }
+        let version = r.u16()?;
+        if version != savestate::VERSION {
+            return Err(format!("Versão de save state {} não suportada (esperado {})", version, savestate::VERSION).into());
+        }
+        let mapper = r.u16()?;
+        let crc = r.u32()?;
+        match self.bus.cartridge {
+            Some(ref cart) => {
+                if mapper != cart.mapper_id() || crc != cart.prg_crc {
+                    return Err("Save state é de outra ROM".into());
+                }
+            }
+            None => return Err("Nenhuma ROM carregada".into()),
+        }
+
+        let backup = self.save_state();
+        if let Err(e) = self.load_sections(&mut r) {
+            let mut r = StateReader::new(&backup[4 + 2 + 2 + 4..]);
+            self.load_sections(&mut r).expect("restaurar backup do save state");
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn load_sections(&mut self, r: &mut StateReader) -> Result<(), Box<dyn std::error::Error>> {
+        self.cpu.load_state(r)?;
+        self.bus.load_state(r)?;
+        if let Some(ref mut cart) = self.bus.cartridge {
+            cart.load_state(r)?;
+        }
+        self.system_clock_counter = r.u32()?;
+        self.set_region(Region::from_u8(r.u8()?));
+        self.cpu_phase = r.u32()? % self.region.ppu_cpu_ratio().0;
+        self.cpu_cycle = r.u64()?;
+        if !r.is_empty() {
+            return Err("Save state com dados sobrando".into());
+        }
+        // A pilha de chamadas de antes não vale mais
+        self.debugger.call_stack.clear();
+        self.debugger.pc_history.clear();
+        Ok(())
+    }
+
     pub fn clock(&mut self) {
-        // Setar cart_ptr pra todo o clock cycle (PPU reads E CPU writes precisam)
-        self.bus.ppu.cart_ptr = self.bus.cartridge.as_mut()
-            .map(|c| c as *mut crate::cartridge::Cartridge);
-        self.bus.ppu.clock(self.bus.cartridge.as_mut());
+        let (num, den) = self.region.ppu_cpu_ratio();
+        let cpu_tick = self.cpu_phase < den;
+
+        // Debug: hook antes da CPU começar uma instrução. Se for pra parar
+        // (breakpoint/step) esse clock não acontece; o próximo segue daqui.
+        // Se a busca do opcode virar halt de DMA ela é repetida, não conta de novo
+        if cpu_tick && !self.instr_reported && !self.bus.dma.cpu_halted() && self.cpu.is_instruction_start() {
+            self.instr_reported = true;
+            if self.debugger.on_instruction(&self.cpu, &mut self.bus) {
+                return;
+            }
+        }
+
+        self.bus.ppu.clock(&mut self.bus.cartridge);
 
         // Atualizar mirroring cada frame
         if self.bus.ppu.scanline == -1 && self.bus.ppu.cycle == 0 {
             if let Some(ref mut cart) = self.bus.cartridge {
-                self.bus.ppu.mirror_mode = match cart.get_mirror() {
-                    crate::cartridge::Mirror::Vertical => 0,
-                    crate::cartridge::Mirror::Horizontal => 1,
-                    crate::cartridge::Mirror::OneScreenLo => 2,
-                    crate::cartridge::Mirror::OneScreenHi => 3,
-                };
+                self.bus.ppu.mirror_mode = cart.get_mirror().to_u8();
             }
         }
 
@@ -48,48 +212,39 @@ impl Nes {
             self.bus.ppu.scanline_trigger = false;
             if let Some(ref mut cart) = self.bus.cartridge {
                 cart.clock_scanline();
-                if cart.mapper_irq() {
-                    self.cpu.irq(&mut self.bus);
-                }
             }
         }
 
-        if self.system_clock_counter % 3 == 0 {
+        self.cpu_phase = (self.cpu_phase + den) % num;
+
+        if cpu_tick {
+            // Linha IRQ compartilhada (open collector): qualquer fonte segura ela em nível baixo
+            let mapper_irq = self.bus.cartridge.as_ref().is_some_and(|c| c.mapper_irq());
+            self.cpu.set_irq_line(self.bus.apu.irq_line() || mapper_irq);
+
             self.bus.apu.clock();
             if let Some(addr) = self.bus.apu.dmc_read_addr.take() {
-                let data = self.bus.cpu_read(addr, false);
-                self.bus.apu.dmc_feed_sample(data);
+                self.bus.dma.request_dmc(addr);
             }
-            if self.bus.dma_transfer {
-                if self.bus.dma_dummy {
-                    if self.system_clock_counter % 2 == 1 {
-                        self.bus.dma_dummy = false;
-                    }
-                } else {
-                    if self.system_clock_counter % 2 == 0 {
-                        self.bus.dma_data = self.bus.cpu_read(
-                            (self.bus.dma_page as u16) << 8 | self.bus.dma_addr as u16, false
-                        );
-                    } else {
-                        self.bus.ppu.cpu_write(0x0004, self.bus.dma_data);
-                        self.bus.dma_addr = self.bus.dma_addr.wrapping_add(1);
-                        if self.bus.dma_addr == 0x00 {
-                            self.bus.dma_transfer = false;
-                            self.bus.dma_dummy = true;
-                        }
-                    }
-                }
+            if self.bus.dma.cpu_halted() {
+                // CPU parada pelo RDY: o ciclo é da DMA (get nos ciclos pares)
+                self.bus.clock_dma(self.cpu_cycle.is_multiple_of(2));
+                self.cpu.total_cycles += 1; // ciclo roubado conta no contador da CPU
             } else {
-                // Debug: trackear instrução antes de executar
-                if self.cpu.is_instruction_start() {
-                    self.debugger.on_instruction(&self.cpu, &self.bus);
-                }
                 self.cpu.clock(&mut self.bus);
+                if !self.bus.dma.cpu_halted() {
+                    self.instr_reported = false;
+                }
+            }
+            self.cpu_cycle += 1;
+
+            if !self.bus.watch.hits.is_empty() {
+                self.debugger.on_watch_hits(&self.cpu, &mut self.bus);
             }
         }
 
         if self.bus.ppu.get_nmi() {
-            self.cpu.nmi(&mut self.bus);
+            self.cpu.nmi();
         }
 
         self.system_clock_counter += 1;
@@ -98,6 +253,11 @@ impl Nes {
     pub fn reset(&mut self) {
         self.bus.reset();
         self.cpu.reset(&mut self.bus);
+        self.debugger.jam = None;
+        self.debugger.call_stack.clear();
+        self.debugger.pc_history.clear();
         self.system_clock_counter = 0;
+        self.cpu_phase = 0;
+        self.cpu_cycle = 0;
     }
 }
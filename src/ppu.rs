@@ -1,8 +1,21 @@
+use crate::region::Region;
+use crate::savestate::{StateReader, StateResult, StateWriter};
+
+// ~600ms sem renovar e o bit do latch de I/O decai pra 0
+// (em frames depende da região: 36 no NTSC, 30 no PAL/Dendy)
+const IO_LATCH_DECAY_SECS: f64 = 0.6;
+
+// O que fica do lado de fora da PPU no barramento dela: $0000-$1FFF (CHR)
+// é sempre do cartucho, via mapper. Nametables e paletas são internas.
+// Quem chama a PPU empresta o cartucho por chamada, nada de ponteiro guardado.
+pub trait PpuBus {
+    fn chr_read(&mut self, addr: u16) -> u8;
+    fn chr_write(&mut self, addr: u16, data: u8);
+}
+
 pub struct Ppu {
     pub nametable: [[u8; 1024]; 2],
     pub palette_table: [u8; 32],
-    pub pattern_table: [[u8; 4096]; 2],
-    pub cart_ptr: Option<*mut crate::cartridge::Cartridge>,
     
     // Status registers
     pub status: u8,
@@ -11,7 +24,7 @@ pub struct Ppu {
     
     // Internal registers
     address_latch: u8,
-    ppu_data_buffer: u8,
+    pub ppu_data_buffer: u8,
     pub vram_addr: u16,   // v register - current VRAM address
     pub tram_addr: u16,   // t register - temporary VRAM address
     fine_x: u8,
@@ -53,9 +66,18 @@ pub struct Ppu {
 
     // Frame par/ímpar
     odd_frame: bool,
+    pub frame_count: u32,
+
+    // Latch do barramento CPU<->PPU: registradores write-only e bits não
+    // usados devolvem o último valor escrito/lido, que some com o tempo
+    io_latch: u8,
+    io_refresh: [u32; 8], // frame em que cada bit foi renovado
 
     // Mirroring (0=vertical, 1=horizontal, 2=onescreen_lo, 3=onescreen_hi)
     pub mirror_mode: u8,
+
+    // Quantas linhas o frame tem, onde começa o vblank
+    pub region: Region,
 }
 
 #[derive(Clone, Copy)]
@@ -71,8 +93,6 @@ impl Ppu {
         Ppu {
             nametable: [[0; 1024]; 2],
             palette_table: [0; 32],
-            pattern_table: [[0; 4096]; 2],
-            cart_ptr: None,
             status: 0x80, // vblank flag setado no powerup
             mask: 0,
             control: 0,
@@ -104,16 +124,11 @@ impl Ppu {
             nmi: false,
             scanline_trigger: false,
             odd_frame: false,
+            frame_count: 0,
+            io_latch: 0,
+            io_refresh: [0; 8],
             mirror_mode: 0,
-        }
-    }
-
-    pub fn load_chr(&mut self, chr_data: &[u8]) {
-        let len = chr_data.len().min(8192);
-        for i in 0..len {
-            let table = (i >> 12) & 1;
-            let offset = i & 0x0FFF;
-            self.pattern_table[table][offset] = chr_data[i];
+            region: Region::Ntsc,
         }
     }
 
@@ -121,12 +136,33 @@ impl Ppu {
         match addr {
             0x0002 => self.status,
             0x0004 => self.oam[self.oam_addr as usize],
-            _ => 0,
+            _ => self.io_latch,
+        }
+    }
+
+    // Renova os bits de `mask` do latch com `value`
+    fn refresh_latch(&mut self, value: u8, mask: u8) {
+        self.io_latch = (self.io_latch & !mask) | (value & mask);
+        for bit in 0..8 {
+            if mask & (1 << bit) != 0 {
+                self.io_refresh[bit] = self.frame_count;
+            }
+        }
+    }
+
+    // Bits em 1 que ficaram ~600ms sem ser renovados voltam pra 0
+    fn decayed_latch(&mut self) -> u8 {
+        let decay = (IO_LATCH_DECAY_SECS * self.region.frame_rate()) as u32;
+        for bit in 0..8 {
+            if self.frame_count.wrapping_sub(self.io_refresh[bit]) > decay {
+                self.io_latch &= !(1 << bit);
+            }
         }
+        self.io_latch
     }
 
-    pub fn cpu_read(&mut self, addr: u16, read_only: bool) -> u8 {
-        let mut data = 0x00;
+    pub fn cpu_read(&mut self, addr: u16, read_only: bool, cart: &mut dyn PpuBus) -> u8 {
+        let mut data = self.decayed_latch();
         
         if read_only {
             match addr {
@@ -145,22 +181,32 @@ impl Ppu {
                 0x0000 => {},
                 0x0001 => {},
                 0x0002 => {
-                    data = (self.status & 0xE0) | (self.ppu_data_buffer & 0x1F);
+                    // Só os 3 bits de cima são do status, o resto é o latch
+                    data = (self.status & 0xE0) | (data & 0x1F);
+                    self.refresh_latch(data, 0xE0);
                     self.status &= 0x7F;
                     self.address_latch = 0;
                 },
                 0x0003 => {},
-                0x0004 => data = self.oam[self.oam_addr as usize],
+                0x0004 => {
+                    data = self.oam[self.oam_addr as usize];
+                    self.refresh_latch(data, 0xFF);
+                },
                 0x0005 => {},
                 0x0006 => {},
                 0x0007 => {
+                    let latch = data;
                     data = self.ppu_data_buffer;
-                    self.ppu_data_buffer = self.ppu_read_internal(self.vram_addr);
+                    self.ppu_data_buffer = self.ppu_read(self.vram_addr, cart);
 
-                    // Paletas retornam imediatamente, buffer recebe o nametable abaixo
+                    // Paletas retornam imediatamente, buffer recebe o nametable abaixo.
+                    // Paleta só tem 6 bits, os 2 de cima vêm do latch
                     if self.vram_addr >= 0x3F00 {
-                        data = self.ppu_data_buffer;
-                        self.ppu_data_buffer = self.ppu_read_internal(self.vram_addr - 0x1000);
+                        data = (self.ppu_data_buffer & 0x3F) | (latch & 0xC0);
+                        self.ppu_data_buffer = self.ppu_read(self.vram_addr - 0x1000, cart);
+                        self.refresh_latch(data, 0x3F);
+                    } else {
+                        self.refresh_latch(data, 0xFF);
                     }
 
                     if (self.control & 0x04) != 0 {
@@ -176,7 +222,8 @@ impl Ppu {
         data
     }
 
-    pub fn cpu_write(&mut self, addr: u16, data: u8) {
+    pub fn cpu_write(&mut self, addr: u16, data: u8, cart: &mut dyn PpuBus) {
+        self.refresh_latch(data, 0xFF);
         match addr {
             0x0000 => {
                 let old_nmi = self.control & 0x80;
@@ -220,7 +267,7 @@ impl Ppu {
                 }
             },
             0x0007 => {
-                self.ppu_write_internal(self.vram_addr, data);
+                self.ppu_write(self.vram_addr, data, cart);
                 if (self.control & 0x04) != 0 {
                     self.vram_addr = self.vram_addr.wrapping_add(32);
                 } else {
@@ -231,7 +278,8 @@ impl Ppu {
         }
     }
 
-    fn mirror_nametable(&self, addr: u16) -> (usize, usize) {
+    // (nametable física, offset) de um endereço $2000-$2FFF
+    pub fn mirror_nametable(&self, addr: u16) -> (usize, usize) {
         let addr = addr & 0x0FFF;
         let table = (addr >> 10) as usize; // 0-3
         let offset = (addr & 0x03FF) as usize;
@@ -245,29 +293,12 @@ impl Ppu {
         (nt, offset)
     }
 
-    // Atualizar pattern tables a partir do cartridge (chamado pelo Nes)
-    pub fn update_chr_from_cartridge(&mut self, cartridge: &mut crate::cartridge::Cartridge) {
-        for addr in 0..0x2000u16 {
-            if let Some(byte) = cartridge.ppu_read(addr) {
-                let table = ((addr & 0x1000) >> 12) as usize;
-                let offset = (addr & 0x0FFF) as usize;
-                self.pattern_table[table][offset] = byte;
-            }
-        }
-    }
-
-    fn ppu_read_internal(&mut self, addr: u16) -> u8 {
+    fn ppu_read(&mut self, addr: u16, cart: &mut dyn PpuBus) -> u8 {
         let addr = addr & 0x3FFF;
 
         if addr <= 0x1FFF {
-            // Read CHR through cartridge mapper if available (supports bank switching)
-            if let Some(cart_ptr) = self.cart_ptr {
-                let cart = unsafe { &mut *cart_ptr };
-                if let Some(data) = cart.ppu_read(addr) {
-                    return data;
-                }
-            }
-            self.pattern_table[((addr & 0x1000) >> 12) as usize][(addr & 0x0FFF) as usize]
+            // CHR sempre via mapper (bank switching, latches do MMC2, CHR-RAM)
+            cart.chr_read(addr)
         } else if addr >= 0x2000 && addr <= 0x3EFF {
             let (nt, offset) = self.mirror_nametable(addr);
             self.nametable[nt][offset]
@@ -283,31 +314,12 @@ impl Ppu {
             0
         }
     }
-    
-    pub fn ppu_read(&mut self, addr: u16, read_only: bool, cartridge: Option<&mut crate::cartridge::Cartridge>) -> u8 {
-        let addr = addr & 0x3FFF;
-        
-        if addr >= 0x0000 && addr <= 0x1FFF {
-            if let Some(cart) = cartridge {
-                if let Some(cart_data) = cart.ppu_read(addr) {
-                    return cart_data;
-                }
-            }
-        }
-        
-        self.ppu_read_internal(addr)
-    }
 
-    fn ppu_write_internal(&mut self, addr: u16, data: u8) {
+    fn ppu_write(&mut self, addr: u16, data: u8, cart: &mut dyn PpuBus) {
         let addr = addr & 0x3FFF;
 
         if addr <= 0x1FFF {
-            // Escrever no cartridge CHR RAM se disponível
-            if let Some(cart) = self.cart_ptr {
-                unsafe { (*cart).ppu_write_chr(addr, data); }
-            }
-            // Também escrever no pattern_table local (fallback)
-            self.pattern_table[((addr & 0x1000) >> 12) as usize][(addr & 0x0FFF) as usize] = data;
+            cart.chr_write(addr, data);
         } else if addr >= 0x2000 && addr <= 0x3EFF {
             let (nt, offset) = self.mirror_nametable(addr);
             self.nametable[nt][offset] = data;
@@ -321,26 +333,12 @@ impl Ppu {
             self.palette_table[addr as usize] = data;
         }
     }
-    
-    pub fn ppu_write(&mut self, addr: u16, data: u8, cartridge: Option<&mut crate::cartridge::Cartridge>) {
-        let addr = addr & 0x3FFF;
-        
-        if addr >= 0x0000 && addr <= 0x1FFF {
-            if let Some(cart) = cartridge {
-                if cart.ppu_write(addr, data) {
-                    return;
-                }
-            }
-        }
-        
-        self.ppu_write_internal(addr, data);
-    }
 
-    pub fn clock(&mut self, cartridge: Option<&mut crate::cartridge::Cartridge>) {
-        self.cart_ptr = cartridge.map(|c| c as *mut _);
+    pub fn clock(&mut self, cart: &mut dyn PpuBus) {
         // Background rendering logic
         if self.scanline >= -1 && self.scanline < 240 {
-            if self.scanline == 0 && self.cycle == 0 && self.odd_frame && (self.mask & 0x18) != 0 {
+            if self.scanline == 0 && self.cycle == 0 && self.odd_frame && (self.mask & 0x18) != 0
+                && self.region.skips_odd_dot() {
                 self.cycle = 1;
             }
 
@@ -360,19 +358,19 @@ impl Ppu {
                 match (self.cycle - 1) % 8 {
                     0 => {
                         self.load_background_shifters();
-                        self.bg_next_tile_id = self.ppu_read_internal(0x2000 | (self.vram_addr & 0x0FFF));
+                        self.bg_next_tile_id = self.ppu_read(0x2000 | (self.vram_addr & 0x0FFF), cart);
                     },
                     2 => {
-                        self.bg_next_tile_attr = self.ppu_read_internal(0x23C0 | (self.vram_addr & 0x0C00) | ((self.vram_addr >> 4) & 0x38) | ((self.vram_addr >> 2) & 0x07));
+                        self.bg_next_tile_attr = self.ppu_read(0x23C0 | (self.vram_addr & 0x0C00) | ((self.vram_addr >> 4) & 0x38) | ((self.vram_addr >> 2) & 0x07), cart);
                         if (self.vram_addr & 0x0040) != 0 { self.bg_next_tile_attr >>= 4; }
                         if (self.vram_addr & 0x0002) != 0 { self.bg_next_tile_attr >>= 2; }
                         self.bg_next_tile_attr &= 0x03;
                     },
                     4 => {
-                        self.bg_next_tile_lsb = self.ppu_read_internal(((self.control as u16 & 0x10) << 8) + (self.bg_next_tile_id as u16 * 16) + ((self.vram_addr >> 12) & 0x07));
+                        self.bg_next_tile_lsb = self.ppu_read(((self.control as u16 & 0x10) << 8) + (self.bg_next_tile_id as u16 * 16) + ((self.vram_addr >> 12) & 0x07), cart);
                     },
                     6 => {
-                        self.bg_next_tile_msb = self.ppu_read_internal(((self.control as u16 & 0x10) << 8) + (self.bg_next_tile_id as u16 * 16) + ((self.vram_addr >> 12) & 0x07) + 8);
+                        self.bg_next_tile_msb = self.ppu_read(((self.control as u16 & 0x10) << 8) + (self.bg_next_tile_id as u16 * 16) + ((self.vram_addr >> 12) & 0x07) + 8, cart);
                     },
                     7 => {
                         self.increment_scroll_x();
@@ -395,7 +393,7 @@ impl Ppu {
             }
 
             if self.cycle == 338 || self.cycle == 340 {
-                self.bg_next_tile_id = self.ppu_read_internal(0x2000 | (self.vram_addr & 0x0FFF));
+                self.bg_next_tile_id = self.ppu_read(0x2000 | (self.vram_addr & 0x0FFF), cart);
             }
 
             if self.scanline == -1 && self.cycle >= 280 && self.cycle < 305 {
@@ -475,8 +473,8 @@ impl Ppu {
                     }
                     
                     sprite_pattern_addr_hi = sprite_pattern_addr_lo + 8;
-                    sprite_pattern_bits_lo = self.ppu_read_internal(sprite_pattern_addr_lo);
-                    sprite_pattern_bits_hi = self.ppu_read_internal(sprite_pattern_addr_hi);
+                    sprite_pattern_bits_lo = self.ppu_read(sprite_pattern_addr_lo, cart);
+                    sprite_pattern_bits_hi = self.ppu_read(sprite_pattern_addr_hi, cart);
                     
                     if (self.sprites_scanline[i].attribute & 0x40) != 0 {
                         fn flip_byte(b: u8) -> u8 {
@@ -497,8 +495,9 @@ impl Ppu {
             }
         }
 
-        if self.scanline >= 241 && self.scanline < 261 {
-            if self.scanline == 241 && self.cycle == 1 {
+        let vblank = self.region.vblank_line();
+        if self.scanline >= vblank && self.scanline < self.region.scanlines() - 1 {
+            if self.scanline == vblank && self.cycle == 1 {
                 self.status |= 0x80;
                 if (self.control & 0x80) != 0 {
                     self.nmi = true;
@@ -609,15 +608,15 @@ impl Ppu {
             }
         }
 
-        // cart_ptr permanece setado - limpo no próximo clock cycle pelo nes.rs
         self.cycle += 1;
         if self.cycle >= 341 {
             self.cycle = 0;
             self.scanline += 1;
-            if self.scanline >= 261 {
+            if self.scanline >= self.region.scanlines() - 1 {
                 self.scanline = -1;
                 self.frame_complete = true;
                 self.odd_frame = !self.odd_frame;
+                self.frame_count = self.frame_count.wrapping_add(1);
             }
         }
     }
@@ -727,6 +726,98 @@ impl Ppu {
         nes_palette[color_index as usize]
     }
 
+    // Tudo menos a tela (screen é recalculada no próximo frame)
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.bytes(&self.nametable[0]);
+        w.bytes(&self.nametable[1]);
+        w.bytes(&self.palette_table);
+        w.u8(self.status);
+        w.u8(self.mask);
+        w.u8(self.control);
+        w.u8(self.address_latch);
+        w.u8(self.ppu_data_buffer);
+        w.u16(self.vram_addr);
+        w.u16(self.tram_addr);
+        w.u8(self.fine_x);
+        w.u8(self.bg_next_tile_id);
+        w.u8(self.bg_next_tile_attr);
+        w.u8(self.bg_next_tile_lsb);
+        w.u8(self.bg_next_tile_msb);
+        w.u16(self.bg_shifter_pattern_lo);
+        w.u16(self.bg_shifter_pattern_hi);
+        w.u16(self.bg_shifter_attr_lo);
+        w.u16(self.bg_shifter_attr_hi);
+        w.bytes(&self.oam);
+        w.u8(self.oam_addr);
+        for s in &self.sprites_scanline {
+            w.bytes(&[s.y, s.id, s.attribute, s.x]);
+        }
+        w.u8(self.sprite_count as u8);
+        w.bytes(&self.sprite_shifter_pattern_lo);
+        w.bytes(&self.sprite_shifter_pattern_hi);
+        w.bool(self.sprite_zero_hit_possible);
+        w.bool(self.sprite_zero_being_rendered);
+        w.i16(self.scanline);
+        w.i16(self.cycle);
+        w.bool(self.frame_complete);
+        w.bool(self.nmi);
+        w.bool(self.scanline_trigger);
+        w.bool(self.odd_frame);
+        w.u32(self.frame_count);
+        w.u8(self.io_latch);
+        for t in &self.io_refresh {
+            w.u32(*t);
+        }
+        w.u8(self.mirror_mode);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> StateResult<()> {
+        r.bytes(&mut self.nametable[0])?;
+        r.bytes(&mut self.nametable[1])?;
+        r.bytes(&mut self.palette_table)?;
+        self.status = r.u8()?;
+        self.mask = r.u8()?;
+        self.control = r.u8()?;
+        self.address_latch = r.u8()?;
+        self.ppu_data_buffer = r.u8()?;
+        self.vram_addr = r.u16()?;
+        self.tram_addr = r.u16()?;
+        self.fine_x = r.u8()?;
+        self.bg_next_tile_id = r.u8()?;
+        self.bg_next_tile_attr = r.u8()?;
+        self.bg_next_tile_lsb = r.u8()?;
+        self.bg_next_tile_msb = r.u8()?;
+        self.bg_shifter_pattern_lo = r.u16()?;
+        self.bg_shifter_pattern_hi = r.u16()?;
+        self.bg_shifter_attr_lo = r.u16()?;
+        self.bg_shifter_attr_hi = r.u16()?;
+        r.bytes(&mut self.oam)?;
+        self.oam_addr = r.u8()?;
+        for i in 0..8 {
+            let mut e = [0u8; 4];
+            r.bytes(&mut e)?;
+            self.sprites_scanline[i] = ObjectAttributeEntry { y: e[0], id: e[1], attribute: e[2], x: e[3] };
+        }
+        self.sprite_count = (r.u8()? as usize).min(8);
+        r.bytes(&mut self.sprite_shifter_pattern_lo)?;
+        r.bytes(&mut self.sprite_shifter_pattern_hi)?;
+        self.sprite_zero_hit_possible = r.bool()?;
+        self.sprite_zero_being_rendered = r.bool()?;
+        self.scanline = r.i16()?;
+        self.cycle = r.i16()?;
+        self.frame_complete = r.bool()?;
+        self.nmi = r.bool()?;
+        self.scanline_trigger = r.bool()?;
+        self.odd_frame = r.bool()?;
+        self.frame_count = r.u32()?;
+        self.io_latch = r.u8()?;
+        for t in self.io_refresh.iter_mut() {
+            *t = r.u32()?;
+        }
+        self.mirror_mode = r.u8()?;
+        Ok(())
+    }
+
     pub fn get_nmi(&mut self) -> bool {
         let temp = self.nmi;
         self.nmi = false;
@@ -0,0 +1,119 @@
+// Região/timing do console: NTSC (2C02), PAL (2C07) e Dendy (clone UA6538)
+//
+//            CPU Hz    PPU:CPU  linhas  vblank  fps
+//   NTSC   1789773     3:1      262     241     60.0988
+//   PAL    1662607     3.2:1    312     241     50.0070
+//   Dendy  1773448     3:1      312     291     50.0070
+//
+// Dendy usa as tabelas de APU do NTSC, só o clock muda.
+
+use crate::cartridge::{Cartridge, Timing};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region {
+    Ntsc,
+    Pal,
+    Dendy,
+}
+
+impl Region {
+    pub fn cpu_clock_hz(self) -> f64 {
+        match self {
+            Region::Ntsc => 1_789_773.0,
+            Region::Pal => 1_662_607.0,
+            Region::Dendy => 1_773_448.0,
+        }
+    }
+
+    // PPU dots por CPU cycle como fração (num/den): 3/1 ou 16/5
+    pub fn ppu_cpu_ratio(self) -> (u32, u32) {
+        match self {
+            Region::Pal => (16, 5),
+            _ => (3, 1),
+        }
+    }
+
+    // Total de scanlines por frame (contando a pre-render)
+    pub fn scanlines(self) -> i16 {
+        match self {
+            Region::Ntsc => 262,
+            _ => 312,
+        }
+    }
+
+    // Linha em que o vblank começa
+    pub fn vblank_line(self) -> i16 {
+        match self {
+            Region::Dendy => 291,
+            _ => 241,
+        }
+    }
+
+    // Só o 2C02 pula um dot nos frames ímpares
+    pub fn skips_odd_dot(self) -> bool {
+        self == Region::Ntsc
+    }
+
+    pub fn frame_rate(self) -> f64 {
+        match self {
+            Region::Ntsc => 60.0988,
+            _ => 50.0070,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Region::Ntsc => 0,
+            Region::Pal => 1,
+            Region::Dendy => 2,
+        }
+    }
+
+    pub fn from_u8(v: u8) -> Region {
+        match v {
+            1 => Region::Pal,
+            2 => Region::Dendy,
+            _ => Region::Ntsc,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Region> {
+        match s.to_ascii_lowercase().as_str() {
+            "ntsc" => Some(Region::Ntsc),
+            "pal" => Some(Region::Pal),
+            "dendy" => Some(Region::Dendy),
+            _ => None,
+        }
+    }
+
+    // Header NES 2.0 primeiro, depois tag no nome do arquivo, senão NTSC
+    pub fn detect(cart: &Cartridge) -> Region {
+        match cart.header.timing {
+            Some(Timing::Pal) => return Region::Pal,
+            Some(Timing::Dendy) => return Region::Dendy,
+            Some(Timing::Ntsc) | Some(Timing::Multi) => return Region::Ntsc,
+            None => {}
+        }
+        cart.rom_path.as_deref()
+            .and_then(Region::from_filename)
+            .unwrap_or(Region::Ntsc)
+    }
+
+    // Tags GoodNES/No-Intro: "(E)", "(Europe)", "(PAL)", ...
+    pub fn from_filename(path: &str) -> Option<Region> {
+        let name = std::path::Path::new(path).file_name()?.to_string_lossy().to_ascii_lowercase();
+        const PAL_TAGS: [&str; 12] = [
+            "(e)", "(europe)", "(pal)", "(a)", "(australia)", "(g)", "(germany)",
+            "(f)", "(france)", "(i)", "(italy)", "(spain)",
+        ];
+        if name.contains("(dendy)") {
+            Some(Region::Dendy)
+        } else if PAL_TAGS.iter().any(|t| name.contains(t)) {
+            Some(Region::Pal)
+        } else if name.contains("(u)") || name.contains("(usa)") || name.contains("(j)") || name.contains("(japan)") {
+            Some(Region::Ntsc)
+        } else {
+            None
+        }
+    }
+}
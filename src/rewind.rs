@@ -0,0 +1,200 @@
+// Rewind - ring buffer de save states
+//
+// Guarda só o snapshot mais recente inteiro (head). Cada snapshot anterior
+// vira um delta: XOR com o seguinte, comprimido com RLE de zeros (quase tudo
+// é zero entre dois frames próximos). Voltar um passo = head ^= delta.
+// Os mais antigos saem pela frente do VecDeque sem precisar de nada.
+
+use std::collections::VecDeque;
+use crate::nes::Nes;
+use crate::region::Region;
+
+pub struct Rewind {
+    pub interval: u32,   // frames entre snapshots
+    pub seconds: u32,
+    pub max_steps: usize,
+    fps: f64,           // da região do jogo rodando; PAL/Dendy têm menos frames por segundo
+    pub max_bytes: usize,
+    head: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    bytes: usize,
+    frame: u32,
+}
+
+impl Rewind {
+    // seconds de histórico, um snapshot a cada `interval` frames. Começa
+    // contando NTSC, on_frame acerta pela região do jogo
+    pub fn new(seconds: u32, interval: u32) -> Self {
+        let interval = interval.max(1);
+        let fps = Region::Ntsc.frame_rate();
+        Rewind {
+            interval,
+            seconds,
+            max_steps: steps(seconds, fps, interval),
+            fps,
+            max_bytes: 32 * 1024 * 1024,
+            head: None,
+            deltas: VecDeque::new(),
+            bytes: 0,
+            frame: 0,
+        }
+    }
+
+    // Chamar uma vez por frame emulado
+    pub fn on_frame(&mut self, nes: &Nes) {
+        let fps = nes.region.frame_rate();
+        if fps != self.fps {
+            self.fps = fps;
+            self.max_steps = steps(self.seconds, fps, self.interval);
+        }
+        self.frame += 1;
+        if self.frame < self.interval {
+            return;
+        }
+        self.frame = 0;
+        self.push(nes.save_state());
+    }
+
+    fn push(&mut self, state: Vec<u8>) {
+        if let Some(prev) = self.head.take() {
+            if prev.len() == state.len() {
+                let delta = xor_rle(&prev, &state);
+                self.bytes += delta.len();
+                self.deltas.push_back(delta);
+            } else {
+                // Trocou de ROM/mapper no meio, histórico não serve mais
+                self.deltas.clear();
+                self.bytes = 0;
+            }
+        }
+        self.head = Some(state);
+
+        while self.deltas.len() > self.max_steps || self.bytes > self.max_bytes {
+            match self.deltas.pop_front() {
+                Some(d) => self.bytes -= d.len(),
+                None => break,
+            }
+        }
+    }
+
+    // Volta um snapshot. Retorna false quando acabou o histórico.
+    pub fn step_back(&mut self, nes: &mut Nes) -> bool {
+        let (Some(head), Some(delta)) = (self.head.as_mut(), self.deltas.pop_back()) else {
+            return false;
+        };
+        self.bytes -= delta.len();
+        unrle_xor(&delta, head);
+        self.frame = 0;
+        match nes.load_state(head) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Rewind: {}", e);
+                self.clear();
+                false
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.head = None;
+        self.deltas.clear();
+        self.bytes = 0;
+        self.frame = 0;
+    }
+
+    // Segundos de histórico disponíveis (pra mostrar no overlay)
+    pub fn seconds(&self) -> f32 {
+        ((self.deltas.len() as u32 * self.interval) as f64 / self.fps) as f32
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.bytes + self.head.as_ref().map_or(0, |h| h.len())
+    }
+}
+
+fn steps(seconds: u32, fps: f64, interval: u32) -> usize {
+    (seconds as f64 * fps / interval as f64) as usize
+}
+
+// Formato: [zeros (varint)] [n literais (varint)] [literais...] repetido
+fn xor_rle(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < a.len() {
+        let start = i;
+        while i < a.len() && a[i] == b[i] {
+            i += 1;
+        }
+        write_varint(&mut out, i - start);
+        let lit = i;
+        while i < a.len() && a[i] != b[i] {
+            i += 1;
+        }
+        write_varint(&mut out, i - lit);
+        for j in lit..i {
+            out.push(a[j] ^ b[j]);
+        }
+    }
+    out
+}
+
+fn unrle_xor(delta: &[u8], target: &mut [u8]) {
+    let mut pos = 0;
+    let mut i = 0;
+    while i < delta.len() {
+        pos += read_varint(delta, &mut i);
+        let n = read_varint(delta, &mut i);
+        for _ in 0..n {
+            target[pos] ^= delta[i];
+            pos += 1;
+            i += 1;
+        }
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: usize) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(data: &[u8], i: &mut usize) -> usize {
+    let mut v = 0usize;
+    let mut shift = 0;
+    loop {
+        let b = data[*i];
+        *i += 1;
+        v |= ((b & 0x7F) as usize) << shift;
+        if b & 0x80 == 0 {
+            return v;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unrle_xor, xor_rle};
+
+    // Diferença no começo, no fim e um trecho igual de mais de 127 bytes (varint de 2 bytes)
+    #[test]
+    fn xor_rle_roundtrip() {
+        let old: Vec<u8> = (0..2000).map(|i| (i * 7) as u8).collect();
+        let mut new = old.clone();
+        new[0] ^= 0xFF;
+        new[1] = 0;
+        new[600..900].fill(0x55);
+        new[1999] = 0xAA;
+
+        for (a, b) in [(&new, &old), (&old, &new), (&old, &old)] {
+            let delta = xor_rle(a, b);
+            let mut out = b.clone();
+            unrle_xor(&delta, &mut out);
+            assert_eq!(&out, a);
+        }
+        // Tudo igual: só "2000 iguais, 0 diferentes"
+        assert_eq!(xor_rle(&old, &old), [0xD0, 0x0F, 0x00]);
+    }
+}
@@ -0,0 +1,104 @@
+// Save states - formato binário versionado
+//
+// Layout: "RNSS" | versão (u16) | mapper (u16) | CRC32 da PRG (u32) | seções
+// Cada componente (Cpu, Ppu, Apu, Bus, Cartridge/Mapper) escreve os seus
+// próprios campos na ordem fixa, tudo little-endian.
+
+pub const MAGIC: &[u8; 4] = b"RNSS";
+pub const VERSION: u16 = 8;
+
+pub type StateResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+pub struct StateWriter {
+    pub buf: Vec<u8>,
+}
+
+impl Default for StateWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        StateWriter { buf: Vec::with_capacity(32 * 1024) }
+    }
+
+    pub fn u8(&mut self, v: u8) { self.buf.push(v); }
+    pub fn bool(&mut self, v: bool) { self.buf.push(v as u8); }
+    pub fn u16(&mut self, v: u16) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn i16(&mut self, v: i16) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn u32(&mut self, v: u32) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn u64(&mut self, v: u64) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn f32(&mut self, v: f32) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn f64(&mut self, v: f64) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+
+    // Tamanho fixo, conhecido dos dois lados
+    pub fn bytes(&mut self, v: &[u8]) { self.buf.extend_from_slice(v); }
+
+    // Tamanho variável (PRG-RAM, CHR-RAM), com prefixo u32
+    pub fn vec(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.buf.extend_from_slice(v);
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        StateReader { data, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> StateResult<&'a [u8]> {
+        if self.pos + n > self.data.len() {
+            return Err("Save state truncado".into());
+        }
+        let s = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    pub fn u8(&mut self) -> StateResult<u8> { Ok(self.take(1)?[0]) }
+    pub fn bool(&mut self) -> StateResult<bool> { Ok(self.take(1)?[0] != 0) }
+    pub fn u16(&mut self) -> StateResult<u16> { Ok(u16::from_le_bytes(self.take(2)?.try_into()?)) }
+    pub fn i16(&mut self) -> StateResult<i16> { Ok(i16::from_le_bytes(self.take(2)?.try_into()?)) }
+    pub fn u32(&mut self) -> StateResult<u32> { Ok(u32::from_le_bytes(self.take(4)?.try_into()?)) }
+    pub fn u64(&mut self) -> StateResult<u64> { Ok(u64::from_le_bytes(self.take(8)?.try_into()?)) }
+    pub fn f32(&mut self) -> StateResult<f32> { Ok(f32::from_le_bytes(self.take(4)?.try_into()?)) }
+    pub fn f64(&mut self) -> StateResult<f64> { Ok(f64::from_le_bytes(self.take(8)?.try_into()?)) }
+
+    pub fn bytes(&mut self, out: &mut [u8]) -> StateResult<()> {
+        out.copy_from_slice(self.take(out.len())?);
+        Ok(())
+    }
+
+    // Lê um bloco com prefixo e exige o mesmo tamanho do destino
+    pub fn vec_into(&mut self, out: &mut [u8]) -> StateResult<()> {
+        let len = self.u32()? as usize;
+        if len != out.len() {
+            return Err(format!("Save state: bloco de {} bytes, esperado {}", len, out.len()).into());
+        }
+        self.bytes(out)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+// CRC32 (IEEE) - identifica a ROM no header do save state
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,181 @@
+// Código fonte pro debugger: tabelas file/line/span do .dbg do ld65
+//
+// Cada "line" aponta pra um ou mais "span" (pedaço de um segmento), e o
+// segmento diz onde ele foi parar no .nes (ooffs). Igual aos labels (ver
+// symbols.rs), guarda pela posição na PRG, então o banco mapeado decide
+// qual linha aparece. Só entra o que está na PRG ROM.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::bus::Bus;
+use crate::symbols::{dbg_segments, parse_dbg_record, parse_num};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceLoc {
+    pub file: usize,    // índice em SourceMap::files
+    pub line: u32,      // começa em 1
+}
+
+pub struct SourceFile {
+    pub name: String,
+    pub lines: Option<Vec<String>>, // None = não achou o arquivo
+}
+
+#[derive(Default)]
+pub struct SourceMap {
+    pub files: Vec<SourceFile>,
+    prg: HashMap<usize, (SourceLoc, u8)>, // posição na PRG -> (linha, prioridade)
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap { files: Vec::new(), prg: HashMap::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prg.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+        self.prg.clear();
+    }
+
+    // dir = pasta do .dbg, os nomes de arquivo costumam ser relativos a ela.
+    // Devolve quantas linhas de fonte entraram
+    pub fn parse_ca65(&mut self, text: &str, dir: &Path, prg_start: usize) -> usize {
+        let segs = dbg_segments(text);
+        let mut files = HashMap::new();                   // id do .dbg -> índice em self.files
+        let mut spans: HashMap<&str, (usize, u32)> = HashMap::new(); // id -> (posição na PRG, tamanho)
+        let mut lines = Vec::new();
+        for line in text.lines() {
+            let Some((kind, fields)) = parse_dbg_record(line) else { continue };
+            match kind {
+                "file" => {
+                    let (Some(id), Some(name)) = (fields.get("id"), fields.get("name")) else { continue };
+                    files.insert(id.to_string(), self.files.len());
+                    self.files.push(SourceFile { name: name.to_string(), lines: read_source(dir, name) });
+                },
+                "span" => {
+                    let (Some(id), Some(seg), Some(start)) = (fields.get("id"), fields.get("seg"), fields.get("start").and_then(|v| parse_num(v))) else { continue };
+                    let size = fields.get("size").and_then(|v| parse_num(v)).unwrap_or(1);
+                    let Some(off) = segs.get(*seg)
+                        .and_then(|&(_, ooffs)| ooffs?.checked_add(start as usize)?.checked_sub(prg_start)) else { continue };
+                    spans.insert(id, (off, size));
+                },
+                "line" => lines.push(fields),
+                _ => {},
+            }
+        }
+
+        let before = self.prg.len();
+        for fields in lines {
+            let (Some(file), Some(num), Some(span)) = (fields.get("file").and_then(|id| files.get(*id)), fields.get("line").and_then(|v| parse_num(v)), fields.get("span")) else { continue };
+            // type=1 é linha de C, 2 é dentro de macro. C ganha do asm gerado,
+            // e a linha que chamou a macro ganha da definição dela
+            let rank = match fields.get("type").copied() {
+                Some("1") => 2,
+                Some("2") => 0,
+                _ => 1,
+            };
+            let loc = SourceLoc { file: *file, line: num };
+            for &(off, size) in span.split('+').filter_map(|id| spans.get(id)) {
+                for o in off..off + size as usize {
+                    let entry = self.prg.entry(o).or_insert((loc, rank));
+                    if rank > entry.1 {
+                        *entry = (loc, rank);
+                    }
+                }
+            }
+        }
+        self.prg.len() - before
+    }
+
+    // Linha do que está em addr agora, olhando o banco mapeado
+    pub fn lookup(&self, bus: &Bus, addr: u16) -> Option<SourceLoc> {
+        if self.is_empty() {
+            return None;
+        }
+        self.prg.get(&bus.prg_offset(addr)?).map(|&(loc, _)| loc)
+    }
+
+    // "main.s:12"
+    pub fn describe(&self, loc: SourceLoc) -> String {
+        let name = &self.files[loc.file].name;
+        let short = Path::new(name).file_name().and_then(|n| n.to_str()).unwrap_or(name);
+        format!("{}:{}", short, loc.line)
+    }
+
+    pub fn line_text(&self, file: usize, line: u32) -> Option<&str> {
+        let lines = self.files.get(file)?.lines.as_ref()?;
+        lines.get(line.checked_sub(1)? as usize).map(|s| s.as_str())
+    }
+
+    // Linhas em volta de loc: (número, texto). Vazio se o arquivo não foi achado
+    pub fn context(&self, loc: SourceLoc, before: u32, after: u32) -> Vec<(u32, &str)> {
+        (loc.line.saturating_sub(before).max(1)..=loc.line + after)
+            .filter_map(|n| Some((n, self.line_text(loc.file, n)?)))
+            .collect()
+    }
+}
+
+// Tenta o caminho como está, relativo ao .dbg e só o nome do arquivo do lado do .dbg
+fn read_source(dir: &Path, name: &str) -> Option<Vec<String>> {
+    let path = Path::new(name);
+    let candidates = [path.to_path_buf(), dir.join(path), dir.join(path.file_name()?)];
+    let text = candidates.iter().find_map(|p| fs::read_to_string(p).ok())?;
+    Some(text.lines().map(|l| l.replace('\t', "    ")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::{test_rom, Cartridge};
+
+    // UxROM de 4 bancos; os arquivos não existem, só as tabelas importam
+    const DBG: &str = "version\tmajor=2,minor=0
+file\tid=0,name=\"main.c\",size=100,mtime=0x0,mod=0
+file\tid=1,name=\"src/main.s\",size=100,mtime=0x0,mod=0
+file\tid=2,name=\"macros.inc\",size=100,mtime=0x0,mod=0
+seg\tid=0,name=\"BANK0\",start=0x008000,size=0x4000,addrsize=absolute,type=ro,oname=\"t.nes\",ooffs=16
+seg\tid=1,name=\"BANK1\",start=0x008000,size=0x4000,addrsize=absolute,type=ro,oname=\"t.nes\",ooffs=16400
+seg\tid=2,name=\"FIXED\",start=0x00C000,size=0x4000,addrsize=absolute,type=ro,oname=\"t.nes\",ooffs=49168
+span\tid=0,seg=2,start=0,size=3
+span\tid=1,seg=2,start=3,size=2
+span\tid=2,seg=2,start=5,size=1
+span\tid=3,seg=0,start=16,size=1
+span\tid=4,seg=1,start=16,size=1
+line\tid=0,file=1,line=10,span=0+1
+line\tid=1,file=0,line=5,type=1,span=1
+line\tid=2,file=2,line=3,type=2,span=2
+line\tid=3,file=1,line=12,span=2
+line\tid=4,file=2,line=7,type=2,span=0
+line\tid=5,file=1,line=20,span=3
+line\tid=6,file=1,line=30,span=4
+";
+
+    #[test]
+    fn ca65_lines() {
+        let mut map = SourceMap::new();
+        assert_eq!(map.parse_ca65(DBG, Path::new("/nonexistent"), 16), 8);
+        assert!(map.files[1].lines.is_none());
+
+        let mut bus = Bus::new();
+        bus.cartridge = Some(Cartridge::from_bytes(&test_rom(4, [0x20, 0, 0], &[])).unwrap());
+        let at = |bus: &Bus, addr| map.lookup(bus, addr).map(|loc| map.describe(loc));
+
+        // span=0+1 cobre C000-C004, mas C ganha em C003-C004; macro perde pra quem chamou
+        assert_eq!(at(&bus, 0xC000).as_deref(), Some("main.s:10"));
+        assert_eq!(at(&bus, 0xC002).as_deref(), Some("main.s:10"));
+        assert_eq!(at(&bus, 0xC003).as_deref(), Some("main.c:5"));
+        assert_eq!(at(&bus, 0xC004).as_deref(), Some("main.c:5"));
+        assert_eq!(at(&bus, 0xC005).as_deref(), Some("main.s:12"));
+        assert_eq!(at(&bus, 0xC006), None);
+
+        // Mesmo endereço, linha de acordo com o banco mapeado
+        assert_eq!(at(&bus, 0x8010).as_deref(), Some("main.s:20"));
+        bus.cpu_write(0x8000, 1);
+        assert_eq!(at(&bus, 0x8010).as_deref(), Some("main.s:30"));
+    }
+}
@@ -0,0 +1,401 @@
+// Símbolos/labels pro debugger: ca65 (.dbg), FCEUX (.nl) e Mesen (.mlb)
+//
+// Label de ROM é guardado pela posição na PRG (não pelo endereço da CPU),
+// então com mapper trocando banco o nome certo aparece: o lookup pergunta
+// ao mapper o que está em addr agora (Bus::prg_offset). RAM, registradores
+// e PRG RAM não têm banco e ficam pelo endereço da CPU mesmo.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::bus::Bus;
+use crate::source::SourceMap;
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub addr: u16,              // endereço da CPU (no banco em que foi montado)
+    pub prg: Option<usize>,     // posição na PRG ROM; None = RAM/registrador
+}
+
+pub struct SymbolTable {
+    rom: HashMap<usize, String>,
+    cpu: HashMap<u16, String>,
+    names: HashMap<String, Symbol>,
+    pub files: Vec<String>,
+    pub source: SourceMap,      // linhas de fonte do .dbg (ver source.rs)
+    prg_start: usize,           // onde a PRG começa no .nes (header + trainer), pros ooffs do ca65
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable {
+            rom: HashMap::new(),
+            cpu: HashMap::new(),
+            names: HashMap::new(),
+            files: Vec::new(),
+            source: SourceMap::new(),
+            prg_start: 16,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.rom.clear();
+        self.cpu.clear();
+        self.names.clear();
+        self.files.clear();
+        self.source.clear();
+    }
+
+    pub fn set_prg_start(&mut self, prg_start: usize) {
+        self.prg_start = prg_start;
+    }
+
+    // O primeiro nome de cada endereço fica (no ca65 é o que foi definido primeiro)
+    pub fn add(&mut self, sym: Symbol) {
+        match sym.prg {
+            Some(off) => { self.rom.entry(off).or_insert_with(|| sym.name.clone()); },
+            None => { self.cpu.entry(sym.addr).or_insert_with(|| sym.name.clone()); },
+        }
+        self.names.entry(sym.name.clone()).or_insert(sym);
+    }
+
+    // Carrega pela extensão. Devolve quantos símbolos entraram
+    pub fn load(&mut self, path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let text = fs::read_to_string(path)?;
+        let before = self.len();
+        let lower = path.to_lowercase();
+        if lower.ends_with(".dbg") {
+            self.parse_ca65(&text);
+            let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+            self.source.parse_ca65(&text, dir, self.prg_start);
+        } else if lower.ends_with(".nl") {
+            self.parse_fceux(&text, nl_bank(path));
+        } else if lower.ends_with(".mlb") {
+            self.parse_mesen(&text);
+        } else {
+            return Err(format!("formato de símbolos desconhecido: {} (use .dbg, .nl ou .mlb)", path).into());
+        }
+        self.files.push(path.to_string());
+        Ok(self.len() - before)
+    }
+
+    // Procura os arquivos do lado da ROM: jogo.dbg, jogo.mlb, jogo.nes.ram.nl, jogo.nes.N.nl
+    pub fn load_for_rom(&mut self, rom_path: &str) -> usize {
+        let rom = Path::new(rom_path);
+        let stem = rom.with_extension("");
+        let mut candidates = vec![
+            format!("{}.dbg", stem.display()),
+            format!("{}.mlb", stem.display()),
+            format!("{}.ram.nl", rom_path),
+        ];
+        for bank in 0..256 {
+            let nl = format!("{}.{}.nl", rom_path, bank);
+            if Path::new(&nl).exists() {
+                candidates.push(nl);
+            }
+        }
+
+        let mut total = 0;
+        for path in candidates.iter().filter(|p| Path::new(p).exists()) {
+            match self.load(path) {
+                Ok(n) => total += n,
+                Err(e) => eprintln!("[SYM] erro em {}: {}", path, e),
+            }
+        }
+        if total > 0 || !self.source.is_empty() {
+            eprintln!("[SYM] {} símbolos{} de {}", total,
+                if self.source.is_empty() { "" } else { " + linhas de fonte" }, self.files.join(", "));
+        }
+        total
+    }
+
+    // Nome do que está em addr agora, olhando o banco mapeado
+    pub fn lookup(&self, bus: &Bus, addr: u16) -> Option<&str> {
+        if self.is_empty() {
+            return None;
+        }
+        match bus.prg_offset(addr) {
+            Some(off) => self.rom.get(&off).or_else(|| self.cpu.get(&addr)),
+            None => self.cpu.get(&addr),
+        }
+        .map(|s| s.as_str())
+    }
+
+    // "$C003 (reset) main.s:12" ou só "$C003"
+    pub fn describe(&self, bus: &Bus, addr: u16) -> String {
+        let mut s = match self.lookup(bus, addr) {
+            Some(name) => format!("${:04X} ({})", addr, name),
+            None => format!("${:04X}", addr),
+        };
+        if let Some(loc) = self.source.lookup(bus, addr) {
+            s.push(' ');
+            s.push_str(&self.source.describe(loc));
+        }
+        s
+    }
+
+    // Label de uma posição da PRG, mapeada ou não
+    pub fn prg_label(&self, off: usize) -> Option<&str> {
+        self.rom.get(&off).map(|s| s.as_str())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.names.get(name)
+    }
+
+    // Endereço da CPU de um label. Label de ROM: se o banco está mapeado em
+    // alguma janela de 8K, usa essa; senão o endereço em que foi montado
+    pub fn resolve(&self, bus: &Bus, name: &str) -> Option<(u16, Option<usize>)> {
+        let sym = self.names.get(name)?;
+        let Some(off) = sym.prg else { return Some((sym.addr, None)) };
+        let mapped = (0x6000..=0xE000u32).step_by(0x2000)
+            .map(|base| (base as u16) | (off & 0x1FFF) as u16)
+            .find(|&a| bus.prg_offset(a) == Some(off));
+        Some((mapped.unwrap_or(sym.addr), Some(off)))
+    }
+
+    // ca65: "seg id=1,...,start=0x00C000,...,ooffs=16" e "sym id=5,name=\"main\",...,val=0xC003,seg=1,type=lab"
+    fn parse_ca65(&mut self, text: &str) {
+        let segs = dbg_segments(text);
+        let syms = text.lines()
+            .filter_map(parse_dbg_record)
+            .filter(|(kind, _)| *kind == "sym")
+            .map(|(_, fields)| fields);
+
+        for fields in syms {
+            // equ são constantes (tamanhos, bits...), não endereço
+            if fields.get("type").is_some_and(|t| *t != "lab") {
+                continue;
+            }
+            let (Some(name), Some(val)) = (fields.get("name"), fields.get("val").and_then(|v| parse_num(v))) else { continue };
+            if val > 0xFFFF {
+                continue;
+            }
+            let prg = fields.get("seg")
+                .and_then(|id| segs.get(*id))
+                .and_then(|&(start, ooffs)| ooffs?.checked_add(val.checked_sub(start)? as usize)?.checked_sub(self.prg_start));
+            self.add(Symbol { name: name.to_string(), addr: val as u16, prg });
+        }
+    }
+
+    // FCEUX: "$C003#reset#comentário". bank = Some(n) pros jogo.nes.N.nl (bancos de 16K)
+    fn parse_fceux(&mut self, text: &str, bank: Option<usize>) {
+        for line in text.lines() {
+            let mut parts = line.trim().splitn(3, '#');
+            let (Some(addr), Some(name)) = (parts.next(), parts.next()) else { continue };
+            let Some(addr) = addr.strip_prefix('$').and_then(|a| u16::from_str_radix(a, 16).ok()) else { continue };
+            if name.is_empty() {
+                continue;
+            }
+            let prg = bank.filter(|_| addr >= 0x8000).map(|b| b * 0x4000 + (addr as usize & 0x3FFF));
+            self.add(Symbol { name: name.to_string(), addr, prg });
+        }
+    }
+
+    // Mesen: "P:1A2B:label:comentário" (P = PRG ROM, R = RAM, W/S = PRG RAM, G = registrador)
+    // O Mesen 2 usa os nomes compridos (NesPrgRom, NesInternalRam...)
+    fn parse_mesen(&mut self, text: &str) {
+        for line in text.lines() {
+            let mut parts = line.trim().splitn(4, ':');
+            let (Some(kind), Some(addr), Some(name)) = (parts.next(), parts.next(), parts.next()) else { continue };
+            // Faixa "0300-0301": o label vale pro primeiro
+            let addr = addr.split('-').next().unwrap_or(addr);
+            let (Ok(addr), false) = (usize::from_str_radix(addr, 16), name.is_empty()) else { continue };
+            let (cpu, prg) = match kind {
+                "P" | "NesPrgRom" => (0x8000 | (addr & 0x7FFF) as u16, Some(addr)),
+                "R" | "NesInternalRam" => (addr as u16 & 0x07FF, None),
+                "W" | "S" | "NesWorkRam" | "NesSaveRam" => (0x6000 + (addr & 0x1FFF) as u16, None),
+                "G" | "NesMemory" => (addr as u16, None),
+                _ => continue,
+            };
+            self.add(Symbol { name: name.to_string(), addr: cpu, prg });
+        }
+    }
+}
+
+// "jogo.nes.3.nl" -> Some(3), "jogo.nes.ram.nl" -> None
+fn nl_bank(path: &str) -> Option<usize> {
+    let name = Path::new(path).file_name()?.to_str()?;
+    name.strip_suffix(".nl")?.rsplit('.').next()?.parse().ok()
+}
+
+// Segmentos do .dbg: id -> (start, posição no arquivo). Sem ooffs = RAM/BSS
+pub fn dbg_segments(text: &str) -> HashMap<String, (u32, Option<usize>)> {
+    let mut segs = HashMap::new();
+    for (kind, fields) in text.lines().filter_map(parse_dbg_record) {
+        if kind != "seg" {
+            continue;
+        }
+        let (Some(id), Some(start)) = (fields.get("id"), fields.get("start").and_then(|v| parse_num(v))) else { continue };
+        let ooffs = fields.get("ooffs").and_then(|v| parse_num(v)).map(|v| v as usize);
+        segs.insert(id.to_string(), (start, ooffs));
+    }
+    segs
+}
+
+// 0x1A, $1A ou decimal
+pub fn parse_num(v: &str) -> Option<u32> {
+    if let Some(hex) = v.strip_prefix("0x").or_else(|| v.strip_prefix('$')) {
+        u32::from_str_radix(hex, 16).ok()
+    } else {
+        v.parse().ok()
+    }
+}
+
+// Uma linha do .dbg do ld65: "tipo<tab>chave=valor,chave=\"texto, com vírgula\",..."
+pub fn parse_dbg_record(line: &str) -> Option<(&str, HashMap<&str, &str>)> {
+    let (kind, rest) = line.split_once(char::is_whitespace)?;
+    let mut fields = HashMap::new();
+    let mut rest = rest.trim();
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once('=')?;
+        let (value, next) = if let Some(quoted) = after.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            (&quoted[..end], quoted[end + 1..].trim_start_matches(','))
+        } else {
+            match after.split_once(',') {
+                Some((v, n)) => (v, n),
+                None => (after, ""),
+            }
+        };
+        fields.insert(key.trim(), value);
+        rest = next.trim();
+    }
+    Some((kind, fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::{test_rom, Cartridge};
+
+    // UxROM com 4 bancos de 16K: $8000 troca, $C000 fica no último
+    fn uxrom_bus() -> Bus {
+        let mut bus = Bus::new();
+        bus.cartridge = Some(Cartridge::from_bytes(&test_rom(4, [0x20, 0, 0], &[])).unwrap());
+        bus
+    }
+
+    const DBG: &str = "version\tmajor=2,minor=0
+seg\tid=0,name=\"BANK0\",start=0x008000,size=0x4000,addrsize=absolute,type=ro,oname=\"t.nes\",ooffs=16
+seg\tid=1,name=\"BANK1\",start=0x008000,size=0x4000,addrsize=absolute,type=ro,oname=\"t.nes\",ooffs=16400
+seg\tid=2,name=\"FIXED\",start=0x00C000,size=0x4000,addrsize=absolute,type=ro,oname=\"t.nes\",ooffs=49168
+seg\tid=3,name=\"BSS\",start=0x000300,size=0x0010,addrsize=absolute,type=rw
+sym\tid=0,name=\"reset\",addrsize=absolute,scope=0,def=1,val=0xC000,seg=2,type=lab
+sym\tid=1,name=\"bank0_func\",addrsize=absolute,scope=0,def=2,val=0x8010,seg=0,type=lab
+sym\tid=2,name=\"bank1_func\",addrsize=absolute,scope=0,def=3,val=0x8010,seg=1,type=lab
+sym\tid=3,name=\"counter\",addrsize=absolute,scope=0,def=4,val=0x0302,seg=3,type=lab
+sym\tid=4,name=\"SPEED\",addrsize=zeropage,scope=0,def=5,val=0x3,type=equ
+";
+
+    #[test]
+    fn ca65_offsets() {
+        let mut syms = SymbolTable::new();
+        syms.parse_ca65(DBG);
+        assert_eq!(syms.len(), 4);
+        assert!(syms.get("SPEED").is_none());
+        // ooffs + (val - start) - header
+        assert_eq!(syms.get("bank0_func").unwrap().prg, Some(0x0010));
+        assert_eq!(syms.get("bank1_func").unwrap().prg, Some(0x4010));
+        assert_eq!(syms.get("reset").unwrap().prg, Some(0xC000));
+        assert_eq!(syms.get("counter").unwrap().prg, None);
+        assert_eq!(syms.prg_label(0x4010), Some("bank1_func"));
+    }
+
+    #[test]
+    fn same_address_in_two_banks() {
+        let mut syms = SymbolTable::new();
+        syms.parse_ca65(DBG);
+        let mut bus = uxrom_bus();
+
+        assert_eq!(bus.prg_offset(0x8010), Some(0x0010));
+        assert_eq!(syms.lookup(&bus, 0x8010), Some("bank0_func"));
+        assert_eq!(syms.lookup(&bus, 0xC000), Some("reset"));
+        assert_eq!(syms.lookup(&bus, 0x0302), Some("counter"));
+        // Banco 1 não está mapeado: fica no endereço em que foi montado
+        assert_eq!(syms.resolve(&bus, "bank1_func"), Some((0x8010, Some(0x4010))));
+
+        bus.cpu_write(0x8000, 1);
+        assert_eq!(bus.prg_offset(0x8010), Some(0x4010));
+        assert_eq!(syms.lookup(&bus, 0x8010), Some("bank1_func"));
+        assert_eq!(syms.describe(&bus, 0x8010), "$8010 (bank1_func)");
+    }
+
+    #[test]
+    fn fceux_banks() {
+        let mut syms = SymbolTable::new();
+        syms.parse_fceux("$8010#bank1_func#chamada pelo NMI\n$C000#reset#\nlixo\n$8020##\n", Some(1));
+        syms.parse_fceux("$0302#counter#\n", None);
+        assert_eq!(syms.len(), 3);
+        assert_eq!(syms.get("bank1_func").unwrap().prg, Some(0x4010));
+        // Banco de 16K: $C000 no .1.nl é o começo do banco 1 também
+        assert_eq!(syms.get("reset").unwrap().prg, Some(0x4000));
+        assert_eq!(syms.get("counter").unwrap().prg, None);
+
+        let mut bus = uxrom_bus();
+        bus.cpu_write(0x8000, 1);
+        assert_eq!(syms.lookup(&bus, 0x8010), Some("bank1_func"));
+        assert_eq!(syms.lookup(&bus, 0x0302), Some("counter"));
+    }
+
+    #[test]
+    fn nl_file_names() {
+        assert_eq!(nl_bank("jogo.nes.3.nl"), Some(3));
+        assert_eq!(nl_bank("roms/v1.2/jogo.nes.12.nl"), Some(12));
+        assert_eq!(nl_bank("jogo.nes.ram.nl"), None);
+        assert_eq!(nl_bank("jogo.mlb"), None);
+    }
+
+    #[test]
+    fn mesen_kinds() {
+        let mut syms = SymbolTable::new();
+        syms.parse_mesen("P:4010:bank1_func:comentario\nNesPrgRom:0010:bank0_func\nR:0302:counter\n\
+            R:0310-0311:pair\nW:0004:save_slot\nG:2000:PPUCTRL\nX:0000:nada\nP:0020:\n");
+        assert_eq!(syms.len(), 6);
+        let sym = |n: &str| { let s = syms.get(n).unwrap(); (s.addr, s.prg) };
+        assert_eq!(sym("bank1_func"), (0xC010, Some(0x4010)));
+        assert_eq!(sym("bank0_func"), (0x8010, Some(0x0010)));
+        assert_eq!(sym("counter"), (0x0302, None));
+        assert_eq!(sym("pair"), (0x0310, None));
+        assert_eq!(sym("save_slot"), (0x6004, None));
+        assert_eq!(sym("PPUCTRL"), (0x2000, None));
+
+        let mut bus = uxrom_bus();
+        assert_eq!(syms.lookup(&bus, 0x8010), Some("bank0_func"));
+        bus.cpu_write(0x8000, 1);
+        assert_eq!(syms.lookup(&bus, 0x8010), Some("bank1_func"));
+    }
+
+    #[test]
+    fn dbg_records() {
+        let (kind, fields) = parse_dbg_record("sym\tid=1,name=\"a, b\",val=0x10,type=lab").unwrap();
+        assert_eq!(kind, "sym");
+        assert_eq!(fields["name"], "a, b");
+        assert_eq!(fields["val"], "0x10");
+        assert_eq!(fields["type"], "lab");
+        assert!(parse_dbg_record("semespaco").is_none());
+        assert_eq!(parse_num("0x1A"), Some(26));
+        assert_eq!(parse_num("$1A"), Some(26));
+        assert_eq!(parse_num("26"), Some(26));
+        assert_eq!(parse_num("zz"), None);
+
+        let segs = dbg_segments(DBG);
+        assert_eq!(segs["1"], (0x8000, Some(16400)));
+        assert_eq!(segs["3"], (0x0300, None));
+    }
+}
@@ -0,0 +1,134 @@
+// Runner de ROMs de teste (blargg e cia.)
+//
+// Protocolo em $6000 (PRG RAM):
+//   $6001-$6003  assinatura DE B0 61 (só vale o status depois que ela aparece)
+//   $6000        status: $80 = rodando, $81 = aperta reset, < $80 = resultado (0 = passou)
+//   $6004...     texto com o resultado, terminado em 0
+//
+// ROMs antigas não usam isso e só escrevem na tela; aí o que sobra é comparar
+// o CRC da tela (ou do nametable) com um valor conhecido.
+use crate::nes::Nes;
+use crate::savestate::crc32;
+
+pub const SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+pub const STATUS_RUNNING: u8 = 0x80;
+pub const STATUS_RESET: u8 = 0x81;
+
+// O protocolo pede pelo menos 100ms entre o pedido e o reset
+const RESET_DELAY_FRAMES: u32 = 6;
+const MAX_TEXT_LEN: u16 = 0x1FFC;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed(u8),     // código de resultado do $6000 (ou 0xFF se foi o CRC que não bateu)
+    Jammed,
+    Timeout,
+}
+
+pub struct TestReport {
+    pub outcome: Outcome,
+    pub message: String,
+    pub frames: u32,
+    pub screen_crc: u32,
+    pub protocol: bool,     // a ROM falou o protocolo do $6000?
+}
+
+impl TestReport {
+    // 0 = passou, 1 = falhou, 2 = não deu pra saber (timeout sem CRC)
+    pub fn exit_code(&self) -> i32 {
+        match self.outcome {
+            Outcome::Passed => 0,
+            Outcome::Failed(_) | Outcome::Jammed => 1,
+            Outcome::Timeout => 2,
+        }
+    }
+}
+
+pub fn has_signature(nes: &Nes) -> bool {
+    (0..3).all(|i| nes.bus.cpu_read_debug(0x6001 + i) == SIGNATURE[i as usize])
+}
+
+pub fn read_text(nes: &Nes) -> String {
+    let mut text = Vec::new();
+    for i in 0..MAX_TEXT_LEN {
+        let b = nes.bus.cpu_read_debug(0x6004 + i);
+        if b == 0 {
+            break;
+        }
+        text.push(b);
+    }
+    String::from_utf8_lossy(&text).trim().to_string()
+}
+
+pub fn screen_crc(nes: &Nes) -> u32 {
+    let data: Vec<u8> = nes.framebuffer().iter().flat_map(|p| p.iter().copied()).collect();
+    crc32(&data)
+}
+
+// Texto do nametable 0 (as ROMs antigas usam tiles = ASCII), só as linhas não vazias
+pub fn nametable_text(nes: &Nes) -> String {
+    let mut lines = Vec::new();
+    for row in 0..30 {
+        let line: String = (0..32)
+            .map(|col| {
+                let b = nes.bus.ppu.nametable[0][row * 32 + col];
+                if (0x20..0x7F).contains(&b) { b as char } else { ' ' }
+            })
+            .collect();
+        let line = line.trim_end();
+        if !line.trim().is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+// Roda até a ROM dar o resultado, travar, bater o CRC esperado ou estourar max_frames.
+// Tem que receber a Nes já com cartucho e resetada.
+pub fn run(nes: &mut Nes, max_frames: u32, expected_crc: Option<u32>) -> TestReport {
+    let mut protocol = false;
+    let mut reset_wait: Option<u32> = None;
+
+    for frame in 1..=max_frames {
+        nes.run_frame();
+
+        if let Some((opcode, pc)) = nes.debugger.jam {
+            return report(nes, Outcome::Jammed, format!("CPU JAM: opcode ${:02X} em ${:04X}", opcode, pc), frame, protocol);
+        }
+
+        if has_signature(nes) {
+            protocol = true;
+            match nes.bus.cpu_read_debug(0x6000) {
+                STATUS_RUNNING => {},
+                STATUS_RESET => {
+                    let waited = reset_wait.get_or_insert(0);
+                    *waited += 1;
+                    if *waited >= RESET_DELAY_FRAMES {
+                        reset_wait = None;
+                        nes.reset();
+                    }
+                },
+                0 => return report(nes, Outcome::Passed, read_text(nes), frame, true),
+                code if code < STATUS_RUNNING => return report(nes, Outcome::Failed(code), read_text(nes), frame, true),
+                _ => {},    // lixo de PRG RAM antes da ROM inicializar
+            }
+        } else if expected_crc.is_some() && expected_crc == Some(screen_crc(nes)) {
+            return report(nes, Outcome::Passed, nametable_text(nes), frame, false);
+        }
+    }
+
+    // Acabou o tempo: sem protocolo, o jeito é olhar a tela
+    let (outcome, message) = if protocol {
+        (Outcome::Timeout, read_text(nes))
+    } else if expected_crc.is_some() {
+        (Outcome::Failed(0xFF), nametable_text(nes))
+    } else {
+        (Outcome::Timeout, nametable_text(nes))
+    };
+    report(nes, outcome, message, max_frames, protocol)
+}
+
+fn report(nes: &Nes, outcome: Outcome, message: String, frames: u32, protocol: bool) -> TestReport {
+    TestReport { outcome, message, frames, screen_crc: screen_crc(nes), protocol }
+}
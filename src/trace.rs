@@ -0,0 +1,466 @@
+// Trace da CPU no formato do nestest.log / Nintendulator (o Mesen exporta igual):
+//
+// C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+// C72F  B1 89    *NOP ($89),Y = 0300 @ 0300 = 89  ...
+//
+// Dá pra comparar linha a linha com o log de outro emulador.
+use std::fmt::Write as _;
+use std::fs::File;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use crate::bus::Bus;
+use crate::cpu6502::{AddrMode, Cpu6502};
+use crate::disasm;
+use crate::symbols::SymbolTable;
+
+// Quando gravar: tudo None = grava sempre
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraceCondition {
+    pub pc_range: Option<(u16, u16)>,   // só instruções com PC dentro (inclusivo)
+    pub start_frame: Option<u64>,       // começa nesse frame
+    pub stop_frame: Option<u64>,        // fecha o arquivo nesse frame
+}
+
+impl TraceCondition {
+    // "C000-C0FF" ou "C000" (um endereço só)
+    pub fn parse_pc_range(s: &str) -> Option<(u16, u16)> {
+        let hex = |v: &str| u16::from_str_radix(v.trim().trim_start_matches('$').trim_start_matches("0x"), 16).ok();
+        match s.split_once('-') {
+            Some((a, b)) => Some((hex(a)?, hex(b)?)),
+            None => hex(s).map(|a| (a, a)),
+        }
+    }
+
+    // "100-200", "100-" ou "-200"
+    pub fn parse_frames(s: &str) -> Option<(Option<u64>, Option<u64>)> {
+        let num = |v: &str| if v.is_empty() { Some(None) } else { v.parse().ok().map(Some) };
+        let (a, b) = s.split_once('-')?;
+        Some((num(a)?, num(b)?))
+    }
+
+    pub fn accepts(&self, pc: u16, frame: u64) -> bool {
+        if self.start_frame.is_some_and(|f| frame < f) {
+            return false;
+        }
+        if self.stop_frame.is_some_and(|f| frame >= f) {
+            return false;
+        }
+        match self.pc_range {
+            Some((lo, hi)) => pc >= lo && pc <= hi,
+            None => true,
+        }
+    }
+}
+
+// Histórico recente em memória: buffer circular de tamanho fixo. As Strings
+// são reaproveitadas, então depois de encher não aloca nem move nada.
+// Não tem lock porque só a thread da emulação mexe nele (é do Debugger); não
+// é um ring concorrente, quem quiser ler de outra thread vai precisar de um
+pub struct TraceRing {
+    lines: Vec<String>,
+    head: usize,    // próxima posição a escrever
+    len: usize,
+}
+
+impl TraceRing {
+    pub fn new(capacity: usize) -> Self {
+        TraceRing {
+            lines: (0..capacity.max(1)).map(|_| String::with_capacity(96)).collect(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    // Devolve o slot pra quem for escrever (já limpo)
+    pub fn next_slot(&mut self) -> &mut String {
+        let cap = self.lines.len();
+        let slot = &mut self.lines[self.head];
+        self.head = (self.head + 1) % cap;
+        self.len = (self.len + 1).min(cap);
+        slot.clear();
+        slot
+    }
+
+    // Do mais antigo pro mais novo
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        let cap = self.lines.len();
+        let start = (self.head + cap - self.len) % cap;
+        (0..self.len).map(move |i| self.lines[(start + i) % cap].as_str())
+    }
+
+    // As últimas n linhas, em ordem
+    pub fn recent(&self, n: usize) -> impl Iterator<Item = &str> {
+        self.iter().skip(self.len.saturating_sub(n))
+    }
+}
+
+pub struct TraceFile {
+    out: BufWriter<File>,
+    pub path: String,
+    pub lines: u64,
+}
+
+impl TraceFile {
+    pub fn create(path: &str) -> io::Result<Self> {
+        Ok(TraceFile { out: BufWriter::new(File::create(path)?), path: path.to_string(), lines: 0 })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.lines += 1;
+        self.out.write_all(line.as_bytes())?;
+        self.out.write_all(b"\n")
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// Linha no formato Nintendulator pro estado atual (antes de executar a instrução em PC)
+// Com símbolos carregados o operando sai com o label no lugar do endereço
+pub fn format_line(cpu: &Cpu6502, bus: &Bus, syms: &SymbolTable, out: &mut String) {
+    let d = disasm::decode_with_cpu(cpu, bus, cpu.pc);
+    let star = if d.official { ' ' } else { '*' };
+
+    let mut text = String::with_capacity(32);
+    let _ = write!(text, "{} {}", d.name, d.symbolic_operand(bus, syms));
+    annotate(&d, cpu, bus, &mut text);
+
+    // PPU: a pré-render aparece como 261 no Nintendulator (-1 aqui)
+    let scanline = if bus.ppu.scanline < 0 { 261 } else { bus.ppu.scanline };
+    let _ = write!(
+        out,
+        "{:04X}  {:<8} {}{:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+        d.addr, d.bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" "),
+        star, text.trim_end(),
+        cpu.a, cpu.x, cpu.y, cpu.status, cpu.stkp,
+        scanline, bus.ppu.cycle, cpu.total_cycles
+    );
+}
+
+// Os "= valor" e "@ endereço" que o nestest.log põe depois do operando
+fn annotate(d: &disasm::Disasm, cpu: &Cpu6502, bus: &Bus, text: &mut String) {
+    let lo = d.bytes.get(1).copied().unwrap_or(0);
+    let jump = matches!(d.name, "JMP" | "JSR");
+    let value = |addr: u16| bus.cpu_read_debug(addr);
+    match (d.mode, d.effective) {
+        (AddrMode::Zp0, Some(ea)) | (AddrMode::Abs, Some(ea)) if !jump => {
+            let _ = write!(text, " = {:02X}", value(ea));
+        },
+        (AddrMode::Zpx, Some(ea)) | (AddrMode::Zpy, Some(ea)) => {
+            let _ = write!(text, " @ {:02X} = {:02X}", ea, value(ea));
+        },
+        (AddrMode::Abx, Some(ea)) | (AddrMode::Aby, Some(ea)) => {
+            let _ = write!(text, " @ {:04X} = {:02X}", ea, value(ea));
+        },
+        (AddrMode::Izx, Some(ea)) => {
+            // ($xx,X) @ ponteiro = endereço = valor
+            let ptr = lo.wrapping_add(cpu.x);
+            let _ = write!(text, " @ {:02X} = {:04X} = {:02X}", ptr, ea, value(ea));
+        },
+        (AddrMode::Izy, Some(ea)) => {
+            // ($xx),Y = base @ endereço = valor
+            let base = ea.wrapping_sub(cpu.y as u16);
+            let _ = write!(text, " = {:04X} @ {:04X} = {:02X}", base, ea, value(ea));
+        },
+        (AddrMode::Ind, _) => {
+            if let Some(t) = d.target {
+                let _ = write!(text, " = {:04X}", t);
+            }
+        },
+        _ => {},
+    }
+}
+
+// Uma linha de trace já separada em campos. Os campos que o log de referência
+// não tem ficam None e não entram na comparação
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TraceRecord {
+    pub pc: u16,
+    pub a: Option<u8>,
+    pub x: Option<u8>,
+    pub y: Option<u8>,
+    pub p: Option<u8>,
+    pub sp: Option<u8>,
+    pub scanline: Option<i16>,
+    pub dot: Option<i16>,
+    pub cycles: Option<u64>,
+}
+
+impl TraceRecord {
+    pub fn from_cpu(cpu: &Cpu6502, bus: &Bus) -> Self {
+        TraceRecord {
+            pc: cpu.pc,
+            a: Some(cpu.a),
+            x: Some(cpu.x),
+            y: Some(cpu.y),
+            p: Some(cpu.status),
+            sp: Some(cpu.stkp),
+            scanline: Some(if bus.ppu.scanline < 0 { 261 } else { bus.ppu.scanline }),
+            dot: Some(bus.ppu.cycle),
+            cycles: Some(cpu.total_cycles),
+        }
+    }
+
+    // Entende o formato do nestest/Nintendulator e o trace padrão do Mesen:
+    //   C000  4C F5 C5  JMP $C5F5   A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+    //   C000  $4C $F5 $C5  JMP $C5F5   A:00 X:00 Y:00 S:FD P:nvUbdIzc V:0 H:21 Cycle:7
+    pub fn parse(line: &str) -> Option<Self> {
+        let pc = u16::from_str_radix(line.get(..4)?, 16).ok()?;
+        let mut rec = TraceRecord { pc, ..Default::default() };
+
+        // Registradores vêm depois do disassembly; "A:" só aparece a partir dali
+        let regs = &line[line.find(" A:")?..];
+        let mut tokens = regs.split_whitespace().peekable();
+        while let Some(tok) = tokens.next() {
+            let Some((key, val)) = tok.split_once(':') else { continue };
+            let hex8 = |v: &str| u8::from_str_radix(v, 16).ok();
+            match key {
+                "A" => rec.a = hex8(val),
+                "X" => rec.x = hex8(val),
+                "Y" => rec.y = hex8(val),
+                "SP" | "S" => rec.sp = hex8(val),
+                "P" => rec.p = hex8(val).or_else(|| parse_flags(val)),
+                "PPU" => {
+                    // "PPU:  0, 21" vem quebrado em tokens: "PPU:", "0,", "21"
+                    let mut pos = val.to_string();
+                    while !pos.contains(',') || pos.ends_with(',') {
+                        match tokens.next() {
+                            Some(t) => pos.push_str(t),
+                            None => break,
+                        }
+                    }
+                    if let Some((sl, dot)) = pos.split_once(',') {
+                        rec.scanline = sl.trim().parse().ok();
+                        rec.dot = dot.trim().parse().ok();
+                    }
+                },
+                "V" => rec.scanline = val.parse().ok(),
+                "H" => rec.dot = val.parse().ok(),
+                "CYC" | "Cycle" => rec.cycles = val.parse().ok(),
+                _ => {},
+            }
+        }
+        Some(rec)
+    }
+}
+
+// "nvUbdIzc": maiúscula = setado
+fn parse_flags(s: &str) -> Option<u8> {
+    if s.len() != 8 {
+        return None;
+    }
+    let mut p = 0u8;
+    for (i, c) in s.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            p |= 0x80 >> i;
+        }
+    }
+    Some(p)
+}
+
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub expected: String,
+    pub got: String,
+}
+
+// Compara só o que a referência tem. ignore: nomes de campo ("p", "ppu", "cyc"...)
+pub fn compare(expected: &TraceRecord, got: &TraceRecord, ignore: &[String]) -> Vec<FieldDiff> {
+    let mut diffs = Vec::new();
+    let skip = |f: &str| ignore.iter().any(|i| i == f);
+    let mut check = |field: &'static str, e: Option<String>, g: Option<String>| {
+        if let (Some(e), Some(g)) = (e, g) && e != g && !skip(field) {
+            diffs.push(FieldDiff { field, expected: e, got: g });
+        }
+    };
+    let h8 = |v: Option<u8>| v.map(|v| format!("{:02X}", v));
+    check("pc", Some(format!("{:04X}", expected.pc)), Some(format!("{:04X}", got.pc)));
+    check("a", h8(expected.a), h8(got.a));
+    check("x", h8(expected.x), h8(got.x));
+    check("y", h8(expected.y), h8(got.y));
+    check("p", expected.p.map(flags_str), got.p.map(flags_str));
+    check("sp", h8(expected.sp), h8(got.sp));
+    if !skip("ppu") {
+        check("scanline", expected.scanline.map(|v| v.to_string()), got.scanline.map(|v| v.to_string()));
+        check("dot", expected.dot.map(|v| v.to_string()), got.dot.map(|v| v.to_string()));
+    }
+    check("cyc", expected.cycles.map(|v| v.to_string()), got.cycles.map(|v| v.to_string()));
+    diffs
+}
+
+// "24 (nvUbdIzc)" - mais fácil de ver qual flag mudou
+fn flags_str(p: u8) -> String {
+    let names = "NVUBDIZC";
+    let flags: String = names.chars().enumerate()
+        .map(|(i, c)| if p & (0x80 >> i) != 0 { c } else { c.to_ascii_lowercase() })
+        .collect();
+    format!("{:02X} ({})", p, flags)
+}
+
+pub struct TraceMismatch {
+    pub line: usize,                // linha do log de referência (começa em 1)
+    pub expected: String,
+    pub got: String,
+    pub fields: Vec<FieldDiff>,
+    pub before: Vec<String>,        // linhas da referência antes da diferença
+    pub after: Vec<String>,         // e depois
+}
+
+// Compara cada instrução executada com a próxima linha do log de referência
+pub struct TraceDiff {
+    reference: io::Lines<BufReader<File>>,
+    pub path: String,
+    pub ignore: Vec<String>,
+    pub context: usize,
+    pub line: usize,
+    recent: VecDeque<String>,
+    pub mismatch: Option<TraceMismatch>,
+    pub exhausted: bool,            // a referência acabou sem diferença
+}
+
+impl TraceDiff {
+    pub fn open(path: &str, ignore: Vec<String>, context: usize) -> io::Result<Self> {
+        Ok(TraceDiff {
+            reference: BufReader::new(File::open(path)?).lines(),
+            path: path.to_string(),
+            ignore,
+            context,
+            line: 0,
+            recent: VecDeque::new(),
+            mismatch: None,
+            exhausted: false,
+        })
+    }
+
+    pub fn finished(&self) -> bool {
+        self.mismatch.is_some() || self.exhausted
+    }
+
+    // Próxima linha que dá pra parsear (pula vazias/comentários)
+    fn next_record(&mut self) -> Option<(String, TraceRecord)> {
+        loop {
+            let line = self.reference.next()?.ok()?;
+            self.line += 1;
+            if let Some(rec) = TraceRecord::parse(&line) {
+                return Some((line, rec));
+            }
+        }
+    }
+
+    // got_line é a nossa linha já formatada (só pro relatório)
+    pub fn check(&mut self, got: &TraceRecord, got_line: &str) {
+        if self.finished() {
+            return;
+        }
+        let Some((line, expected)) = self.next_record() else {
+            self.exhausted = true;
+            return;
+        };
+
+        let fields = compare(&expected, got, &self.ignore);
+        if fields.is_empty() {
+            self.recent.push_back(line);
+            if self.recent.len() > self.context {
+                self.recent.pop_front();
+            }
+            return;
+        }
+
+        let mismatch_line = self.line;
+        let mut after = Vec::new();
+        while after.len() < self.context {
+            match self.next_record() {
+                Some((l, _)) => after.push(l),
+                None => break,
+            }
+        }
+        self.mismatch = Some(TraceMismatch {
+            line: mismatch_line,
+            expected: line,
+            got: got_line.to_string(),
+            fields,
+            before: self.recent.drain(..).collect(),
+            after,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::test_rom;
+    use crate::nes::Nes;
+
+    // NROM com: LDA #$80 / STA $2000 (liga NMI) e um tobogã de NOPs até $FF00.
+    // O NMI em $FF10 desliga o NMI de novo e volta com RTI
+    fn nmi_rom() -> Vec<u8> {
+        test_rom(1, [0; 3], &[
+            (0xC000, &[0xA9, 0x80, 0x8D, 0x00, 0x20]),
+            (0xFF00, &[0x4C, 0x00, 0xFF]),
+            (0xFF10, &[0xA9, 0x00, 0x8D, 0x00, 0x20, 0x40]),
+            (0xFFFA, &[0x10, 0xFF, 0x00, 0xC0, 0x00, 0xFF]),
+        ])
+    }
+
+    #[test]
+    fn diff_has_no_extra_line_at_nmi() {
+        let rom = nmi_rom();
+
+        // Onde o NMI caiu sai da pilha, não do trace: é o retorno que ele empilhou
+        let mut nes = Nes::new();
+        nes.load_rom(&rom).unwrap();
+        for _ in 0..3 {
+            nes.run_frame();
+        }
+        let ret = u16::from_le_bytes([nes.bus.cpu_read_debug(0x01FC), nes.bus.cpu_read_debug(0x01FD)]);
+        assert!((0xC006..0xFF00).contains(&ret), "NMI não caiu no tobogã: ${:04X}", ret);
+
+        // Referência escrita à mão no formato do Nintendulator, sem PPU/CYC
+        let line = |pc: u16, a: u8, p: u8, sp: u8| format!("{:04X}  A:{:02X} X:00 Y:00 P:{:02X} SP:{:02X}\n", pc, a, p, sp);
+        let mut reference = line(0xC000, 0x00, 0x24, 0xFD);
+        reference += &line(0xC002, 0x80, 0xA4, 0xFD);
+        for pc in 0xC005..ret {
+            reference += &line(pc, 0x80, 0xA4, 0xFD);
+        }
+        reference += &line(0xFF10, 0x80, 0xA4, 0xFA);
+        reference += &line(0xFF12, 0x00, 0x26, 0xFA);
+        reference += &line(0xFF15, 0x00, 0x26, 0xFA);
+        for pc in ret..ret + 4 {
+            reference += &line(pc, 0x00, 0xA4, 0xFD);
+        }
+        let path = std::env::temp_dir().join(format!("rnfe-nmi-{}.log", std::process::id()));
+        std::fs::write(&path, reference).unwrap();
+
+        let mut nes = Nes::new();
+        nes.load_rom(&rom).unwrap();
+        nes.debugger.trace_diff = Some(TraceDiff::open(path.to_str().unwrap(), Vec::new(), 2).unwrap());
+        for _ in 0..3 {
+            nes.run_frame();
+        }
+        let diff = nes.debugger.trace_diff.take().unwrap();
+        let _ = std::fs::remove_file(&path);
+        if let Some(m) = diff.mismatch {
+            panic!("linha {}: esperado {:?}, obtido {:?}", m.line, m.expected, m.got);
+        }
+        assert!(diff.exhausted);
+    }
+}
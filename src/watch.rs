@@ -0,0 +1,237 @@
+// Watchpoints: disparam no acesso de verdade (Bus::cpu_read/cpu_write e
+// $2007 pra memória da PPU), não comparando valor entre instruções.
+//
+// O Bus só anota os hits; quem sabe o PC da instrução é o Debugger, que
+// recolhe os hits a cada ciclo (Nes::clock), testa a condição e decide se
+// loga ou para. A condição vê os registradores no meio da instrução.
+use crate::expr::{split_condition, Condition};
+
+// Tipos de acesso (dá pra combinar)
+pub const WATCH_READ: u8 = 1;
+pub const WATCH_WRITE: u8 = 2;
+pub const WATCH_EXEC: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchSpace {
+    Cpu,
+    Ppu,    // só acessos via $2007 (a renderização não conta)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchpoint {
+    pub space: WatchSpace,
+    pub start: u16,
+    pub end: u16,       // inclusivo
+    pub kinds: u8,      // WATCH_READ | WATCH_WRITE | WATCH_EXEC
+    pub brk: bool,      // false = só loga
+    pub cond: Option<Condition>,
+    pub hits: u64,      // acessos que bateram no endereço (antes da condição)
+    pub gdb: bool,      // posto pelo stub do GDB (ver gdb.rs)
+}
+
+impl Watchpoint {
+    // [cpu:|ppu:]ADDR[-END][:rwx][:log][ if COND]
+    // ex: "0300-030F:w", "ppu:23C0-23FF:w:log", "0300:w if value == 0 && scanline < 20"
+    // Sem tipo = escrita; sem :log = para
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (s, cond) = split_condition(spec)?;
+        let mut parts = s.split(':').peekable();
+        let space = match parts.peek().map(|p| p.to_ascii_lowercase()) {
+            Some(ref p) if p == "cpu" => { parts.next(); WatchSpace::Cpu },
+            Some(ref p) if p == "ppu" => { parts.next(); WatchSpace::Ppu },
+            _ => WatchSpace::Cpu,
+        };
+
+        let hex = |v: &str| u16::from_str_radix(v.trim().trim_start_matches('$'), 16)
+            .map_err(|_| format!("endereco invalido: {}", v));
+        let range = parts.next().ok_or("falta o endereco")?;
+        let (start, end) = match range.split_once('-') {
+            Some((a, b)) => (hex(a)?, hex(b)?),
+            None => (hex(range)?, hex(range)?),
+        };
+        if end < start {
+            return Err(format!("faixa invertida: {}", range));
+        }
+
+        let mut kinds = 0;
+        let mut brk = true;
+        for p in parts {
+            if p.eq_ignore_ascii_case("log") {
+                brk = false;
+                continue;
+            }
+            for c in p.chars() {
+                kinds |= match c.to_ascii_lowercase() {
+                    'r' => WATCH_READ,
+                    'w' => WATCH_WRITE,
+                    'x' => WATCH_EXEC,
+                    _ => return Err(format!("tipo de acesso invalido: {} (use r, w, x)", p)),
+                };
+            }
+        }
+        if kinds == 0 {
+            kinds = WATCH_WRITE;
+        }
+        // Execução só faz sentido no espaço da CPU
+        if space == WatchSpace::Ppu && kinds & WATCH_EXEC != 0 {
+            return Err("watchpoint de execucao so no espaco da CPU".to_string());
+        }
+        Ok(Watchpoint { space, start, end, kinds, brk, cond, hits: 0, gdb: false })
+    }
+
+    pub fn matches(&self, space: WatchSpace, kind: u8, addr: u16) -> bool {
+        self.space == space && self.kinds & kind != 0 && addr >= self.start && addr <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WatchHit {
+    pub index: usize,   // qual watchpoint (WatchUnit::points)
+    pub space: WatchSpace,
+    pub kind: u8,
+    pub addr: u16,
+    pub value: u8,
+    pub brk: bool,
+}
+
+impl WatchHit {
+    pub fn describe(&self, pc: u16) -> String {
+        let kind = match self.kind {
+            WATCH_READ => "R",
+            WATCH_WRITE => "W",
+            _ => "X",
+        };
+        let space = if self.space == WatchSpace::Ppu { "PPU " } else { "" };
+        format!("{} {}${:04X} = {:02X} (PC ${:04X})", kind, space, self.addr, self.value, pc)
+    }
+}
+
+#[derive(Default)]
+pub struct WatchUnit {
+    pub points: Vec<Watchpoint>,
+    pub hits: Vec<WatchHit>,    // desde o último drain
+    kinds: [u8; 2],             // união dos tipos por espaço, pra sair rápido
+}
+
+impl WatchUnit {
+    pub fn new() -> Self {
+        WatchUnit { points: Vec::new(), hits: Vec::new(), kinds: [0; 2] }
+    }
+
+    pub fn add(&mut self, wp: Watchpoint) {
+        self.points.push(wp);
+        self.update();
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Watchpoint> {
+        if index >= self.points.len() {
+            return None;
+        }
+        let wp = self.points.remove(index);
+        self.update();
+        Some(wp)
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.update();
+    }
+
+    fn update(&mut self) {
+        self.kinds = [0; 2];
+        for wp in &self.points {
+            self.kinds[wp.space as usize] |= wp.kinds;
+        }
+    }
+
+    #[inline]
+    pub fn wants(&self, space: WatchSpace, kind: u8) -> bool {
+        self.kinds[space as usize] & kind != 0
+    }
+
+    #[inline]
+    pub fn check(&mut self, space: WatchSpace, kind: u8, addr: u16, value: u8) {
+        if !self.wants(space, kind) {
+            return;
+        }
+        // Um hit por watchpoint: cada um tem a sua condição e contagem
+        for (index, wp) in self.points.iter().enumerate() {
+            if wp.matches(space, kind, addr) {
+                self.hits.push(WatchHit { index, space, kind, addr, value, brk: wp.brk });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::test_rom;
+    use crate::nes::Nes;
+
+    // Escreve $0300/$0310, lê $0301, passa num NOP, e lê $2007 uma vez fora
+    // da paleta ($2000 = $11, vem do buffer) e uma dentro ($3F00 = $0D)
+    const PROGRAM: &[u8] = &[
+        0xA9, 0x42, 0x8D, 0x00, 0x03, 0x8D, 0x10, 0x03,     // C000 LDA #$42 / STA $0300 / STA $0310
+        0xAE, 0x01, 0x03, 0xEA,                             // C008 LDX $0301 / C00B NOP
+        0xA9, 0x20, 0x8D, 0x06, 0x20, 0xA9, 0x00, 0x8D, 0x06, 0x20, 0xA9, 0x11, 0x8D, 0x07, 0x20,   // C00C $2000 = $11
+        0xA9, 0x3F, 0x8D, 0x06, 0x20, 0xA9, 0x00, 0x8D, 0x06, 0x20, 0xA9, 0x0D, 0x8D, 0x07, 0x20,   // C01B $3F00 = $0D
+        0xA9, 0x20, 0x8D, 0x06, 0x20, 0xA9, 0x00, 0x8D, 0x06, 0x20, 0xAD, 0x07, 0x20,               // C02A LDA $2007 ($2000)
+        0xA9, 0x3F, 0x8D, 0x06, 0x20, 0xA9, 0x00, 0x8D, 0x06, 0x20, 0xAD, 0x07, 0x20,               // C037 LDA $2007 ($3F00)
+        0x4C, 0x44, 0xC0,                                   // C044 JMP $C044
+    ];
+
+    fn nes_with(specs: &[&str]) -> Nes {
+        let mut nes = Nes::new();
+        nes.load_rom(&test_rom(1, [0; 3], &[(0xC000, PROGRAM)])).unwrap();
+        for spec in specs {
+            nes.bus.watch.add(Watchpoint::parse(spec).unwrap());
+        }
+        nes
+    }
+
+    #[test]
+    fn parse_and_ranges() {
+        let wp = Watchpoint::parse("ppu:23C0-23FF:rw:log").unwrap();
+        assert_eq!((wp.space, wp.start, wp.end, wp.kinds, wp.brk), (WatchSpace::Ppu, 0x23C0, 0x23FF, WATCH_READ | WATCH_WRITE, false));
+        assert!(wp.matches(WatchSpace::Ppu, WATCH_WRITE, 0x23C0));
+        assert!(wp.matches(WatchSpace::Ppu, WATCH_READ, 0x23FF));
+        assert!(!wp.matches(WatchSpace::Ppu, WATCH_READ, 0x2400));
+        assert!(!wp.matches(WatchSpace::Cpu, WATCH_READ, 0x23C0));
+
+        let wp = Watchpoint::parse("$0300").unwrap();
+        assert_eq!((wp.space, wp.start, wp.end, wp.kinds, wp.brk), (WatchSpace::Cpu, 0x0300, 0x0300, WATCH_WRITE, true));
+        assert!(Watchpoint::parse("0310-0300").is_err());
+        assert!(Watchpoint::parse("ppu:2000:x").is_err());
+    }
+
+    #[test]
+    fn hits_carry_pc_and_value() {
+        let mut nes = nes_with(&["0300-030F:w:log", "0301:r:log", "C00B:x:log", "ppu:2000:r:log", "ppu:3F00-3F1F:r:log"]);
+        assert!(nes.run_frame());
+
+        let log: Vec<_> = nes.debugger.watch_log.iter().cloned().collect();
+        assert_eq!(log, vec![
+            (0xC002, "W $0300 = 42 (PC $C002) #1".to_string()),
+            (0xC008, "R $0301 = 00 (PC $C008) #1".to_string()),
+            (0xC00B, "X $C00B = EA (PC $C00B) #1".to_string()),
+            (0xC034, "R PPU $2000 = 11 (PC $C034) #1".to_string()),
+            (0xC041, "R PPU $3F00 = 0D (PC $C041) #1".to_string()),
+        ]);
+    }
+
+    // Sem :log para na instrução seguinte ao acesso; com :log o frame vai até o fim
+    #[test]
+    fn log_vs_break() {
+        let mut nes = nes_with(&["0300:w:log"]);
+        assert!(nes.run_frame());
+        assert!(!nes.debugger.hit_breakpoint);
+
+        let mut nes = nes_with(&["0300:w"]);
+        assert!(!nes.run_frame());
+        assert!(nes.debugger.hit_breakpoint);
+        assert_eq!(nes.cpu.pc, 0xC005);
+        assert!(nes.debugger.break_reason.starts_with("Watch W $0300 = 42"), "{}", nes.debugger.break_reason);
+        assert_eq!(nes.bus.watch.points[0].hits, 1);
+    }
+}